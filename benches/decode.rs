@@ -0,0 +1,80 @@
+//! Decode and upload preparation timings, run with `cargo bench`.
+//! The JPEG comes from `test_images`, the PNG and GIF are generated into the temp folder on every run, so they always match the code below.
+use criterion::{criterion_group, criterion_main, Criterion};
+use image::codecs::gif::GifEncoder;
+use image::{Delay, Frame, RgbaImage};
+use luminix::image_loader::{load_image, LoadedImage};
+use luminix::wgpu_renderer::{premultiply_to_bgra, AlphaMode};
+use std::fs::File;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+const JPEG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_images/mountain_landscape_4-wallpaper-7680x4320.jpg");
+const PNG_SIZE: u32 = 4096;
+const GIF_SIZE: u32 = 512;
+const GIF_FRAMES: u32 = 30;
+
+/// Gradient with a falling alpha ramp, so every pixel goes through the premultiply math
+fn alpha_image(size: u32, shift: u32) -> RgbaImage {
+    RgbaImage::from_fn(size, size, |x, y| {
+        image::Rgba([(x + shift) as u8, (y + shift) as u8, ((x ^ y) + shift) as u8, (x * 255 / size) as u8])
+    })
+}
+
+/// Generated image file, deleted again once the benchmarks using it are done
+struct Fixture(PathBuf);
+
+impl Fixture {
+    fn new(name: &str, write: impl FnOnce(&Path)) -> Self {
+        let path = std::env::temp_dir().join(format!("luminix-bench-{}-{name}", std::process::id()));
+        write(&path);
+        Self(path)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn png_fixture() -> Fixture {
+    Fixture::new("alpha.png", |path| alpha_image(PNG_SIZE, 0).save(path).unwrap())
+}
+
+fn gif_fixture() -> Fixture {
+    Fixture::new("animation.gif", |path| {
+        let mut encoder = GifEncoder::new(File::create(path).unwrap());
+        let frames = (0..GIF_FRAMES).map(|i| Frame::from_parts(alpha_image(GIF_SIZE, i * 8), 0, 0, Delay::from_numer_denom_ms(40, 1)));
+        encoder.encode_frames(frames).unwrap();
+    })
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.sample_size(10);
+    group.bench_function("jpeg 7680x4320", |b| b.iter(|| load_image(black_box(Path::new(JPEG_PATH))).unwrap()));
+    let png = png_fixture();
+    group.bench_function("png 4096x4096 with alpha", |b| b.iter(|| load_image(black_box(&png.0)).unwrap()));
+    let gif = gif_fixture();
+    group.bench_function("gif 30 frames 512x512", |b| b.iter(|| load_image(black_box(&gif.0)).unwrap()));
+    group.finish();
+}
+
+fn premultiply(c: &mut Criterion) {
+    let LoadedImage::Static(jpeg) = load_image(Path::new(JPEG_PATH)).unwrap() else { unreachable!("jpegs are still images") };
+    let LoadedImage::Static(png) = load_image(&png_fixture().0).unwrap() else { unreachable!("pngs are still images") };
+    // one translucent pixel is enough to send the whole image through the multiplication
+    let mut nearly_opaque = jpeg.rgba_data.clone();
+    *nearly_opaque.last_mut().unwrap() = u8::MAX - 1;
+    let mut group = c.benchmark_group("premultiply");
+    group.sample_size(20);
+    group.bench_function("jpeg 7680x4320", |b| b.iter(|| premultiply_to_bgra(black_box(&jpeg.rgba_data), AlphaMode::Straight)));
+    group.bench_function("png 4096x4096 with alpha", |b| b.iter(|| premultiply_to_bgra(black_box(&png.rgba_data), AlphaMode::Straight)));
+    group.bench_function("jpeg 7680x4320 without the opaque shortcut", |b| b.iter(|| premultiply_to_bgra(black_box(&nearly_opaque), AlphaMode::Straight)));
+    group.bench_function("png 4096x4096 already premultiplied", |b| b.iter(|| premultiply_to_bgra(black_box(&png.rgba_data), AlphaMode::Premultiplied)));
+    group.finish();
+}
+
+criterion_group!(benches, decode, premultiply);
+criterion_main!(benches);
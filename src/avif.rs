@@ -0,0 +1,305 @@
+//! Animated AVIF. The `image` crate only decodes the still image of an AVIF, so image sequences are read here:
+//! mp4parse finds the samples of the color track and its alpha track, dav1d decodes them one after another
+
+use crate::errors::LoadImageError;
+use dav1d::pixel::{MatrixCoefficients, YUVRange};
+use dav1d::{Picture, PixelLayout, PlanarImageComponent};
+use image::error::{DecodingError, ImageFormatHint};
+use image::metadata::LoopCount;
+use image::{AnimationDecoder, Delay, Frame, Frames, ImageError, ImageFormat, ImageResult, Rgba, RgbaImage};
+use mp4parse::unstable::create_sample_table;
+use mp4parse::{ParseStrictness, SampleEntry, Track, TrackType};
+use std::fs;
+use std::num::NonZeroU32;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// The frames of an AVIF image sequence, decoded as they're iterated
+pub struct AvifSequenceDecoder {
+    data: Vec<u8>,
+    color: TrackDecoder,
+    /// The auxiliary track holding the alpha channel, if the sequence has one
+    alpha: Option<TrackDecoder>,
+    dimensions: (u32, u32),
+    /// Whether the edit list asks for the track to repeat, `None` for files without one
+    looped: Option<bool>,
+}
+
+struct TrackDecoder {
+    decoder: dav1d::Decoder,
+    samples: Vec<Sample>,
+}
+
+struct Sample {
+    /// Byte range of the sample's AV1 data in the file
+    range: Range<usize>,
+    duration: Duration,
+}
+
+impl AvifSequenceDecoder {
+    /// Reads the tracks of an AVIF, `None` for files that only hold a still image
+    pub fn new(image_path: &Path) -> Result<Option<Self>, LoadImageError> {
+        let data = fs::read(image_path)?;
+        let context = mp4parse::read_avif(&mut data.as_slice(), ParseStrictness::Normal)
+            .map_err(|e| LoadImageError::Avif(e.to_string()))?;
+        let Some(sequence) = context.sequence else {
+            return Ok(None);
+        };
+        let Some(color_track) = sequence.tracks.iter().find(|track| matches!(track.track_type, TrackType::Picture | TrackType::Video)) else {
+            return Ok(None);
+        };
+        let alpha_track = color_track.track_id.and_then(|color_id| sequence.tracks.iter().find(|track| {
+            track.track_type == TrackType::AuxiliaryVideo && track.tref.as_ref().is_some_and(|tref| tref.has_auxl_reference(color_id))
+        }));
+        let dimensions = color_track.stsd.as_ref()
+            .and_then(|stsd| stsd.descriptions.iter().find_map(|entry| match entry {
+                SampleEntry::Video(video) => Some((u32::from(video.width), u32::from(video.height))),
+                _ => None,
+            }))
+            .ok_or_else(|| LoadImageError::Avif("the image sequence has no size".to_string()))?;
+        let color = TrackDecoder::new(color_track, data.len())?;
+        if color.samples.is_empty() {
+            return Err(LoadImageError::NoFrames);
+        }
+        Ok(Some(AvifSequenceDecoder {
+            color,
+            alpha: alpha_track.map(|track| TrackDecoder::new(track, data.len())).transpose()?,
+            dimensions,
+            looped: color_track.looped,
+            data,
+        }))
+    }
+
+    /// Size of the canvas the frames are shown on, from the sample description
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn decode_frame(&mut self, index: usize) -> ImageResult<Frame> {
+        let sample = &self.color.samples[index];
+        let picture = decode_sample(&mut self.color.decoder, &self.data[sample.range.clone()])?;
+        let mut buffer = to_rgba(&picture)?;
+        // an alpha track shorter than the color track leaves the remaining frames opaque
+        if let Some(alpha) = self.alpha.as_mut().filter(|alpha| index < alpha.samples.len()) {
+            let alpha_picture = decode_sample(&mut alpha.decoder, &self.data[alpha.samples[index].range.clone()])?;
+            if (alpha_picture.width(), alpha_picture.height()) != buffer.dimensions() {
+                return Err(decoding_error("the alpha track doesn't match the size of the color track"));
+            }
+            let alpha_plane = PlaneReader::new(&alpha_picture, PlanarImageComponent::Y);
+            // alpha is always full range whatever the sequence header says, as in libavif
+            let max = ((1 << alpha_picture.bit_depth()) - 1) as f32;
+            for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+                pixel[3] = to_u8(f32::from(alpha_plane.get(x, y)) / max);
+            }
+        }
+        Ok(Frame::from_parts(buffer, 0, 0, Delay::from_saturating_duration(sample.duration)))
+    }
+}
+
+impl<'a> AnimationDecoder<'a> for AvifSequenceDecoder {
+    fn into_frames(mut self) -> Frames<'a> {
+        Frames::new(Box::new((0..self.color.samples.len()).map(move |index| self.decode_frame(index))))
+    }
+
+    fn loop_count(&self) -> LoopCount {
+        // an edit list without the repeat flag plays the track once, files without one loop forever like browsers do
+        match self.looped {
+            Some(false) => LoopCount::Finite(NonZeroU32::MIN),
+            _ => LoopCount::Infinite,
+        }
+    }
+}
+
+impl TrackDecoder {
+    /// Locates the samples of `track`, which have to lie within the file's `file_length` bytes
+    fn new(track: &Track, file_length: usize) -> Result<Self, LoadImageError> {
+        let error = |message: &str| LoadImageError::Avif(format!("track {}: {message}", track.id));
+        let timescale = track.timescale.as_ref().map(|timescale| timescale.0).filter(|&timescale| timescale > 0)
+            .ok_or_else(|| error("no timescale"))?;
+        let indices = create_sample_table(track, 0.into()).ok_or_else(|| error("no sample table"))?;
+        let samples = indices.iter().map(|indice| {
+            let start = usize::try_from(indice.start_offset.0).ok();
+            let end = usize::try_from(indice.end_offset.0).ok();
+            let (Some(start), Some(end)) = (start, end) else {
+                return Err(error("sample offset out of range"));
+            };
+            if start > end || end > file_length {
+                return Err(error("sample past the end of the file"));
+            }
+            let ticks = u64::try_from(indice.end_composition.0 - indice.start_composition.0).unwrap_or(0);
+            Ok(Sample {
+                range: start..end,
+                duration: Duration::from_secs_f64(ticks as f64 / timescale as f64),
+            })
+        }).collect::<Result<Vec<_>, _>>()?;
+        let mut settings = dav1d::Settings::new();
+        // one picture out for every sample in, so frames can be read right after sending them
+        settings.set_max_frame_delay(1);
+        let decoder = dav1d::Decoder::with_settings(&settings).map_err(|e| error(&e.to_string()))?;
+        Ok(TrackDecoder { decoder, samples })
+    }
+}
+
+/// Sends one sample to the decoder and waits for its picture
+fn decode_sample(decoder: &mut dav1d::Decoder, sample: &[u8]) -> ImageResult<Picture> {
+    // whether part of the sample is still waiting to go in, `Ok` means the decoder took all of it
+    let mut pending = match decoder.send_data(sample.to_vec(), None, None, None) {
+        Ok(()) => false,
+        Err(dav1d::Error::Again) => true,
+        Err(e) => return Err(decoding_error(e)),
+    };
+    loop {
+        match decoder.get_picture() {
+            // with the whole sample sent there's nothing left to wait for, a sample that shows no frame is broken here
+            Err(dav1d::Error::Again) if !pending => return Err(decoding_error("the sample holds no picture")),
+            // the decoder wants the rest of the sample first
+            Err(dav1d::Error::Again) => pending = match decoder.send_pending_data() {
+                Ok(()) => false,
+                Err(dav1d::Error::Again) => true,
+                Err(e) => return Err(decoding_error(e)),
+            },
+            picture => return picture.map_err(decoding_error),
+        }
+    }
+}
+
+fn decoding_error(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(ImageFormat::Avif), error))
+}
+
+/// How luma and chroma combine back into RGB
+enum Matrix {
+    /// Weights of red and blue in luma, green gets the rest
+    KrKb(f32, f32),
+    /// The planes hold green, blue and red
+    Identity,
+    YCgCo,
+}
+
+impl Matrix {
+    fn new(coefficients: MatrixCoefficients) -> ImageResult<Self> {
+        Ok(match coefficients {
+            MatrixCoefficients::Identity => Matrix::Identity,
+            // BT.709 is what browsers assume for unspecified AVIF too
+            MatrixCoefficients::BT709 | MatrixCoefficients::Unspecified => Matrix::KrKb(0.2126, 0.0722),
+            MatrixCoefficients::BT470M => Matrix::KrKb(0.30, 0.11),
+            MatrixCoefficients::BT470BG | MatrixCoefficients::ST170M => Matrix::KrKb(0.299, 0.114),
+            MatrixCoefficients::ST240M => Matrix::KrKb(0.212, 0.087),
+            MatrixCoefficients::BT2020NonConstantLuminance => Matrix::KrKb(0.2627, 0.0593),
+            MatrixCoefficients::YCgCo => Matrix::YCgCo,
+            other => return Err(decoding_error(format!("unsupported matrix coefficients {other:?}"))),
+        })
+    }
+
+    /// `luma` from 0 to 1, `cb` and `cr` centered on 0 except for the identity matrix
+    fn to_rgb(&self, luma: f32, cb: f32, cr: f32) -> [f32; 3] {
+        match *self {
+            Matrix::KrKb(kr, kb) => {
+                let red = luma + 2.0 * (1.0 - kr) * cr;
+                let blue = luma + 2.0 * (1.0 - kb) * cb;
+                let green = (luma - kr * red - kb * blue) / (1.0 - kr - kb);
+                [red, green, blue]
+            }
+            Matrix::Identity => [cr, luma, cb],
+            Matrix::YCgCo => {
+                let base = luma - cb;
+                [base + cr, luma + cb, base - cr]
+            }
+        }
+    }
+}
+
+/// Maps stored sample values to luma from 0 to 1 and chroma centered on 0, for full or limited (studio swing) range
+struct YuvRange {
+    luma_offset: f32,
+    luma_scale: f32,
+    chroma_offset: f32,
+    chroma_scale: f32,
+}
+
+impl YuvRange {
+    fn new(picture: &Picture) -> Self {
+        let bit_depth = picture.bit_depth();
+        let max = ((1 << bit_depth) - 1) as f32;
+        let center = (1 << (bit_depth - 1)) as f32;
+        // limited range values are defined for 8 bits and shifted up for more
+        let step = (1 << (bit_depth - 8)) as f32;
+        match picture.color_range() {
+            YUVRange::Full => YuvRange { luma_offset: 0.0, luma_scale: max, chroma_offset: center, chroma_scale: max },
+            YUVRange::Limited => YuvRange {
+                luma_offset: 16.0 * step,
+                luma_scale: 219.0 * step,
+                chroma_offset: center,
+                chroma_scale: 224.0 * step,
+            },
+        }
+    }
+
+    fn luma(&self, value: u16) -> f32 {
+        (f32::from(value) - self.luma_offset) / self.luma_scale
+    }
+
+    fn chroma(&self, value: u16) -> f32 {
+        (f32::from(value) - self.chroma_offset) / self.chroma_scale
+    }
+}
+
+/// Reads samples out of one plane, two bytes each past 8 bits
+struct PlaneReader {
+    plane: dav1d::Plane,
+    stride: usize,
+    high_bit_depth: bool,
+}
+
+impl PlaneReader {
+    fn new(picture: &Picture, component: PlanarImageComponent) -> Self {
+        PlaneReader {
+            plane: picture.plane(component),
+            stride: picture.stride(component) as usize,
+            high_bit_depth: picture.bit_depth() > 8,
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> u16 {
+        let row = y as usize * self.stride;
+        if self.high_bit_depth {
+            let offset = row + x as usize * 2;
+            u16::from_ne_bytes([self.plane[offset], self.plane[offset + 1]])
+        } else {
+            self.plane[row + x as usize].into()
+        }
+    }
+}
+
+/// Converts a picture to opaque 8 bit RGBA with the matrix and range it was encoded with
+fn to_rgba(picture: &Picture) -> ImageResult<RgbaImage> {
+    let matrix = Matrix::new(picture.matrix_coefficients())?;
+    let range = YuvRange::new(picture);
+    let (shift_x, shift_y) = match picture.pixel_layout() {
+        PixelLayout::I400 | PixelLayout::I444 => (0, 0),
+        PixelLayout::I422 => (1, 0),
+        PixelLayout::I420 => (1, 1),
+    };
+    let luma = PlaneReader::new(picture, PlanarImageComponent::Y);
+    let chroma = (picture.pixel_layout() != PixelLayout::I400)
+        .then(|| (PlaneReader::new(picture, PlanarImageComponent::U), PlaneReader::new(picture, PlanarImageComponent::V)));
+    // the identity matrix stores blue and red as they are, not centered like chroma
+    let chroma_value = |value| if matches!(matrix, Matrix::Identity) { range.luma(value) } else { range.chroma(value) };
+    Ok(RgbaImage::from_fn(picture.width(), picture.height(), |x, y| {
+        let luma = range.luma(luma.get(x, y));
+        let [red, green, blue] = match &chroma {
+            Some((cb, cr)) => {
+                let (x, y) = (x >> shift_x, y >> shift_y);
+                matrix.to_rgb(luma, chroma_value(cb.get(x, y)), chroma_value(cr.get(x, y)))
+            }
+            // monochrome
+            None => [luma; 3],
+        };
+        Rgba([to_u8(red), to_u8(green), to_u8(blue), u8::MAX])
+    }))
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,144 @@
+use crate::errors::LoadImageError;
+use crate::image_loader::{self, Decoders, FrameCount, LoadedImage};
+use crate::UserEvent;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+type LoadResult = (u64, PathBuf, Result<LoadedImage, LoadImageError>);
+
+/// Decodes the images navigated to on a worker thread, one at a time. Each request supersedes the ones before it,
+/// so quickly stepping through a folder only decodes where it ends up instead of every image on the way
+pub struct BackgroundLoader {
+    request_sender: Sender<(u64, PathBuf)>,
+    result_receiver: Receiver<LoadResult>,
+    /// Number of the newest request, results of older ones are dropped
+    latest: Arc<AtomicU64>,
+}
+
+impl BackgroundLoader {
+    /// `notify` hands progress to the event loop, returning false once it's gone
+    pub fn new(decoders: Arc<Decoders>, notify: impl Fn(UserEvent) -> bool + Send + 'static) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<(u64, PathBuf)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let latest = Arc::new(AtomicU64::new(0));
+        let worker_latest = latest.clone();
+        // the worker stops once the loader (and with it the request sender) or the event loop is gone
+        thread::spawn(move || {
+            while let Ok(mut request) = request_receiver.recv() {
+                // whatever piled up during the last decode is stale except the newest
+                while let Ok(newer) = request_receiver.try_recv() {
+                    request = newer;
+                }
+                let (generation, path) = request;
+                // cancelled before its turn came
+                if worker_latest.load(Ordering::Acquire) != generation {
+                    continue;
+                }
+                // the frame count is cheap to read, so animations can show it while their frames are still decoding
+                let frames = image_loader::frame_count(&path);
+                if frames != FrameCount::Still && !notify(UserEvent::AnimationLoading { generation, frames }) {
+                    break;
+                }
+                let result = decoders.load(&path);
+                // decoding can't be interrupted, but a result superseded meanwhile never reaches the window
+                if worker_latest.load(Ordering::Acquire) != generation {
+                    continue;
+                }
+                if result_sender.send((generation, path, result)).is_err() || !notify(UserEvent::ImageLoaded) {
+                    break;
+                }
+            }
+        });
+
+        BackgroundLoader { request_sender, result_receiver, latest }
+    }
+
+    /// Queues `image_path` for decoding, cancelling any earlier request that hasn't been taken yet
+    pub fn request(&self, image_path: &Path) {
+        let generation = self.latest.fetch_add(1, Ordering::AcqRel) + 1;
+        // the worker only goes away with the loader
+        let _ = self.request_sender.send((generation, image_path.to_path_buf()));
+    }
+
+    /// Whether `generation` is the newest request, so news about it is still current
+    pub fn is_latest(&self, generation: u64) -> bool {
+        self.latest.load(Ordering::Acquire) == generation
+    }
+
+    /// Drops every pending request, for when the image changes by other means
+    pub fn cancel(&self) {
+        self.latest.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The image of the newest request once it's decoded, `None` while it's pending or when it was cancelled
+    pub fn take_loaded(&self) -> Option<(PathBuf, Result<LoadedImage, LoadImageError>)> {
+        let latest = self.latest.load(Ordering::Acquire);
+        self.result_receiver.try_iter()
+            .filter(|(generation, ..)| *generation == latest)
+            .last()
+            .map(|(_, path, result)| (path, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use std::{env, fs, process};
+
+    /// A one pixel PNG in the temp folder that is removed again when dropped
+    struct TempImage(PathBuf);
+
+    impl TempImage {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("luminix-test-{}-{name}", process::id()));
+            image::RgbaImage::new(1, 1).save(&path).unwrap();
+            TempImage(path)
+        }
+    }
+
+    impl Drop for TempImage {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn loader() -> (BackgroundLoader, Receiver<UserEvent>) {
+        let (event_sender, events) = mpsc::channel();
+        let loader = BackgroundLoader::new(Arc::new(Decoders::default()), move |event| event_sender.send(event).is_ok());
+        (loader, events)
+    }
+
+    /// Blocks until the worker has sent a decoded image
+    fn wait_loaded(events: &Receiver<UserEvent>) {
+        while !matches!(events.recv_timeout(Duration::from_secs(10)).expect("the loader never finished"), UserEvent::ImageLoaded) {}
+    }
+
+    #[test]
+    fn only_the_newest_request_is_taken() {
+        let (first, second) = (TempImage::new("first.png"), TempImage::new("second.png"));
+        let (loader, events) = loader();
+        loader.request(&first.0);
+        wait_loaded(&events);
+        // the first one is decoded but not taken yet when the second is asked for
+        loader.request(&second.0);
+        wait_loaded(&events);
+        let (path, result) = loader.take_loaded().unwrap();
+        assert_eq!(path, second.0);
+        assert!(result.is_ok());
+        assert!(loader.take_loaded().is_none());
+    }
+
+    #[test]
+    fn cancelled_request_is_dropped() {
+        let image = TempImage::new("cancelled.png");
+        let (loader, events) = loader();
+        loader.request(&image.0);
+        wait_loaded(&events);
+        loader.cancel();
+        assert!(loader.take_loaded().is_none());
+    }
+}
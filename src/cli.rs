@@ -0,0 +1,105 @@
+use crate::errors::ParseArgsError;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: luminix [--watch] [--no-visual-swapchain] [--no-activate] [--zoom <factor>] [--pan <x>,<y>] [--screenshot <path>] [<image_path>...]
+       luminix --slideshow [<image_path>]
+       luminix --reset-settings
+       luminix [--set-key <binding>=<key>]... [--list-keys]";
+
+/// Command line arguments
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// `None` when no path is given, luminix then asks for an image with a file dialog
+    pub image_path: Option<PathBuf>,
+    /// Every image path in the order given when there's more than one, the next and previous image keys
+    /// step through them instead of the folder. Empty for a single path
+    pub playlist: Vec<PathBuf>,
+    /// Reload the image whenever it changes on disk
+    pub watch: bool,
+    /// Overwrite the settings file with defaults and exit
+    pub reset_settings: bool,
+    /// (binding, key name) pairs to save to the settings file before exiting
+    pub set_keys: Vec<(String, String)>,
+    /// Print the key bindings and exit
+    pub list_keys: bool,
+    /// Use the opaque DX12 swapchain for this run regardless of the setting
+    pub no_visual_swapchain: bool,
+    /// Open the window behind the focused one for this run regardless of the setting
+    pub no_activate: bool,
+    /// Cycle through the hotfolders from the settings, the image path is optional and picks the first image
+    pub slideshow: bool,
+    /// Starting magnification relative to fitting the window, 1.0 is the fit
+    pub zoom: Option<f32>,
+    /// Starting pan in fractions of the image size, positive values look right and down from the center
+    pub pan: Option<(f32, f32)>,
+    /// Save the opening view to this file and exit, for capturing a spot picked with `zoom` and `pan`
+    pub screenshot: Option<PathBuf>,
+}
+
+impl CliArgs {
+    /// Parses the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliArgs, ParseArgsError> {
+        let mut image_paths = Vec::new();
+        let mut watch = false;
+        let mut reset_settings = false;
+        let mut set_keys = Vec::new();
+        let mut list_keys = false;
+        let mut no_visual_swapchain = false;
+        let mut no_activate = false;
+        let mut slideshow = false;
+        let mut zoom = None;
+        let mut pan = None;
+        let mut screenshot = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--watch" => watch = true,
+                "--reset-settings" => reset_settings = true,
+                "--list-keys" => list_keys = true,
+                "--no-visual-swapchain" => no_visual_swapchain = true,
+                "--no-activate" => no_activate = true,
+                "--slideshow" => slideshow = true,
+                "--zoom" => {
+                    let value = args.next().ok_or_else(|| ParseArgsError::MissingValue(arg.clone()))?;
+                    zoom = Some(parse_number(&value).filter(|zoom| *zoom > 0.0).ok_or(ParseArgsError::InvalidValue(arg, value))?);
+                }
+                "--pan" => {
+                    let value = args.next().ok_or_else(|| ParseArgsError::MissingValue(arg.clone()))?;
+                    let parsed = value.split_once(',').and_then(|(x, y)| Some((parse_number(x)?, parse_number(y)?)));
+                    pan = Some(parsed.ok_or(ParseArgsError::InvalidValue(arg, value))?);
+                }
+                "--screenshot" => screenshot = Some(PathBuf::from(args.next().ok_or(ParseArgsError::MissingValue(arg))?)),
+                "--set-key" => {
+                    let assignment = args.next().ok_or(ParseArgsError::MissingValue(arg))?;
+                    let Some((binding, key_name)) = assignment.split_once('=') else {
+                        return Err(ParseArgsError::InvalidKeyAssignment(assignment));
+                    };
+                    set_keys.push((binding.trim().to_owned(), key_name.trim().to_owned()));
+                }
+                option if option.starts_with("--") => return Err(ParseArgsError::UnknownOption(arg)),
+                _ => image_paths.push(PathBuf::from(arg)),
+            }
+        }
+        let image_path = image_paths.first().cloned();
+        let playlist = if image_paths.len() > 1 { image_paths } else { Vec::new() };
+        Ok(CliArgs {
+            image_path,
+            playlist,
+            watch,
+            reset_settings,
+            set_keys,
+            list_keys,
+            no_visual_swapchain,
+            no_activate,
+            slideshow,
+            zoom,
+            pan,
+            screenshot,
+        })
+    }
+}
+
+/// Parses a finite number, surrounding whitespace is ignored
+fn parse_number(value: &str) -> Option<f32> {
+    value.trim().parse::<f32>().ok().filter(|number| number.is_finite())
+}
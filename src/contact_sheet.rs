@@ -0,0 +1,206 @@
+use crate::image_loader::{image_dir, image_paths_in_dir, load_thumbnail};
+use crate::UserEvent;
+use egui::{Align2, Color32, ColorImage, Context, FontId, Rect, Sense, StrokeKind, TextureHandle, TextureOptions, Vec2};
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use winit::event_loop::EventLoopProxy;
+
+const THUMBNAIL_SIZE: u32 = 160;
+const CELL_SPACING: f32 = 8.0;
+const LABEL_HEIGHT: f32 = 18.0;
+/// Rows above and below the visible ones that keep their thumbnails loaded
+const ROW_MARGIN: usize = 2;
+
+enum Thumbnail {
+    Pending,
+    Loaded(TextureHandle),
+    Failed,
+}
+
+enum ThumbnailResult {
+    Loaded(usize, ColorImage),
+    Failed(usize),
+    /// Scrolled out of view before a worker got to it
+    Skipped(usize),
+}
+
+/// Scrollable grid of thumbnails for every image in a folder
+pub struct ContactSheet {
+    paths: Vec<PathBuf>,
+    current: Option<usize>,
+    thumbnails: HashMap<usize, Thumbnail>,
+    /// Indices the workers should still bother decoding
+    wanted: Arc<Mutex<Range<usize>>>,
+    request_sender: Sender<(usize, PathBuf)>,
+    result_receiver: Receiver<ThumbnailResult>,
+    scroll_to_current: bool,
+}
+
+impl ContactSheet {
+    /// Lists the folder containing `image_path` and starts the thumbnail workers
+    pub fn new(image_path: &Path, event_loop_proxy: EventLoopProxy<UserEvent>) -> io::Result<Self> {
+        let paths = image_paths_in_dir(image_dir(image_path))?;
+        let current = paths.iter().position(|path| path.file_name() == image_path.file_name());
+
+        let wanted = Arc::new(Mutex::new(0..0));
+        let (request_sender, request_receiver) = mpsc::channel::<(usize, PathBuf)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let worker_count = thread::available_parallelism().map_or(1, |n| n.get().clamp(1, 4));
+        for _ in 0..worker_count {
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            let wanted = wanted.clone();
+            let event_loop_proxy = event_loop_proxy.clone();
+            // workers stop once the sheet (and with it the request sender) is dropped
+            thread::spawn(move || loop {
+                let Ok((index, path)) = request_receiver.lock().unwrap().recv() else { break };
+                let result = if wanted.lock().unwrap().contains(&index) {
+                    match load_thumbnail(&path, THUMBNAIL_SIZE) {
+                        Ok(thumbnail) => {
+                            let size = [thumbnail.width() as usize, thumbnail.height() as usize];
+                            ThumbnailResult::Loaded(index, ColorImage::from_rgba_unmultiplied(size, thumbnail.as_raw()))
+                        }
+                        Err(e) => {
+                            log::error!("Failed to create thumbnail for {}. Error message: {e}", path.display());
+                            ThumbnailResult::Failed(index)
+                        }
+                    }
+                } else {
+                    ThumbnailResult::Skipped(index)
+                };
+                if result_sender.send(result).is_err() || event_loop_proxy.send_event(UserEvent::ThumbnailReady).is_err() {
+                    break;
+                }
+            });
+        }
+
+        Ok(ContactSheet {
+            paths,
+            current,
+            thumbnails: HashMap::new(),
+            wanted,
+            request_sender,
+            result_receiver,
+            scroll_to_current: true,
+        })
+    }
+
+    /// Draws the sheet, returns the image the user clicked on
+    pub fn ui(&mut self, ctx: &Context) -> Option<PathBuf> {
+        self.receive_thumbnails(ctx);
+
+        let mut clicked = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.paths.is_empty() {
+                ui.centered_and_justified(|ui| ui.label("No images in this folder"));
+                return;
+            }
+            let cell_size = Vec2::new(THUMBNAIL_SIZE as f32, THUMBNAIL_SIZE as f32 + LABEL_HEIGHT);
+            let columns = ((ui.available_width() + CELL_SPACING) / (cell_size.x + CELL_SPACING)).floor().max(1.0) as usize;
+            let rows = self.paths.len().div_ceil(columns);
+            ui.spacing_mut().item_spacing = Vec2::splat(CELL_SPACING);
+
+            let mut scroll_area = egui::ScrollArea::vertical().auto_shrink(false);
+            if self.scroll_to_current {
+                self.scroll_to_current = false;
+                if let Some(current) = self.current {
+                    scroll_area = scroll_area.vertical_scroll_offset((current / columns) as f32 * (cell_size.y + CELL_SPACING));
+                }
+            }
+            scroll_area.show_rows(ui, cell_size.y, rows, |ui, row_range| {
+                let first = row_range.start.saturating_sub(ROW_MARGIN) * columns;
+                let last = ((row_range.end + ROW_MARGIN) * columns).min(self.paths.len());
+                self.set_wanted(first..last);
+
+                for row in row_range {
+                    ui.horizontal(|ui| {
+                        for index in row * columns..((row + 1) * columns).min(self.paths.len()) {
+                            if self.cell_ui(ui, index, cell_size) {
+                                clicked = Some(self.paths[index].clone());
+                            }
+                        }
+                    });
+                }
+            });
+        });
+        clicked
+    }
+
+    fn cell_ui(&self, ui: &mut egui::Ui, index: usize, cell_size: Vec2) -> bool {
+        let (rect, response) = ui.allocate_exact_size(cell_size, Sense::click());
+        let painter = ui.painter_at(rect);
+        let thumbnail_rect = Rect::from_min_size(rect.min, Vec2::splat(cell_size.x));
+
+        if response.hovered() || Some(index) == self.current {
+            painter.rect_filled(rect, 4.0, ui.visuals().widgets.hovered.bg_fill);
+        }
+        match self.thumbnails.get(&index) {
+            Some(Thumbnail::Loaded(texture)) => {
+                let image_rect = Rect::from_center_size(thumbnail_rect.center(), texture.size_vec2());
+                painter.image(texture.id(), image_rect, Rect::from_min_max([0.0, 0.0].into(), [1.0, 1.0].into()), Color32::WHITE);
+            }
+            Some(Thumbnail::Failed) => {
+                painter.text(thumbnail_rect.center(), Align2::CENTER_CENTER, "?", FontId::proportional(32.0), ui.visuals().weak_text_color());
+            }
+            Some(Thumbnail::Pending) | None => {
+                painter.rect_stroke(thumbnail_rect.shrink(24.0), 4.0, ui.visuals().widgets.noninteractive.bg_stroke, StrokeKind::Inside);
+            }
+        }
+        let name = self.paths[index].file_name().unwrap_or_default().to_string_lossy();
+        painter.text(
+            [rect.center().x, rect.max.y - LABEL_HEIGHT / 2.0].into(),
+            Align2::CENTER_CENTER,
+            name,
+            FontId::proportional(12.0),
+            ui.visuals().text_color(),
+        );
+        response.clicked()
+    }
+
+    /// Requests thumbnails that came into view and frees the ones that left it, so memory stays bounded by the view size
+    fn set_wanted(&mut self, wanted: Range<usize>) {
+        if *self.wanted.lock().unwrap() == wanted {
+            return;
+        }
+        self.thumbnails.retain(|index, _| wanted.contains(index));
+        for index in wanted.clone() {
+            if !self.thumbnails.contains_key(&index) && self.request_sender.send((index, self.paths[index].clone())).is_ok() {
+                self.thumbnails.insert(index, Thumbnail::Pending);
+            }
+        }
+        *self.wanted.lock().unwrap() = wanted;
+    }
+
+    fn receive_thumbnails(&mut self, ctx: &Context) {
+        let wanted = self.wanted.lock().unwrap().clone();
+        for result in self.result_receiver.try_iter() {
+            match result {
+                ThumbnailResult::Loaded(index, image) if wanted.contains(&index) => {
+                    let texture = ctx.load_texture(format!("thumbnail {index}"), image, TextureOptions::LINEAR);
+                    self.thumbnails.insert(index, Thumbnail::Loaded(texture));
+                }
+                ThumbnailResult::Failed(index) if wanted.contains(&index) => {
+                    self.thumbnails.insert(index, Thumbnail::Failed);
+                }
+                ThumbnailResult::Loaded(index, _) | ThumbnailResult::Failed(index) | ThumbnailResult::Skipped(index) => {
+                    // out of view by now, request it again once it scrolls back in
+                    if matches!(self.thumbnails.get(&index), Some(Thumbnail::Pending)) {
+                        self.thumbnails.remove(&index);
+                    }
+                }
+            }
+        }
+        // re-request anything that was skipped while still in view
+        for index in wanted {
+            if !self.thumbnails.contains_key(&index) && self.request_sender.send((index, self.paths[index].clone())).is_ok() {
+                self.thumbnails.insert(index, Thumbnail::Pending);
+            }
+        }
+    }
+}
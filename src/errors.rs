@@ -12,8 +12,109 @@ pub enum CommandExecutionError {
 }
 
 
+#[derive(Error, Debug)]
+pub enum FileActionError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("no destination folder chosen")]
+    NoDestination,
+    #[error("image path has no file name")]
+    InvalidImagePath,
+}
+
+
 #[derive(Error, Debug)]
 pub enum RunActionError {
     #[error(transparent)]
     CommandExecutionError(#[from] CommandExecutionError),
-}
\ No newline at end of file
+    #[error(transparent)]
+    FileActionError(#[from] FileActionError),
+    #[error("no action named {0} is registered")]
+    Unregistered(String),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadImageError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[error("unrecognized image format")]
+    UnknownFormat,
+    #[error("the file is empty")]
+    Empty,
+    #[error("animation has no frames")]
+    NoFrames,
+    #[error("this build of luminix can't decode AVIF, build it with --features avif")]
+    AvifDisabled,
+    #[cfg(feature = "avif")]
+    #[error("can't decode AVIF file: {0}")]
+    Avif(String),
+    #[error("can't decode RAW file: {0}")]
+    Raw(String),
+    #[error(transparent)]
+    Tiff(#[from] tiff::TiffError),
+}
+
+#[derive(Error, Debug)]
+pub enum ParseArgsError {
+    #[error("unknown option {0}")]
+    UnknownOption(String),
+    #[error("{0} needs a value")]
+    MissingValue(String),
+    #[error("expected <binding>=<key>, got {0}")]
+    InvalidKeyAssignment(String),
+    #[error("invalid value {1} for {0}")]
+    InvalidValue(String, String),
+}
+
+#[derive(Error, Debug)]
+pub enum SetKeyError {
+    #[error("unknown binding {0}, see --list-keys for the available ones")]
+    UnknownBinding(String),
+    #[error("unknown key {0}")]
+    UnknownKey(String),
+    #[error("{0} has modifiers, only single keys can be bound")]
+    Modifiers(String),
+}
+
+#[derive(Error, Debug)]
+pub enum SidecarError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum SlideshowError {
+    #[error("no hotfolders are set up, add some in the settings window")]
+    NoFolders,
+    #[error("the hotfolders have no images")]
+    NoImages,
+    #[error(transparent)]
+    Watch(#[from] notify_debouncer_mini::notify::Error),
+}
+
+/// Why the GPU can't draw to the window, there's nothing luminix can show then
+#[derive(Error, Debug)]
+pub enum RendererError {
+    #[error(transparent)]
+    Surface(#[from] wgpu::CreateSurfaceError),
+    #[error(transparent)]
+    Adapter(#[from] wgpu::RequestAdapterError),
+    #[error(transparent)]
+    Device(#[from] wgpu::RequestDeviceError),
+}
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("can't read back frames in {0:?}")]
+    UnsupportedFormat(wgpu::TextureFormat),
+    #[error(transparent)]
+    Poll(#[from] wgpu::PollError),
+    #[error(transparent)]
+    Map(#[from] wgpu::BufferAsyncError),
+    #[error("the frame was never read back")]
+    Unmapped,
+}
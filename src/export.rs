@@ -0,0 +1,107 @@
+use crate::image_loader::{GifData, ImageData};
+use crate::settings_window::DataUrlFormat;
+use base64::Engine;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, Frame, ImageError, ImageFormat, ImageResult, Rgba, RgbaImage};
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use std::path::Path;
+
+/// Palette quantization speed of the GIF encoder, from 1 (best colors) to 30 (fastest), 10 is the encoder's usual trade-off
+const GIF_QUANTIZATION_SPEED: i32 = 10;
+
+/// Quality of JPEG data URLs, high enough that the usual artifacts don't show at 1:1
+const DATA_URL_JPEG_QUALITY: u8 = 90;
+
+/// Encodes `image` as base64, behind a `data:` prefix with its media type if `prefix` is set.
+/// JPEG has no alpha channel, so transparent pixels lose their transparency
+pub fn data_url(image: &ImageData, format: DataUrlFormat, prefix: bool) -> ImageResult<String> {
+    let rgba = RgbaImage::from_raw(image.width, image.height, image.rgba_data.clone())
+        .expect("image data matches its dimensions");
+    let mut encoded = Cursor::new(Vec::new());
+    let media_type = match format {
+        DataUrlFormat::Png => {
+            rgba.write_to(&mut encoded, ImageFormat::Png)?;
+            "image/png"
+        }
+        DataUrlFormat::Jpeg => {
+            let rgb = DynamicImage::ImageRgba8(rgba).into_rgb8();
+            rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, DATA_URL_JPEG_QUALITY))?;
+            "image/jpeg"
+        }
+    };
+    let base64 = base64::engine::general_purpose::STANDARD.encode(encoded.into_inner());
+    Ok(if prefix { format!("data:{media_type};base64,{base64}") } else { base64 })
+}
+
+/// Turns `image` clockwise by `degrees` onto a transparent canvas just large enough to hold all of it.
+/// Samples bilinearly in premultiplied space, so edges fade out instead of picking up dark fringes
+pub fn rotate_image(image: &ImageData, degrees: f32) -> RgbaImage {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (width, height) = (image.width as f32, image.height as f32);
+    let rotated_width = (width * cos.abs() + height * sin.abs()).round().max(1.0) as u32;
+    let rotated_height = (width * sin.abs() + height * cos.abs()).round().max(1.0) as u32;
+
+    let premultiplied_pixel = |x: i64, y: i64| -> [f32; 4] {
+        if x < 0 || y < 0 || x >= i64::from(image.width) || y >= i64::from(image.height) {
+            return [0.0; 4];
+        }
+        let index = (y as usize * image.width as usize + x as usize) * 4;
+        let pixel = &image.rgba_data[index..index + 4];
+        let alpha = f32::from(pixel[3]) / 255.0;
+        [f32::from(pixel[0]) * alpha, f32::from(pixel[1]) * alpha, f32::from(pixel[2]) * alpha, alpha]
+    };
+
+    RgbaImage::from_fn(rotated_width, rotated_height, |x, y| {
+        // back from the rotated canvas into the source, y points down so this is the usual inverse rotation
+        let dx = x as f32 + 0.5 - rotated_width as f32 / 2.0;
+        let dy = y as f32 + 0.5 - rotated_height as f32 / 2.0;
+        let source_x = dx * cos + dy * sin + width / 2.0 - 0.5;
+        let source_y = dy * cos - dx * sin + height / 2.0 - 0.5;
+
+        let (x0, y0) = (source_x.floor(), source_y.floor());
+        let (fx, fy) = (source_x - x0, source_y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut sum = [0.0; 4];
+        for (sample_x, sample_y, weight) in [
+            (x0, y0, (1.0 - fx) * (1.0 - fy)),
+            (x0 + 1, y0, fx * (1.0 - fy)),
+            (x0, y0 + 1, (1.0 - fx) * fy),
+            (x0 + 1, y0 + 1, fx * fy),
+        ] {
+            let pixel = premultiplied_pixel(sample_x, sample_y);
+            for channel in 0..4 {
+                sum[channel] += pixel[channel] * weight;
+            }
+        }
+
+        let alpha = sum[3];
+        if alpha <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        Rgba([
+            (sum[0] / alpha).round().clamp(0.0, 255.0) as u8,
+            (sum[1] / alpha).round().clamp(0.0, 255.0) as u8,
+            (sum[2] / alpha).round().clamp(0.0, 255.0) as u8,
+            (alpha * 255.0).round() as u8,
+        ])
+    })
+}
+
+/// Writes `frames` to a looping GIF at `path`, each frame keeping its delay.
+/// Every frame gets its own palette from the encoder's quantizer, `progress` is called with the number of frames done
+pub fn encode_gif(frames: &[GifData], path: &Path, mut progress: impl FnMut(usize)) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), GIF_QUANTIZATION_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    for (index, frame) in frames.iter().enumerate() {
+        let Some(buffer) = RgbaImage::from_raw(frame.width, frame.height, frame.rgba_data.clone()) else {
+            return Err(ImageError::Parameter(image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            )));
+        };
+        encoder.encode_frame(Frame::from_parts(buffer, 0, 0, frame.delay))?;
+        progress(index + 1);
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+// Bare bones version of shader.wgsl, used when a driver can't compile that one.
+// Keeps the same bindings and vertex transform but only samples the image, tone mapping HDR images like it does
+
+struct Uniforms {
+    image_aspect: f32,
+    window_aspect: f32,
+    zoom: f32,
+    pan_x: f32,
+    pan_y: f32,
+    // screen pixels per image pixel at which the pixel grid appears, 0.0 disables it
+    grid_threshold: f32,
+    // 1.0 draws a checkerboard behind transparent pixels
+    checkerboard: f32,
+    tone_mapping: f32,
+    exposure: f32,
+    // 0.0 fits the whole image inside the window, 1.0 matches its width and 2.0 its height
+    fit_mode: f32,
+    sharpen: f32,
+    // clockwise, in radians
+    rotation: f32,
+    colormap: f32,
+    flip_x: f32,
+    flip_y: f32,
+    crop_x: f32,
+    crop_y: f32,
+    crop_width: f32,
+    crop_height: f32,
+    dim: f32,
+    negative: f32,
+    film_base_r: f32,
+    film_base_g: f32,
+    film_base_b: f32,
+    inset_left: f32,
+    inset_top: f32,
+    inset_right: f32,
+    inset_bottom: f32,
+    window_height: f32,
+    frame_width: f32,
+    frame_shadow: f32,
+    frame_r: f32,
+    frame_g: f32,
+    frame_b: f32,
+    frame_a: f32,
+    channel: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    
+    // Apply aspect ratio correction
+    var pos = model.position;
+    
+    // Fit the rotated image's bounding box to the part of the window the insets leave uncovered
+    // while maintaining aspect ratio, sizes are in image heights and window heights
+    let rotation_sin = sin(uniforms.rotation);
+    let rotation_cos = cos(uniforms.rotation);
+    let bounds_width = uniforms.image_aspect * abs(rotation_cos) + abs(rotation_sin);
+    let bounds_height = uniforms.image_aspect * abs(rotation_sin) + abs(rotation_cos);
+    let free_width = 1.0 - uniforms.inset_left - uniforms.inset_right;
+    let free_height = 1.0 - uniforms.inset_top - uniforms.inset_bottom;
+    let fit_width = uniforms.window_aspect * free_width / bounds_width;
+    let fit_height = free_height / bounds_height;
+    var size: f32;
+    if (uniforms.fit_mode > 0.5 && uniforms.fit_mode < 1.5) {
+        size = fit_width;
+    } else if (uniforms.fit_mode > 1.5) {
+        size = fit_height;
+    } else {
+        size = min(fit_width, fit_height);
+    }
+    
+    // Apply zoom
+    size = size / uniforms.zoom;
+    
+    // Rotate clockwise in window height units, so pixels stay square
+    let corner = vec2<f32>(pos.x * uniforms.image_aspect * size, pos.y * size);
+    let rotated = vec2<f32>(
+        corner.x * rotation_cos + corner.y * rotation_sin,
+        corner.y * rotation_cos - corner.x * rotation_sin,
+    );
+    
+    // Center on the uncovered area and apply pan (in normalized device coordinates)
+    pos.x = rotated.x / uniforms.window_aspect + uniforms.inset_left - uniforms.inset_right - uniforms.pan_x * 2.0;
+    pos.y = rotated.y + uniforms.inset_bottom - uniforms.inset_top + uniforms.pan_y * 2.0;
+    
+    out.clip_position = vec4<f32>(pos, 1.0);
+    // flip first, so mirroring happens around the image's own axes
+    var tex_coords = model.tex_coords;
+    if (uniforms.flip_x > 0.5) {
+        tex_coords.x = 1.0 - tex_coords.x;
+    }
+    if (uniforms.flip_y > 0.5) {
+        tex_coords.y = 1.0 - tex_coords.y;
+    }
+    out.tex_coords = vec2<f32>(uniforms.crop_x, uniforms.crop_y) + tex_coords * vec2<f32>(uniforms.crop_width, uniforms.crop_height);
+    return out;
+}
+
+// Fragment shader
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+// Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    if (uniforms.tone_mapping > 0.0 && color.a > 0.0) {
+        // tone map the straight color, then pre-multiply again
+        var rgb = color.rgb / color.a * exp2(uniforms.exposure);
+        if (uniforms.tone_mapping < 1.5) {
+            rgb = rgb / (rgb + vec3<f32>(1.0));
+        } else {
+            rgb = aces(rgb);
+        }
+        color = vec4<f32>(rgb * color.a, color.a);
+    }
+    return vec4<f32>(color.rgb * (1.0 - uniforms.dim), color.a);
+}
@@ -0,0 +1,35 @@
+use crate::image_loader::image_dir;
+use crate::UserEvent;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::path::Path;
+use std::time::Duration;
+use winit::event_loop::EventLoopProxy;
+
+/// Quiet period after the last write before the image is reloaded, so a save in progress is only read once
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Notifies the event loop when the shown image changes on disk
+pub struct FileWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl FileWatcher {
+    pub fn new(image_path: &Path, event_loop_proxy: EventLoopProxy<UserEvent>) -> notify_debouncer_mini::notify::Result<Self> {
+        // watch the folder rather than the file, editors often save by deleting or renaming over the original
+        // which would leave a watch on the file itself pointing at nothing
+        let dir = image_dir(image_path);
+        let file_name = image_path.file_name().map(ToOwned::to_owned);
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                if events.iter().any(|event| event.path.file_name() == file_name.as_deref()) {
+                    // the event loop is gone once the app exits, nothing left to notify
+                    let _ = event_loop_proxy.send_event(UserEvent::ImageChanged);
+                }
+            }
+            Err(e) => log::error!("Failed to watch image for changes. Error message: {e}"),
+        })?;
+        debouncer.watcher().watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(FileWatcher { _debouncer: debouncer })
+    }
+}
@@ -0,0 +1,48 @@
+use crate::errors::RunActionError;
+use crate::settings_window::Action;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// What an action gets to know about the shown image when it runs
+pub struct ActionContext<'a> {
+    pub image_path: &'a Path,
+    pub dimensions: (u32, u32),
+    /// Index of the shown frame, `None` for still images
+    pub frame: Option<u32>,
+}
+
+/// Something that can be bound to an action slot and run on the shown image.
+/// The built-in actions implement it through [`Action`], anything else is added to an [`ActionRegistry`]
+pub trait ImageAction {
+    /// Name shown in the settings and the confirmation prompt
+    fn label(&self) -> String;
+    fn run(&self, ctx: &ActionContext) -> Result<(), RunActionError>;
+}
+
+/// Actions beyond the built-in ones, looked up by the name an [`Action::Registered`] slot stores
+#[derive(Default)]
+pub struct ActionRegistry {
+    actions: BTreeMap<String, Box<dyn ImageAction>>,
+}
+
+impl ActionRegistry {
+    /// Adds `action` under its label, replacing an action registered under the same label before
+    #[allow(dead_code)] // nothing is registered by luminix itself, this is the entry point for additional actions
+    pub fn register(&mut self, action: Box<dyn ImageAction>) {
+        self.actions.insert(action.label(), action);
+    }
+
+    /// Labels of all registered actions in alphabetical order, for choosing one in the settings
+    pub fn labels(&self) -> Vec<String> {
+        self.actions.keys().cloned().collect()
+    }
+
+    /// What running `action` should call, registered slots resolve to the action they name.
+    /// Slots naming an action that isn't registered stay as they are and fail when run
+    pub fn resolve<'a>(&'a self, action: &'a Action) -> &'a dyn ImageAction {
+        match action {
+            Action::Registered(label) => self.actions.get(label).map_or(action as &dyn ImageAction, Box::as_ref),
+            _ => action,
+        }
+    }
+}
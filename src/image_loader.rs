@@ -0,0 +1,847 @@
+use crate::errors::LoadImageError;
+use crate::raw::RawDecoder;
+use crate::wgpu_renderer::{AlphaMode, ToneMapping};
+use image::metadata::LoopCount;
+use image::{AnimationDecoder, Delay, ImageFormat, RgbaImage};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone)]
+#[allow(dead_code)]
+pub struct ImageData {
+    pub rgba_data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct GifData {
+    pub rgba_data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub delay: Delay,
+}
+
+/// Linear float pixels from high dynamic range formats, values can go past 1.0
+pub struct HdrImageData {
+    pub rgba_data: Vec<f32>,
+    pub width: u32,
+    pub height: u32,
+    /// OpenEXR always stores premultiplied colors, Radiance HDR has no alpha to speak of
+    pub alpha_mode: AlphaMode,
+}
+impl HdrImageData {
+    /// 8 bit sRGB pixels with straight alpha as they're shown after `exposure` stops and `tone_mapping`,
+    /// for copying and saving what's on screen
+    pub fn tone_mapped(&self, tone_mapping: ToneMapping, exposure: f32) -> ImageData {
+        let gain = exposure.exp2();
+        let rgba_data = self.rgba_data.chunks_exact(4).flat_map(|pixel| {
+            let alpha = pixel[3].clamp(0.0, 1.0);
+            // the shader tone maps the straight color too
+            let encode = |channel: f32| {
+                let straight = match self.alpha_mode {
+                    AlphaMode::Straight => channel,
+                    AlphaMode::Premultiplied if alpha > 0.0 => channel / alpha,
+                    AlphaMode::Premultiplied => 0.0,
+                };
+                (linear_to_srgb(tone_mapping.apply(straight.max(0.0) * gain)) * 255.0).round() as u8
+            };
+            [encode(pixel[0]), encode(pixel[1]), encode(pixel[2]), (alpha * 255.0).round() as u8]
+        }).collect();
+        ImageData { rgba_data, width: self.width, height: self.height }
+    }
+}
+
+pub enum LoadedImage {
+    Static(ImageData),
+    /// A RAW file shown through the JPEG preview its camera embedded rather than demosaiced
+    RawPreview(ImageData),
+    Animated {
+        frames: Vec<GifData>,
+        /// How many times the animation plays before stopping on its last frame, `None` to loop forever
+        loop_count: Option<u32>,
+    },
+    Hdr(HdrImageData),
+}
+impl LoadedImage {
+    /// Size of the still image, or of the first frame for animations
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            LoadedImage::Static(image) | LoadedImage::RawPreview(image) => (image.width, image.height),
+            // all frames share the canvas size
+            LoadedImage::Animated { frames, .. } => (frames[0].width, frames[0].height),
+            LoadedImage::Hdr(image) => (image.width, image.height),
+        }
+    }
+    
+    /// Whether any pixel is not fully opaque, scans the whole image so only call it once per load
+    pub fn has_transparency(&self) -> bool {
+        let is_transparent = |rgba_data: &[u8]| rgba_data.chunks_exact(4).any(|pixel| pixel[3] != u8::MAX);
+        match self {
+            LoadedImage::Static(image) | LoadedImage::RawPreview(image) => is_transparent(&image.rgba_data),
+            LoadedImage::Animated { frames, .. } => frames.iter().any(|frame| is_transparent(&frame.rgba_data)),
+            LoadedImage::Hdr(image) => image.rgba_data.chunks_exact(4).any(|pixel| pixel[3] < 1.0),
+        }
+    }
+}
+
+pub fn load_image(image_path: &Path) -> Result<LoadedImage, LoadImageError> {
+    let img_reader = image::ImageReader::open(image_path)?.with_guessed_format()?;
+    let format = img_reader.format().ok_or(LoadImageError::UnknownFormat)?;
+    if format == ImageFormat::Gif {
+        let decoder = image::codecs::gif::GifDecoder::new(BufReader::new(File::open(image_path)?))?;
+        return load_animated(image_path, image::ImageDecoder::dimensions(&decoder), decoder);
+    }
+    if format == ImageFormat::WebP {
+        let decoder = image::codecs::webp::WebPDecoder::new(BufReader::new(File::open(image_path)?))?;
+        // still WebP takes the regular path below
+        if decoder.has_animation() {
+            return load_animated(image_path, image::ImageDecoder::dimensions(&decoder), decoder);
+        }
+    }
+    if format == ImageFormat::Png {
+        let decoder = image::codecs::png::PngDecoder::new(BufReader::new(File::open(image_path)?))?;
+        // plain PNG takes the regular path below
+        if decoder.is_apng()? {
+            let canvas = image::ImageDecoder::dimensions(&decoder);
+            return load_animated(image_path, canvas, decoder.apng()?);
+        }
+    }
+    
+    // the image crate recognizes AVIF either way but only decodes it with dav1d, which is optional since it's a system library
+    if format == ImageFormat::Avif && !cfg!(feature = "avif") {
+        return Err(LoadImageError::AvifDisabled);
+    }
+    // the image crate only decodes the still image, image sequences are decoded here. Still AVIF takes the regular path below
+    #[cfg(feature = "avif")]
+    if format == ImageFormat::Avif {
+        if let Some(decoder) = crate::avif::AvifSequenceDecoder::new(image_path)? {
+            return load_animated(image_path, decoder.dimensions(), decoder);
+        }
+    }
+    
+    if matches!(format, ImageFormat::OpenExr | ImageFormat::Hdr) {
+        // keep the full range, tone mapping happens in the shader
+        let rgba_img = img_reader.decode()?.into_rgba32f();
+        let (width, height) = rgba_img.dimensions();
+        return Ok(LoadedImage::Hdr(HdrImageData {
+            rgba_data: rgba_img.into_raw(),
+            width,
+            height,
+            alpha_mode: if format == ImageFormat::OpenExr { AlphaMode::Premultiplied } else { AlphaMode::Straight },
+        }));
+    }
+    
+    // Load regular image
+    let rgba_img = img_reader.decode()?.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    Ok(LoadedImage::Static(ImageData {
+        rgba_data: rgba_img.into_raw(),
+        width,
+        height,
+    }))
+}
+
+/// Decodes every frame of an animation, each one composited onto the full canvas with its delay.
+/// `canvas` is the size from the header, the logical screen for GIF. The `image` crate composites frames covering only
+/// part of it with their offsets, blending and the previous frames' disposal (none, background or previous), so every
+/// frame comes out whole
+fn load_animated<'a>(image_path: &Path, (canvas_width, canvas_height): (u32, u32), decoder: impl AnimationDecoder<'a>) -> Result<LoadedImage, LoadImageError> {
+    // from the NETSCAPE2.0 extension for GIF, files without one loop forever as browsers do
+    let loop_count = match decoder.loop_count() {
+        LoopCount::Infinite => None,
+        LoopCount::Finite(count) => Some(count.get()),
+    };
+    let capacity = if let FrameCount::Known(frame_count) = frame_count(image_path) { frame_count } else { 0 };
+    let mut frames = Vec::with_capacity(capacity);
+    for frame in decoder.into_frames() {
+        frames.push(frame?);
+    }
+    let frames: Vec<GifData> = frames.into_iter().map(|frame| {
+        let delay = frame.delay();
+        let mut buffer = frame.into_buffer();
+        if buffer.dimensions() != (canvas_width, canvas_height) {
+            // the window and playback are sized by the canvas, so a stray frame size would stretch
+            let mut canvas = RgbaImage::new(canvas_width, canvas_height);
+            image::imageops::replace(&mut canvas, &buffer, 0, 0);
+            buffer = canvas;
+        }
+        GifData {
+            width: buffer.width(),
+            height: buffer.height(),
+            rgba_data: buffer.into_raw(),
+            delay
+        }
+    }).collect();
+    if frames.is_empty() {
+        return Err(LoadImageError::NoFrames);
+    }
+    Ok(LoadedImage::Animated { frames, loop_count })
+}
+
+/// What a file's structure tells about its frames before any pixels are decoded
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrameCount {
+    /// Not an animation, or not a file that can be read at all
+    Still,
+    /// An animation with this many frames
+    Known(usize),
+    /// An animation whose frames can't be counted without decoding it, like a damaged GIF
+    Unknown,
+}
+
+/// Counts the frames of a GIF, APNG or animated WebP from the file's structure, cheap next to decoding them.
+/// AVIF image sequences are only recognized, their frame count sits in the movie box after the media data
+pub fn frame_count(image_path: &Path) -> FrameCount {
+    let Ok(file) = File::open(image_path) else {
+        return FrameCount::Still;
+    };
+    let mut reader = BufReader::new(file);
+    let mut signature = [0; 8];
+    if reader.read_exact(&mut signature).is_err() {
+        return FrameCount::Still;
+    }
+    if signature.starts_with(b"GIF") {
+        // GIFs are always played as animations, even with a single frame
+        gif_frame_count(&mut reader).map_or(FrameCount::Unknown, FrameCount::Known)
+    } else if signature == *b"\x89PNG\r\n\x1a\n" {
+        match apng_frame_count(&mut reader) {
+            Ok(Some(frame_count)) => FrameCount::Known(frame_count),
+            Ok(None) => FrameCount::Still,
+            Err(_) => FrameCount::Unknown,
+        }
+    } else if signature.starts_with(b"RIFF") {
+        match webp_frame_count(&mut reader) {
+            Ok(Some(frame_count)) => FrameCount::Known(frame_count),
+            Ok(None) => FrameCount::Still,
+            Err(_) => FrameCount::Unknown,
+        }
+    } else if cfg!(feature = "avif") && is_avif_sequence(signature, &mut reader).unwrap_or(false) {
+        FrameCount::Unknown
+    } else {
+        FrameCount::Still
+    }
+}
+
+/// Whether the file starts with an `ftyp` box listing the AVIF image sequence brand, `header` being its first 8 bytes
+fn is_avif_sequence(header: [u8; 8], reader: &mut impl Read) -> io::Result<bool> {
+    /// Far more than any real `ftyp` box needs, a bound against garbage sizes
+    const MAX_FTYP_SIZE: u64 = 1024;
+    if &header[4..] != b"ftyp" {
+        return Ok(false);
+    }
+    let size = u64::from(u32::from_be_bytes([header[0], header[1], header[2], header[3]]));
+    let mut brands = Vec::new();
+    reader.take(size.saturating_sub(8).min(MAX_FTYP_SIZE)).read_to_end(&mut brands)?;
+    // the major brand, a minor version and the compatible brands
+    Ok(brands.chunks_exact(4).enumerate().any(|(index, brand)| index != 1 && brand == b"avis"))
+}
+
+/// Walks the GIF blocks after the first 8 bytes, skipping over the compressed image data
+fn gif_frame_count(reader: &mut impl Read) -> io::Result<usize> {
+    // the signature took the header and the first two bytes of the logical screen descriptor
+    let mut screen_descriptor = [0; 5];
+    reader.read_exact(&mut screen_descriptor)?;
+    let packed = screen_descriptor[2];
+    if packed & 0x80 != 0 {
+        skip(reader, 3 << ((packed & 0x07) + 1))?;
+    }
+    let mut frames = 0;
+    loop {
+        let mut introducer = [0];
+        reader.read_exact(&mut introducer)?;
+        match introducer[0] {
+            // extension, a label followed by sub-blocks
+            0x21 => {
+                skip(reader, 1)?;
+                skip_sub_blocks(reader)?;
+            }
+            // image descriptor, then an optional local color table, the LZW code size and the image data
+            0x2C => {
+                let mut descriptor = [0; 9];
+                reader.read_exact(&mut descriptor)?;
+                if descriptor[8] & 0x80 != 0 {
+                    skip(reader, 3 << ((descriptor[8] & 0x07) + 1))?;
+                }
+                skip(reader, 1)?;
+                skip_sub_blocks(reader)?;
+                frames += 1;
+            }
+            // trailer, or anything unexpected in a damaged file
+            _ => return Ok(frames),
+        }
+    }
+}
+
+fn skip_sub_blocks(reader: &mut impl Read) -> io::Result<()> {
+    loop {
+        let mut size = [0];
+        reader.read_exact(&mut size)?;
+        if size[0] == 0 {
+            return Ok(());
+        }
+        skip(reader, size[0].into())?;
+    }
+}
+
+/// Reads the frame count from the animation control chunk, which has to come before the image data
+fn apng_frame_count(reader: &mut impl Read) -> io::Result<Option<usize>> {
+    loop {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        match &header[4..] {
+            b"acTL" => {
+                let mut frame_count = [0; 4];
+                reader.read_exact(&mut frame_count)?;
+                return Ok(usize::try_from(u32::from_be_bytes(frame_count)).ok());
+            }
+            b"IDAT" | b"IEND" => return Ok(None),
+            // chunk data and its crc
+            _ => skip(reader, u64::from(length) + 4)?,
+        }
+    }
+}
+
+/// Counts the ANMF chunks of an animated WebP after the first 8 bytes, `None` for still ones.
+/// Only an extended header with the animation flag set makes a WebP animated
+fn webp_frame_count(reader: &mut impl Read) -> io::Result<Option<usize>> {
+    let mut form = [0; 4];
+    reader.read_exact(&mut form)?;
+    if form != *b"WEBP" {
+        return Ok(None);
+    }
+    let mut frames = 0;
+    let mut first_chunk = true;
+    loop {
+        let mut header = [0; 8];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !first_chunk => return Ok(Some(frames)),
+            Err(e) => return Err(e),
+        }
+        // chunks are padded to an even size
+        let length = u64::from(u32::from_le_bytes([header[4], header[5], header[6], header[7]]));
+        let length = length + (length & 1);
+        match &header[..4] {
+            b"VP8X" if first_chunk => {
+                let mut flags = [0];
+                reader.read_exact(&mut flags)?;
+                if flags[0] & 0x02 == 0 {
+                    return Ok(None);
+                }
+                skip(reader, length.saturating_sub(1))?;
+            }
+            _ if first_chunk => return Ok(None),
+            b"ANMF" => {
+                frames += 1;
+                skip(reader, length)?;
+            }
+            _ => skip(reader, length)?,
+        }
+        first_chunk = false;
+    }
+}
+
+/// Pages skimmed at most when counting, a bound against IFD chains that go on forever
+const MAX_TIFF_PAGES: usize = 10_000;
+
+/// Number of pages of a TIFF holding more than one, `None` for single page TIFFs, other formats and unreadable files.
+/// RAW files are TIFF based but their extra images are previews, not pages
+pub fn tiff_page_count(image_path: &Path) -> Option<usize> {
+    if crate::raw::is_raw(image_path) || ImageFormat::from_path(image_path).ok()? != ImageFormat::Tiff {
+        return None;
+    }
+    let mut decoder = tiff::decoder::Decoder::new(BufReader::new(File::open(image_path).ok()?)).ok()?;
+    let mut pages = 1;
+    // only reads the directory of each page, not its pixels
+    while decoder.more_images() && pages < MAX_TIFF_PAGES {
+        decoder.next_image().ok()?;
+        pages += 1;
+    }
+    (pages > 1).then_some(pages)
+}
+
+/// Decodes page `page` of a multi-page TIFF, counting from 0
+pub fn load_tiff_page(image_path: &Path, page: usize) -> Result<ImageData, LoadImageError> {
+    let mut decoder = tiff::decoder::Decoder::new(BufReader::new(File::open(image_path)?))?;
+    decoder.seek_to_image(page)?;
+    let ifd_offset = decoder.ifd_pointer().ok_or(io::Error::from(io::ErrorKind::InvalidData))?.0;
+    // the `image` crate only decodes the first page, so it gets a header that points at this one
+    let reader = BufReader::new(TiffPageReader::new(File::open(image_path)?, ifd_offset)?);
+    let rgba_img = image::DynamicImage::from_decoder(image::codecs::tiff::TiffDecoder::new(reader)?)?.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    Ok(ImageData {
+        rgba_data: rgba_img.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// Reads a TIFF file with the first directory offset in its header replaced
+struct TiffPageReader {
+    file: File,
+    header: Vec<u8>,
+    position: u64,
+}
+
+impl TiffPageReader {
+    fn new(mut file: File, ifd_offset: u64) -> io::Result<Self> {
+        let mut header = vec![0; 8];
+        file.read_exact(&mut header)?;
+        let big_endian = &header[..2] == b"MM";
+        let version = if big_endian { u16::from_be_bytes([header[2], header[3]]) } else { u16::from_le_bytes([header[2], header[3]]) };
+        if version == 43 {
+            // BigTIFF, the offset takes 8 bytes after the offset size and padding
+            let mut offset = [0; 8];
+            file.read_exact(&mut offset)?;
+            header.extend(if big_endian { ifd_offset.to_be_bytes() } else { ifd_offset.to_le_bytes() });
+        } else {
+            let ifd_offset = u32::try_from(ifd_offset).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            header.truncate(4);
+            header.extend(if big_endian { ifd_offset.to_be_bytes() } else { ifd_offset.to_le_bytes() });
+        }
+        file.rewind()?;
+        Ok(TiffPageReader { file, header, position: 0 })
+    }
+}
+
+impl Read for TiffPageReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = match self.header.get(self.position as usize..) {
+            Some(header) if !header.is_empty() => {
+                let read = header.len().min(buf.len());
+                buf[..read].copy_from_slice(&header[..read]);
+                self.file.seek_relative(read as i64)?;
+                read
+            }
+            _ => self.file.read(buf)?,
+        };
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for TiffPageReader {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.position = self.file.seek(position)?;
+        Ok(self.position)
+    }
+}
+
+/// Width of a pixel relative to its height, from the PNG pHYs chunk or the JFIF density of a JPEG.
+/// `None` when the file doesn't say or the pixels are square
+pub fn pixel_aspect(image_path: &Path) -> Option<f32> {
+    let mut reader = BufReader::new(File::open(image_path).ok()?);
+    let mut signature = [0; 8];
+    reader.read_exact(&mut signature).ok()?;
+    let (horizontal_density, vertical_density) = if signature == *b"\x89PNG\r\n\x1a\n" {
+        png_density(&mut reader).ok()??
+    } else if signature.starts_with(&[0xFF, 0xD8, 0xFF, 0xE0]) && &signature[6..] == b"JF" {
+        jfif_density(&mut reader).ok()??
+    } else {
+        return None;
+    };
+    if horizontal_density == 0 || vertical_density == 0 || horizontal_density == vertical_density {
+        return None;
+    }
+    // more pixels per unit across means narrower pixels
+    Some(vertical_density as f32 / horizontal_density as f32)
+}
+
+/// Pixels per unit across and down from the pHYs chunk, which has to come before the image data
+fn png_density(reader: &mut impl Read) -> io::Result<Option<(u32, u32)>> {
+    loop {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        match &header[4..] {
+            b"pHYs" => {
+                let mut density = [0; 8];
+                reader.read_exact(&mut density)?;
+                return Ok(Some((
+                    u32::from_be_bytes([density[0], density[1], density[2], density[3]]),
+                    u32::from_be_bytes([density[4], density[5], density[6], density[7]]),
+                )));
+            }
+            b"IDAT" | b"IEND" => return Ok(None),
+            // chunk data and its crc
+            _ => skip(reader, u64::from(length) + 4)?,
+        }
+    }
+}
+
+/// Densities from the JFIF header, continuing after the 8 bytes the signature took. With the unit set to 0
+/// they only give the pixel aspect, which is all that's needed here
+fn jfif_density(reader: &mut impl Read) -> io::Result<Option<(u32, u32)>> {
+    // the rest of the identifier, the version and the unit
+    let mut header = [0; 6];
+    reader.read_exact(&mut header)?;
+    if header[..3] != *b"IF\0" {
+        return Ok(None);
+    }
+    let mut density = [0; 4];
+    reader.read_exact(&mut density)?;
+    Ok(Some((u32::from(u16::from_be_bytes([density[0], density[1]])), u32::from(u16::from_be_bytes([density[2], density[3]])))))
+}
+
+fn skip(reader: &mut impl Read, bytes: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(bytes), &mut io::sink())?;
+    if skipped < bytes {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+/// Turns files of some format into pixels. Decoders for formats the `image` crate can't read
+/// are added to [`Decoders`], which asks each one in turn
+pub trait ImageDecoder: Send + Sync {
+    /// Whether this decoder is meant for the file, should be cheap like checking the extension or a few header bytes
+    fn can_decode(&self, image_path: &Path) -> bool;
+    /// Decodes the file, returning [`LoadedImage::Animated`] for files with more than one frame
+    fn decode(&self, image_path: &Path) -> Result<LoadedImage, LoadImageError>;
+}
+
+/// Everything the `image` crate reads, with GIFs decoded as animations and EXR and HDR kept as floats
+pub struct BuiltinDecoder;
+
+impl ImageDecoder for BuiltinDecoder {
+    fn can_decode(&self, image_path: &Path) -> bool {
+        // the header decides, so files with a wrong extension still open
+        image::ImageReader::open(image_path)
+            .and_then(image::ImageReader::with_guessed_format)
+            .is_ok_and(|reader| reader.format().is_some_and(|format| format.reading_enabled()))
+    }
+
+    fn decode(&self, image_path: &Path) -> Result<LoadedImage, LoadImageError> {
+        load_image(image_path)
+    }
+}
+
+/// Decoders tried in order, the first that can decode a file gets to
+pub struct Decoders {
+    decoders: Vec<Box<dyn ImageDecoder>>,
+}
+
+impl Default for Decoders {
+    fn default() -> Self {
+        Decoders::new(false)
+    }
+}
+
+impl Decoders {
+    /// The [`RawDecoder`] in front of the [`BuiltinDecoder`], which would otherwise read RAW files as their TIFF thumbnail.
+    /// `raw_full_render` demosaics RAW files instead of showing their embedded preview
+    pub fn new(raw_full_render: bool) -> Self {
+        Decoders {
+            decoders: vec![Box::new(RawDecoder { full_render: raw_full_render }), Box::new(BuiltinDecoder)],
+        }
+    }
+
+    /// Adds `decoder` in front of the ones registered before, so it can also take over formats the built-in decoder reads
+    pub fn register(&mut self, decoder: Box<dyn ImageDecoder>) {
+        self.decoders.insert(0, decoder);
+    }
+
+    /// Decodes `image_path` with the first decoder that claims it
+    pub fn load(&self, image_path: &Path) -> Result<LoadedImage, LoadImageError> {
+        // the decoders would only report running out of bytes
+        if fs::metadata(image_path)?.len() == 0 {
+            return Err(LoadImageError::Empty);
+        }
+        let decoder = self.decoders.iter()
+            .find(|decoder| decoder.can_decode(image_path))
+            .ok_or(LoadImageError::UnknownFormat)?;
+        decoder.decode(image_path)
+    }
+}
+
+/// Decodes an image and shrinks it to fit inside a `size`×`size` square, for animations this is the first frame
+pub fn load_thumbnail(image_path: &Path, size: u32) -> Result<RgbaImage, LoadImageError> {
+    let img = if crate::raw::is_raw(image_path) {
+        // the embedded preview is plenty for a thumbnail
+        let image = match crate::raw::decode_preview(image_path)? {
+            Some(preview) => preview,
+            None => crate::raw::decode_full(image_path)?,
+        };
+        image::DynamicImage::ImageRgba8(RgbaImage::from_raw(image.width, image.height, image.rgba_data).ok_or(LoadImageError::UnknownFormat)?)
+    } else {
+        image::ImageReader::open(image_path)?.with_guessed_format()?.decode()?
+    };
+    Ok(linear_thumbnail(&img, size))
+}
+
+/// Shrinks `img` to fit inside a `size`×`size` square, averaging premultiplied colors in linear light. Averaging
+/// sRGB values directly darkens fine detail like hatching or text, and lets the colors of transparent pixels bleed in
+fn linear_thumbnail(img: &image::DynamicImage, size: u32) -> RgbaImage {
+    let (width, height) = (img.width().max(1), img.height().max(1));
+    let ratio = (f64::from(size) / f64::from(width)).min(f64::from(size) / f64::from(height));
+    let new_width = ((f64::from(width) * ratio).round() as u32).max(1);
+    let new_height = ((f64::from(height) * ratio).round() as u32).max(1);
+    let premultiply = |[red, green, blue, alpha]: [f32; 4]| [red * alpha, green * alpha, blue * alpha, alpha];
+    let thumbnail = if matches!(img.color(), image::ColorType::Rgb32F | image::ColorType::Rgba32F) {
+        // float formats hold linear values already
+        let pixels = img.to_rgba32f();
+        box_downscale((width, height), (new_width, new_height), |x, y| premultiply(pixels.get_pixel(x, y).0))
+    } else {
+        let to_linear: [f32; 256] = std::array::from_fn(|value| srgb_to_linear(value as f32 / 255.0));
+        let pixels = img.to_rgba8();
+        box_downscale((width, height), (new_width, new_height), |x, y| {
+            let [red, green, blue, alpha] = pixels.get_pixel(x, y).0;
+            premultiply([to_linear[red as usize], to_linear[green as usize], to_linear[blue as usize], f32::from(alpha) / 255.0])
+        })
+    };
+    RgbaImage::from_fn(new_width, new_height, |x, y| {
+        let [red, green, blue, alpha] = thumbnail[(y * new_width + x) as usize];
+        let encode = |channel: f32| {
+            let straight = if alpha > 0.0 { channel / alpha } else { 0.0 };
+            (linear_to_srgb(straight.clamp(0.0, 1.0)) * 255.0).round() as u8
+        };
+        image::Rgba([encode(red), encode(green), encode(blue), (alpha.clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
+
+/// Averages the source pixels falling into each target pixel, row by row. Enlarging repeats pixels instead
+fn box_downscale((width, height): (u32, u32), (new_width, new_height): (u32, u32), pixel: impl Fn(u32, u32) -> [f32; 4]) -> Vec<[f32; 4]> {
+    let span = |index: u32, size: u32, new_size: u32| {
+        let start = (u64::from(index) * u64::from(size) / u64::from(new_size)) as u32;
+        let end = (u64::from(index + 1) * u64::from(size) / u64::from(new_size)) as u32;
+        start..end.max(start + 1).min(size)
+    };
+    let mut averages = Vec::with_capacity(new_width as usize * new_height as usize);
+    for target_y in 0..new_height {
+        let rows = span(target_y, height, new_height);
+        for target_x in 0..new_width {
+            let columns = span(target_x, width, new_width);
+            let mut sum = [0.0; 4];
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    for (total, value) in sum.iter_mut().zip(pixel(x, y)) {
+                        *total += value;
+                    }
+                }
+            }
+            let count = (rows.len() * columns.len()) as f32;
+            averages.push(sum.map(|total| total / count));
+        }
+    }
+    averages
+}
+
+/// Decodes an sRGB channel value from 0.0 to 1.0 to linear light
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// File extensions of the formats the image crate decodes, lowercase and without the dot.
+/// Shared by folder scanning, the open dialog and file association registration so they agree on what luminix opens
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "tiff", "tif", "ico", "tga", "exr", "hdr", "qoi", "ff",
+    "pbm", "pam", "ppm", "pgm", "pnm",
+    #[cfg(feature = "avif")]
+    "avif",
+];
+
+/// File extensions of every format `is_supported_image` accepts, RAW ones included
+pub fn supported_extensions() -> Vec<&'static str> {
+    SUPPORTED_EXTENSIONS.iter().chain(crate::raw::RAW_EXTENSIONS).copied().collect()
+}
+
+/// Whether the file extension belongs to a format that can be decoded
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| SUPPORTED_EXTENSIONS.contains(&extension.to_string_lossy().to_ascii_lowercase().as_str()))
+        || crate::raw::is_raw(path)
+}
+
+/// Folder containing the image, "." for bare file names
+pub fn image_dir(image_path: &Path) -> &Path {
+    image_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."))
+}
+
+/// All decodable images directly inside `dir`, sorted by file name
+pub fn image_paths_in_dir(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_supported_image(path))
+        .collect();
+    paths.sort_by_key(|path| sort_key(path));
+    Ok(paths)
+}
+
+/// Order of images in a folder, case insensitive by file name
+pub fn sort_key(path: &Path) -> Option<String> {
+    path.file_name().map(|name| name.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::png::PngEncoder;
+    use image::{ExtendedColorType, ImageEncoder};
+    use std::{env, process};
+
+    /// A 4×4 GIF of three frames: red all over, then a blue 2×2 patch at (2, 2) that is disposed to the background,
+    /// then a green pixel at (0, 0). Only the first frame covers the whole canvas
+    const OPTIMIZED_GIF: &[u8] = &[
+        0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x04, 0x00, 0x04, 0x00, 0x91, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00,
+        0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x21, 0xF9, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x2C, 0x00, 0x00,
+        0x00, 0x00, 0x04, 0x00, 0x04, 0x00, 0x00, 0x02, 0x04, 0x84, 0x8F, 0x09, 0x05, 0x00, 0x21, 0xF9, 0x04, 0x08,
+        0x00, 0x00, 0x00, 0x00, 0x2C, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x02, 0x02, 0x8C, 0x53,
+        0x00, 0x21, 0xF9, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
+        0x00, 0x02, 0x02, 0x54, 0x01, 0x00, 0x3B,
+    ];
+
+    /// A 4×4 GIF whose first frame is only a red 2×2 patch at (1, 1), followed by a green pixel at (0, 0).
+    /// Neither frame covers the whole canvas
+    const PARTIAL_FIRST_FRAME_GIF: &[u8] = &[
+        0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x04, 0x00, 0x04, 0x00, 0x91, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF,
+        0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x21, 0xF9, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x2C, 0x01, 0x00,
+        0x01, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x02, 0x04, 0x04, 0x41, 0x10, 0x05, 0x00, 0x21, 0xF9, 0x04, 0x04,
+        0x00, 0x00, 0x00, 0x00, 0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x4C, 0x01,
+        0x00, 0x3B,
+    ];
+
+    /// A file in the temp folder that is removed again when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = env::temp_dir().join(format!("luminix-test-{}-{name}", process::id()));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn empty_file_is_an_error() {
+        let file = TempFile::new("empty.png", &[]);
+        assert!(matches!(Decoders::default().load(&file.0), Err(LoadImageError::Empty)));
+    }
+
+    #[test]
+    fn truncated_png_is_an_error() {
+        let image = RgbaImage::from_fn(64, 64, |x, y| image::Rgba([x as u8 * 4, y as u8 * 4, (x ^ y) as u8, 255]));
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png).write_image(&image, 64, 64, ExtendedColorType::Rgba8).unwrap();
+        let file = TempFile::new("truncated.png", &png[..png.len() / 2]);
+        assert!(Decoders::default().load(&file.0).is_err());
+    }
+
+    #[test]
+    fn truncated_gif_is_an_error() {
+        // cut off in the middle of the second frame's image data
+        let file = TempFile::new("truncated.gif", &OPTIMIZED_GIF[..70]);
+        assert!(Decoders::default().load(&file.0).is_err());
+    }
+
+    #[test]
+    fn partial_frames_are_composited() {
+        let file = TempFile::new("optimized.gif", OPTIMIZED_GIF);
+        let Ok(LoadedImage::Animated { frames, .. }) = Decoders::default().load(&file.0) else {
+            panic!("the GIF didn't load as an animation");
+        };
+        assert_eq!(frames.len(), 3);
+        let pixel = |frame: &GifData, x: usize, y: usize| -> [u8; 4] {
+            let offset = (y * frame.width as usize + x) * 4;
+            frame.rgba_data[offset..offset + 4].try_into().unwrap()
+        };
+        // the blue patch covers only part of the canvas, the rest still shows the first frame
+        assert_eq!((frames[1].width, frames[1].height), (4, 4));
+        assert_eq!(pixel(&frames[1], 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frames[1], 3, 3), [0, 0, 255, 255]);
+        // the patch was disposed to the background before the green pixel was drawn
+        assert_eq!(pixel(&frames[2], 0, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&frames[2], 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frames[2], 3, 3)[3], 0);
+    }
+
+    #[test]
+    fn partial_first_frame_is_placed_on_the_canvas() {
+        let file = TempFile::new("partial-first-frame.gif", PARTIAL_FIRST_FRAME_GIF);
+        let Ok(LoadedImage::Animated { frames, .. }) = Decoders::default().load(&file.0) else {
+            panic!("the GIF didn't load as an animation");
+        };
+        // sized by the logical screen rather than the first frame's patch
+        assert_eq!((frames[0].width, frames[0].height), (4, 4));
+        let pixel = |frame: &GifData, x: usize, y: usize| -> [u8; 4] {
+            let offset = (y * frame.width as usize + x) * 4;
+            frame.rgba_data[offset..offset + 4].try_into().unwrap()
+        };
+        // the patch sits at its offset, not in the top left corner
+        assert_eq!(pixel(&frames[0], 0, 0)[3], 0);
+        assert_eq!(pixel(&frames[0], 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frames[0], 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frames[0], 3, 3)[3], 0);
+        assert_eq!((frames[1].width, frames[1].height), (4, 4));
+        assert_eq!(pixel(&frames[1], 0, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&frames[1], 2, 2), [255, 0, 0, 255]);
+    }
+
+    #[cfg(feature = "avif")]
+    fn avif_test_image(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("test_images").join(name)
+    }
+
+    /// Whether two colors match within what lossy AV1 coding leaves off
+    #[cfg(feature = "avif")]
+    fn roughly_equal(a: &[u8], b: [u8; 4]) -> bool {
+        a.iter().zip(b).all(|(&a, b)| a.abs_diff(b) <= 3)
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn avif_still_image_loads() {
+        let Ok(LoadedImage::Static(image)) = Decoders::default().load(&avif_test_image("still.avif")) else {
+            panic!("the AVIF didn't load as a still image");
+        };
+        assert_eq!((image.width, image.height), (16, 16));
+        let center = (8 * 16 + 8) * 4;
+        assert!(roughly_equal(&image.rgba_data[center..center + 4], [255, 0, 0, 255]));
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn avif_sequence_plays_every_frame() {
+        // red, half transparent green, then blue, for 100, 200 and 300 ms, with the alpha in its own track
+        let Ok(LoadedImage::Animated { frames, loop_count }) = Decoders::default().load(&avif_test_image("sequence.avif")) else {
+            panic!("the AVIF didn't load as an animation");
+        };
+        // there's no edit list, so it loops forever
+        assert_eq!(loop_count, None);
+        assert_eq!(frames.len(), 3);
+        let expected = [([255, 0, 0, 255], 100), ([0, 255, 0, 128], 200), ([0, 0, 255, 255], 300)];
+        for (frame, (color, delay)) in frames.iter().zip(expected) {
+            assert_eq!((frame.width, frame.height), (16, 16));
+            let center = (8 * 16 + 8) * 4;
+            assert!(roughly_equal(&frame.rgba_data[center..center + 4], color), "{:?} isn't {color:?}", &frame.rgba_data[center..center + 4]);
+            assert_eq!(frame.delay.numer_denom_ms(), (delay, 1));
+        }
+    }
+
+    #[test]
+    fn thumbnails_average_in_linear_light() {
+        // black and white pixels in equal parts are half as bright as white, which sRGB encodes as 188 rather than 128
+        let checkerboard = RgbaImage::from_fn(4, 4, |x, y| {
+            let value = if (x + y) % 2 == 0 { 0 } else { 255 };
+            image::Rgba([value, value, value, 255])
+        });
+        let thumbnail = linear_thumbnail(&image::DynamicImage::ImageRgba8(checkerboard), 2);
+        assert_eq!(thumbnail.dimensions(), (2, 2));
+        for pixel in thumbnail.pixels() {
+            assert!((187..=188).contains(&pixel[0]), "averaged to {}", pixel[0]);
+            assert_eq!(pixel[3], 255);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Decoding and GPU upload, split out of the viewer so they can be benchmarked outside the event loop.
+//! Everything else lives in the binary.
+#[cfg(feature = "avif")]
+pub mod avif;
+pub mod errors;
+pub mod image_loader;
+pub mod metadata;
+pub mod overlay;
+pub mod raw;
+pub mod wgpu_renderer;
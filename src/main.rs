@@ -6,24 +6,52 @@
     windows_subsystem = "windows"
 )]
 mod settings_window;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
 mod register_file_association;
-mod errors;
-mod wgpu_renderer;
+mod contact_sheet;
+mod cli;
+mod file_watcher;
+mod pan_inertia;
+mod image_action;
+mod recent_files;
+mod export;
+mod sidecar;
+mod sleep_prevention;
+mod single_instance;
+mod background_loader;
+mod slideshow;
 
-use image::{AnimationDecoder, Delay, ImageFormat};
+use luminix::{errors, image_loader, metadata, overlay, wgpu_renderer};
+use background_loader::BackgroundLoader;
+use slideshow::Slideshow;
+use cli::CliArgs;
+use contact_sheet::ContactSheet;
+use errors::{LoadImageError, SlideshowError};
+use file_watcher::FileWatcher;
+use image_action::{ActionContext, ActionRegistry, ImageAction};
+use image_loader::{image_dir, image_paths_in_dir, sort_key, Decoders, FrameCount, ImageData, GifData, HdrImageData, LoadedImage};
+use overlay::Overlay;
+use pan_inertia::PanInertia;
+use recent_files::RecentFiles;
+use settings_window::{Action, ConfigurableSettings, DisplayState, HotfolderRefresh, LastImageRemoved, Overscroll};
+use sidecar::{Flag, Sidecar};
+use std::cell::OnceCell;
 use std::env;
-use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
-use wgpu_renderer::WgpuRenderer;
+use wgpu_renderer::{AlphaMode, Background, Channel, Colormap, Crop, FitMode, Insets, WgpuRenderer};
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
 use winit::event::MouseScrollDelta::LineDelta;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, StartCause, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::PhysicalKey;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 #[cfg(target_os = "windows")]
 use winit::platform::windows::{BackdropType, IconExtWindows, WindowAttributesExtWindows};
+use winit::monitor::MonitorHandle;
 use winit::window::{Icon, Window, WindowId};
 
 #[derive(Default)]
@@ -33,56 +61,262 @@ struct App {
     
     // Image data
     current_image: Option<ImageData>,
+    /// Float pixels of the shown HDR image, `current_image` gets them tone mapped when it's needed
+    current_hdr: Option<HdrImageData>,
     img_width: u32,
     img_height: u32,
     
     gif_frames: Option<Vec<GifData>>, // Store GIF frames
     current_frame_index: u32,
+    /// Frame on screen, `current_frame_index` is already the one after it
+    shown_frame_index: u32,
     next_frame_time: Option<Instant>,
+    paused: bool,
+    /// First and last frame playback loops between, only for the current image
+    loop_start: Option<u32>,
+    loop_end: Option<u32>,
+    /// How often the animation asks to be played, `None` for forever
+    loop_count: Option<u32>,
+    /// Times the animation has played through so far
+    loop_iteration: u32,
     
     panning_data: PanningData,
+    pan_inertia: PanInertia,
+    /// Last step of the image springing back after being pulled past the pan bounds
+    rubber_band_step: Option<Instant>,
+    /// Kept when switching images, so a folder of pages can be read at the same fit
+    fit_mode: FitMode,
+    background: Background,
+    colormap: Colormap,
+    /// Channel shown alone as grayscale, kept when switching images to compare the same channel
+    channel: Channel,
+    /// Pages of a multi-page TIFF, `None` for everything else. The frame keys step through them
+    page_count: Option<usize>,
+    /// Counting from 0
+    page_index: usize,
+    /// Rating and flag of the shown image, from its sidecar
+    rating: u8,
+    flag: Flag,
+    /// GIF frames are shown as what changed since the frame before them
+    frame_diff: bool,
+    /// Sharpening is toggled per session, its strength is a setting
+    sharpen: bool,
+    /// Shows the image inverted as a film negative, the film base is a setting
+    negative: bool,
+    /// Clockwise in degrees, reset for every image
+    rotation: f32,
+    /// Mirrored horizontally and vertically, reset for every image like the rotation
+    flip: (bool, bool),
+    crop: Crop,
+    modifiers: ModifiersState,
+    /// Where the cursor was last seen over the window, `None` until it first enters
+    last_cursor_position: Option<PhysicalPosition<f64>>,
+    /// Trackpad scrolling that didn't add up to a whole zoom step yet, in physical pixels
+    pixel_scroll: f64,
+    /// HDR exposure adjustment in stops
+    exposure: f32,
     
     settings_window: Option<settings_window::SettingsWindow>,
+    settings: ConfigurableSettings,
+    
+    image_path: PathBuf,
+    overlay: Option<Overlay>,
+    contact_sheet: Option<ContactSheet>,
+    recents_open: bool,
+    /// Last title set on the window
+    window_title: String,
+    /// 1-based position of the shown image in its folder and the folder's image count, listed on first use
+    folder_position: OnceCell<Option<(usize, usize)>>,
+    recent_files: RecentFiles,
+    event_loop_proxy: Option<EventLoopProxy<UserEvent>>,
+    
+    cli_args: CliArgs,
+    file_watcher: Option<FileWatcher>,
+    /// Shown over the window instead of the image, e.g. after running out of GPU memory
+    error_screen: Option<ErrorScreen>,
+    /// The image given at launch couldn't be shown, luminix exits with a failure code once closed
+    launch_failed: bool,
+    /// A file is dragged over the window
+    drop_hovered: bool,
+    /// Rows of the info overlay for the shown image, `None` while it's hidden
+    image_info: Option<Vec<(&'static str, String)>>,
+    /// Dropped onto the window and still loading, shows the error screen if it can't be loaded
+    dropped_path: Option<PathBuf>,
+    /// Index of an action waiting for the user to confirm it
+    pending_action: Option<usize>,
+    toast: Option<Toast>,
+    /// Frames of the animation the background loader is decoding, shown until it's done
+    loading_frames: Option<FrameCount>,
+    /// When the seek bar of an animation hides again, `None` while it's hidden
+    seek_bar_until: Option<Instant>,
+    /// The seek bar is being dragged, and whether playback was paused by it
+    seeking: Option<bool>,
+    action_registry: ActionRegistry,
+    focused: bool,
+    /// Whether the system was last asked to stay awake
+    keeping_awake: bool,
+    /// Last keyboard or mouse input on the image window, for dimming after inactivity
+    last_input: Option<Instant>,
+    dimmed: bool,
+    /// Latest size from resize events, applied once at the next redraw instead of for every event
+    pending_resize: Option<PhysicalSize<u32>>,
+    /// Frames are previews until then, moved on with every resize event
+    resize_settle_time: Option<Instant>,
+    /// Opened on first use and kept, on Linux the copied data is only offered while it's alive
+    clipboard: Option<arboard::Clipboard>,
+    decoders: Arc<Decoders>,
+    /// Decodes the images opened after the first, `None` until the event loop exists
+    loader: Option<BackgroundLoader>,
+    /// The shown RAW file is its embedded preview, not the demosaiced sensor data
+    raw_preview: bool,
+    /// Pixel aspect the shown image's header declares, `None` for square pixels
+    pixel_aspect: Option<f32>,
+    minimized: bool,
+    /// Fully covered by other windows, as far as the platform reports it
+    occluded: bool,
+    /// Since when nothing of the window can be seen, playback and rendering wait until it's back
+    hidden_since: Option<Instant>,
+    /// Image shown before this one, with its pixels while they're still decoded, for the swap key
+    previous_image: Option<(PathBuf, Option<LoadedImage>)>,
+    /// Zoom level and pan in fractions of the image size to carry over once the swapped in image is shown
+    swap_view: Option<(i32, (f32, f32))>,
+    /// Direction of the next or previous image key while its image loads, one that fails is stepped over
+    navigation_step: Option<isize>,
+    /// Image the background loader is decoding to replace the shown one. Navigation steps on from here,
+    /// so pressing the next image key again while it loads keeps moving instead of asking for the same image
+    pending_path: Option<PathBuf>,
+    /// Set while `pending_path` is the shown image being decoded again
+    pending_reload: Option<PendingReload>,
+    /// First image tried in place of one that was moved away or deleted. Images that fail to load are stepped over
+    /// until it comes around to this one again, then the folder counts as having nothing left to show
+    replacing_removed: Option<PathBuf>,
+    /// Cycles through the hotfolders with `--slideshow`
+    slideshow: Option<Slideshow>,
+    /// When the slideshow moves on to the next image
+    next_slide_time: Option<Instant>,
 }
 
+/// Stops of exposure per key press
+const EXPOSURE_STEP: f32 = 0.5;
+/// Degrees per key press, small enough to straighten a photo by eye
+const ROTATION_STEP: f32 = 1.0;
+/// Degrees per pixel the mouse moves while rotating by dragging
+const ROTATION_DRAG_SPEED: f32 = 0.1;
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// How long the seek bar stays up after the mouse last moved
+const SEEK_BAR_DURATION: Duration = Duration::from_secs(3);
+/// Data URLs longer than this many bytes get a warning, inlined they bloat pages and some editors choke on the line
+const LARGE_DATA_URL: usize = 1024 * 1024;
+/// How long after the last resize event a drag counts as finished and full quality frames are drawn again
+const RESIZE_SETTLE: Duration = Duration::from_millis(150);
+/// Room kept free at each monitor edge when sizing the window to the screen, for taskbars, docks and panels, in logical pixels.
+/// Only a guess for platforms other than Windows, where winit doesn't report the work area they leave
+#[cfg(not(target_os = "windows"))]
+const SCREEN_EDGE_MARGIN: f64 = 48.0;
+/// Window size when the image given at launch can't be loaded, big enough for the error message
+const ERROR_WINDOW_SIZE: (u32, u32) = (640, 360);
+/// Trackpad scroll distance in physical pixels that zooms by one step, like a notch of a mouse wheel
+const PIXEL_SCROLL_STEP: f64 = 50.0;
+/// Fraction the image is darkened by after the idle time from the settings
+const IDLE_DIM: f32 = 0.7;
+/// Fraction of the mouse movement the image follows once it's pulled past the pan bounds
+const RUBBER_BAND_RESISTANCE: f32 = 0.3;
+/// Rate per second at which a pulled image springs back, the remaining distance shrinks by e^-rate each second
+const RUBBER_BAND_RETURN_SPEED: f32 = 12.0;
+
 #[derive(Debug, Default, Copy, Clone)]
 struct PanningData {
     panning: bool,
     pan_offset: PhysicalPosition<f32>,
     zoom_level: i32,
+    /// Zoomed to one image pixel per screen pixel instead of `zoom_level`, kept that way through resizes
+    actual_size: bool,
 }
 
-#[derive(Clone)]
-#[allow(dead_code)]
-struct ImageData {
-    rgba_data: Vec<u8>,
-    width: u32,
-    height: u32,
-}
-
-#[derive(Debug, Clone)]
-struct GifData {
-    rgba_data: Vec<u8>,
-    width: u32,
-    height: u32,
-    delay: Delay,
+/// Events sent to the event loop from other threads
+#[derive(Debug)]
+pub enum UserEvent {
+    /// A contact sheet thumbnail finished decoding
+    ThumbnailReady,
+    /// The shown image was modified on disk, only sent in watch mode
+    ImageChanged,
+    /// A GIF export finished `done` of its `total` frames
+    ExportProgress { done: usize, total: usize },
+    /// A GIF export ended, with the message to show
+    ExportFinished(String),
+    /// Another launch of luminix asked this instance to show an image
+    OpenPath(PathBuf),
+    /// The background loader started decoding an animation of `frames` frames, request number `generation`
+    AnimationLoading { generation: u64, frames: FrameCount },
+    /// The background loader finished decoding an image
+    ImageLoaded,
+    /// Images were added to or removed from the slideshow's hotfolders
+    HotfoldersChanged,
 }
 
-
-impl ApplicationHandler for App {
-    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
-        if let StartCause::ResumeTimeReached { .. } = cause {
-            self.gif_next_frame(event_loop, true);
+impl ApplicationHandler<UserEvent> for App {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        if !self.paused && self.hidden_since.is_none() && self.next_frame_time.is_some_and(|next_frame_time| next_frame_time <= now) {
+            self.gif_next_frame();
+        }
+        if self.next_slide_time.is_some_and(|next_slide_time| next_slide_time <= now) {
+            self.next_slide();
+        }
+        if self.seeking.is_none() && self.seek_bar_until.is_some_and(|until| until <= now) {
+            self.seek_bar_until = None;
+            self.window.as_ref().unwrap().request_redraw();
+        }
+        if self.toast.as_ref().is_some_and(|toast| toast.until <= now) {
+            self.toast = None;
+            self.window.as_ref().unwrap().request_redraw();
+        }
+        if let Some(offset) = self.pan_inertia.step(now) {
+            let (moved_x, moved_y) = self.pan_by(offset);
+            self.pan_inertia.hit_edge(!moved_x, !moved_y);
+        }
+        if self.resize_settle_time.is_some_and(|settle_time| settle_time <= now) {
+            // the drag is over, draws the last size at full quality
+            self.resize_settle_time = None;
+            if let Some(renderer) = &mut self.renderer {
+                renderer.set_preview(false);
+            }
+            self.window.as_ref().unwrap().request_redraw();
         }
+        self.update_keep_awake();
+        let dim_time = self.dim_time();
+        if dim_time.is_some_and(|dim_time| dim_time <= now) != self.dimmed {
+            // also undims right away when dimming was turned off in the settings
+            self.set_dimmed(!self.dimmed);
+        }
+        let dim_time = dim_time.filter(|_| !self.dimmed);
+        let returning = if self.settings.overscroll == Overscroll::RubberBand && !self.panning_data.panning && !self.pan_inertia.is_gliding() {
+            self.rubber_band_return(now)
+        } else {
+            self.rubber_band_step = None;
+            false
+        };
+        
+        event_loop.set_control_flow(if self.pan_inertia.is_gliding() || returning {
+            ControlFlow::Poll
+        } else if let Some(wake_time) = self.next_frame_time.filter(|_| !self.paused && self.hidden_since.is_none()).into_iter()
+            .chain(self.toast.as_ref().map(|toast| toast.until))
+            .chain(self.seek_bar_until.filter(|_| self.seeking.is_none()))
+            .chain(dim_time.filter(|dim_time| *dim_time > now))
+            .chain(self.resize_settle_time)
+            .chain(self.next_slide_time)
+            .min() {
+            ControlFlow::WaitUntil(wake_time)
+        } else {
+            ControlFlow::Wait
+        });
     }
     
     // init function
-    #[allow(clippy::too_many_lines)]
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        // get args
-        let args: Vec<String> = env::args().collect();
-        let image_path = &args[1];
-        dbg!(image_path);
+        let image_path = self.cli_args.image_path.clone().expect("main asks for an image path when none is given");
+        // the idle time for dimming counts from startup until the first input
+        self.last_input = Some(Instant::now());
         // load icon if on linux
         let _icon_width: u16; let _icon_height: u16; let _icon_image_bytes: &[u8];
         #[cfg(target_os = "linux")]
@@ -96,122 +330,180 @@ impl ApplicationHandler for App {
             _icon_image_bytes = &icon_raw_bytes[18..];
         }
         // loading image -- load image with image crate
-        let img_reader = image::ImageReader::open(image_path).unwrap();
-        let format = img_reader.with_guessed_format().unwrap().format().unwrap();
-        if format == ImageFormat::Gif {
-            // Load GIF and extract frames
-            let gif_reader = image::codecs::gif::GifDecoder::new(BufReader::new(std::fs::File::open(image_path).unwrap())).unwrap();
-            let frames = gif_reader.into_frames();
-            let frames = frames.collect_frames().expect("Failed to collect GIF frames");
-            let gif_frames: Vec<GifData> = frames.iter().map(|frame| {
-                let delay = frame.delay();
-                let dyn_img = image::DynamicImage::ImageRgba8(frame.buffer().clone());
-                let rgba = dyn_img.to_rgba8().into_raw();
-                GifData {
-                    rgba_data: rgba,
-                    width: frame.buffer().width(),
-                    height: frame.buffer().height(),
-                    delay
-                }
-            }).collect();
-            println!("this is gif");
-            if let Some(first_frame) = gif_frames.first() {
-                let (img_width, img_height) = (first_frame.width, first_frame.height);
-                dbg!(img_width, img_height);
-                #[cfg(target_os = "windows")]
-                let window_attributes = Window::default_attributes()
-                    .with_min_inner_size(LogicalSize::new(img_width, img_height))
-                    .with_inner_size(LogicalSize::new(img_width, img_height))
-                    .with_active(true)
-                    .with_transparent(true)
-                    .with_title(format!("luminix ({image_path})"))
-                    .with_window_icon(Icon::from_resource(1, Some(PhysicalSize::new(128, 128))).ok())
-                    .with_taskbar_icon(Icon::from_resource(1, Some(PhysicalSize::new(128, 128))).ok())
-                    .with_system_backdrop(BackdropType::TransientWindow);
-                #[cfg(target_os = "linux")]
-                let window_attributes = Window::default_attributes()
-                    .with_min_inner_size(LogicalSize::new(img_width, img_height))
-                    .with_inner_size(LogicalSize::new(img_width, img_height))
-                    .with_active(true)
-                    .with_transparent(true)
-                    .with_title(format!("luminix ({image_path})"))
-                    .with_window_icon(Icon::from_rgba(_icon_image_bytes.to_vec(), _icon_width.into(), _icon_height.into()).ok());
-                let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
-                
-                // Initialize wgpu renderer
-                let mut renderer = pollster::block_on(WgpuRenderer::new(window.clone()));
-                renderer.load_texture(&first_frame.rgba_data, first_frame.width, first_frame.height);
-                
-                self.window = Some(window);
-                self.renderer = Some(renderer);
-                self.gif_frames = Some(gif_frames.clone());
-                self.current_image = Some(ImageData {
-                    rgba_data: first_frame.rgba_data.clone(),
-                    width: first_frame.width,
-                    height: first_frame.height,
-                });
-                self.img_width = img_width;
-                self.img_height = img_height;
-                self.current_frame_index = 0;
-                self.next_frame_time = Some(Instant::now() + first_frame.delay.into());
-                event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_frame_time.unwrap()));
-                self.settings_window = Some(settings_window::SettingsWindow::new(event_loop));
+        let loaded_image = self.decoders.load(&image_path);
+        let (img_width, img_height) = match &loaded_image {
+            Ok(loaded_image) => {
+                let (img_width, img_height) = loaded_image.dimensions();
+                log::info!("Loading: {}, {img_width}x{img_height}", image_path.display());
+                (img_width, img_height)
             }
-            return;
-        }
-        
-        // Load regular image
-        let img = image::open(image_path).expect("failed to load image");
-        let rgba_img = img.to_rgba8();
-        let (img_width, img_height) = rgba_img.dimensions();
-        let rgba_data = rgba_img.into_raw();
-        
-        println!("Loading: {image_path}, {img_width}x{img_height}");
-        
-        dbg!(img_width, img_height);
+            Err(e) => {
+                log::error!("Failed to load {}. Error message: {e}", image_path.display());
+                ERROR_WINDOW_SIZE
+            }
+        };
         
         // creating window
+        let activate = self.settings.activate_on_open && !self.cli_args.no_activate;
         #[cfg(target_os = "windows")]
-        let window_attributes = Window::default_attributes()
+        let mut window_attributes = Window::default_attributes()
             .with_min_inner_size(LogicalSize::new(img_width, img_height))
             .with_inner_size(LogicalSize::new(img_width, img_height))
-            .with_active(true)
+            .with_active(activate)
             .with_transparent(true)
-            .with_title(format!("luminix ({image_path})"))
+            .with_title("luminix")
             .with_taskbar_icon(Icon::from_resource(1, Some(PhysicalSize::new(128, 128))).ok())
             .with_window_icon(Icon::from_resource(1, Some(PhysicalSize::new(128, 128))).ok())
             .with_system_backdrop(BackdropType::TransientWindow);
         #[cfg(target_os = "linux")]
-        let window_attributes = Window::default_attributes()
+        let mut window_attributes = Window::default_attributes()
             .with_min_inner_size(LogicalSize::new(img_width, img_height))
             .with_inner_size(LogicalSize::new(img_width, img_height))
-            .with_active(true)
+            .with_active(activate)
             .with_transparent(true)
-            .with_title(format!("luminix ({image_path})"))
+            .with_title("luminix")
             .with_window_icon(Icon::from_rgba(_icon_image_bytes.to_vec(), _icon_width.into(), _icon_height.into()).ok());
-        let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
+        let monitor = event_loop.primary_monitor().or_else(|| event_loop.available_monitors().next());
+        if let Some((size, position)) = monitor.and_then(|monitor| screen_share_geometry(&monitor, (img_width, img_height), self.settings.window_screen_percent)) {
+            window_attributes.inner_size = Some(size.into());
+            window_attributes.min_inner_size = None;
+            window_attributes.position = Some(position.into());
+        }
+        if let Some((width, height)) = self.settings.last_window_size.filter(|_| self.settings.remember_window_geometry) {
+            let size = PhysicalSize::new(width, height);
+            window_attributes.inner_size = Some(size.into());
+            window_attributes.min_inner_size = None;
+            if let Some((x, y)) = self.settings.last_window_position {
+                let monitors: Vec<_> = event_loop.available_monitors().collect();
+                window_attributes.position = Some(on_screen_position(&monitors, PhysicalPosition::new(x, y), size).into());
+            }
+        }
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(e) => {
+                self.startup_failed(event_loop, &format!("Couldn't open a window: {e}"));
+                return;
+            }
+        };
         
         // Initialize wgpu renderer
-        let mut renderer = pollster::block_on(WgpuRenderer::new(window.clone()));
-        renderer.load_texture(&rgba_data, img_width, img_height);
+        let visual_swapchain = self.settings.visual_swapchain && !self.cli_args.no_visual_swapchain;
+        let renderer = match pollster::block_on(WgpuRenderer::new(window.clone(), visual_swapchain)) {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                self.startup_failed(event_loop, &format!("Couldn't set up the GPU to draw the image: {e}"));
+                return;
+            }
+        };
+        self.overlay = Some(Overlay::new(&window, renderer.max_texture_side()));
         
         self.window = Some(window);
         self.renderer = Some(renderer);
-        self.current_image = Some(ImageData {
-            rgba_data,
-            width: img_width,
-            height: img_height,
-        });
-        self.img_width = img_width;
-        self.img_height = img_height;
-        self.settings_window = Some(settings_window::SettingsWindow::new(event_loop));
+        // remembered from the last session, the defaults otherwise
+        let renderer = self.renderer.as_mut().unwrap();
+        renderer.set_fit_mode(self.fit_mode);
+        renderer.set_colormap(self.colormap);
+        match loaded_image {
+            Ok(loaded_image) => {
+                self.show_image(image_path, loaded_image);
+                self.apply_cli_view();
+            }
+            Err(e) => {
+                self.launch_failed = true;
+                self.show_load_error(image_path, &e);
+            }
+        }
+        if self.slideshow.is_some() {
+            self.next_slide_time = Some(Instant::now() + self.slide_interval());
+        }
+    }
+    
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::ThumbnailReady => {
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            UserEvent::ImageChanged => {
+                if !self.image_path.exists() {
+                    // deleted mid-save, the watcher fires again once it's recreated
+                    return;
+                }
+                // an image on its way replaces this one anyway
+                if self.pending_path.is_none() {
+                    self.reload_image(true, false);
+                }
+            }
+            UserEvent::ExportProgress { done, total } => self.show_toast(format!("Exporting frame {done} of {total}")),
+            UserEvent::ExportFinished(message) => self.show_toast(message),
+            UserEvent::OpenPath(path) => {
+                self.open_path(&path);
+                if let Some(window) = self.window.as_ref().filter(|_| self.settings.activate_on_open) {
+                    window.set_minimized(false);
+                    window.focus_window();
+                }
+            }
+            UserEvent::HotfoldersChanged => self.refresh_slideshow(),
+            UserEvent::AnimationLoading { generation, frames } => {
+                if self.loader.as_ref().is_some_and(|loader| loader.is_latest(generation)) {
+                    self.loading_frames = Some(frames);
+                    self.update_title();
+                    self.window.as_ref().unwrap().request_redraw();
+                }
+            }
+            UserEvent::ImageLoaded => {
+                let Some((image_path, result)) = self.loader.as_ref().and_then(BackgroundLoader::take_loaded) else {
+                    return;
+                };
+                if self.loading_frames.take().is_some() {
+                    self.update_title();
+                    self.window.as_ref().unwrap().request_redraw();
+                }
+                self.pending_path = None;
+                let replacing_removed = self.replacing_removed.take();
+                match result {
+                    Ok(loaded_image) => {
+                        self.navigation_step = None;
+                        self.dropped_path = None;
+                        match self.pending_reload.take() {
+                            Some(reload) => self.apply_reload(loaded_image, reload.keep_view),
+                            None => self.show_image(image_path, loaded_image),
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(reload) = self.pending_reload.take() {
+                            // the old image stays up, only say why it didn't change
+                            log::error!("Failed to reload {}. Error message: {e}", image_path.display());
+                            if reload.report_errors {
+                                self.show_toast(format!("Couldn't reload: {e}"));
+                            }
+                            return;
+                        }
+                        log::error!("Failed to load {}. Error message: {e}", image_path.display());
+                        self.swap_view = None;
+                        if self.dropped_path.take().is_some_and(|dropped_path| dropped_path == image_path) {
+                            self.show_load_error(image_path.clone(), &e);
+                        }
+                        if let Some(step) = self.navigation_step.take() {
+                            let after_removal = replacing_removed.is_some();
+                            self.replacing_removed = replacing_removed;
+                            if !self.step_image_from(&image_path, step) && after_removal {
+                                self.no_images_left(event_loop);
+                            }
+                        }
+                    }
+                }
+            }
+        }
     }
     #[allow(clippy::too_many_lines)]
     fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
-        let window_ref = self.window.as_ref().unwrap();
-        let settings_window = self.settings_window.as_mut().unwrap();
+        // cloned so handlers below can still borrow self mutably
+        let window = self.window.clone().unwrap();
+        let window_ref = window.as_ref();
         
-        if id == settings_window.window.id() {
+        if let Some(settings_window) = self.settings_window.as_mut().filter(|settings_window| settings_window.window.id() == id) {
             let response = settings_window.on_window_event(&event);
             if response.repaint {
                 settings_window.window.request_redraw();
@@ -221,233 +513,2183 @@ impl ApplicationHandler for App {
                     settings_window.state.on_mouse_motion((position.x, position.y));
                 }
                 WindowEvent::RedrawRequested => {
-                    settings_window.on_redraw();
+                    settings_window.on_redraw(&mut self.settings);
                 }
                 _ => (),
             }
         }
         if id == window_ref.id() {
             match event {
-                WindowEvent::KeyboardInput {event, ..} => {
-                    if event.state.is_pressed() {
-                        if let PhysicalKey::Code(code) = event.physical_key {
-                            if Some(code) == settings_window.get_settings().keys.settings.get_keycode() {
-                                self.settings_window.as_ref().unwrap().show();
-                            } else if Some(code) == settings_window.get_settings().keys.pause.get_keycode() {
-                                if self.gif_frames.is_some() {
-                                    match event_loop.control_flow() {
-                                        ControlFlow::WaitUntil(_) => {event_loop.set_control_flow(ControlFlow::Wait)}
-                                        ControlFlow::Wait => {event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_frame_time.unwrap()))}
-                                        ControlFlow::Poll => {}
-                                    }
+                WindowEvent::KeyboardInput { .. } | WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } | WindowEvent::CursorMoved { .. } => {
+                    self.last_input = Some(Instant::now());
+                    if self.dimmed {
+                        self.set_dimmed(false);
+                    }
+                }
+                WindowEvent::Focused(focused) => self.focused = focused,
+                _ => {}
+            }
+            if matches!(event, WindowEvent::CursorMoved { .. }) && self.gif_frames.is_some() && !self.panning_data.panning {
+                if self.seek_bar_until.is_none() {
+                    window_ref.request_redraw();
+                }
+                self.seek_bar_until = Some(Instant::now() + SEEK_BAR_DURATION);
+            }
+            // panels cover the whole window, so the image underneath shouldn't react to the mouse
+            let panel_open = self.contact_sheet.is_some() || self.recents_open;
+            if self.overlay_active() {
+                let response = self.overlay.as_mut().unwrap().on_window_event(window_ref, &event);
+                if response.repaint {
+                    window_ref.request_redraw();
+                }
+                if response.consumed {
+                    return;
+                }
+            }
+            match event {
+                // while a confirmation prompt is up, keys only go to the prompt
+                WindowEvent::KeyboardInput {event, ..} if event.state.is_pressed() && self.pending_action.is_none() => {
+                    if let PhysicalKey::Code(code) = event.physical_key {
+                        if Some(code) == self.settings.keys.settings.get_keycode() {
+                            // created on first use, so viewing an image never pays for the settings window's wgpu setup
+                            let settings_window = self.settings_window.get_or_insert_with(|| {
+                                let mut settings_window = settings_window::SettingsWindow::new(event_loop);
+                                settings_window.current_image_path = Some(self.image_path.clone());
+                                settings_window.current_image_dimensions = (self.img_width, self.img_height);
+                                settings_window.registered_actions = self.action_registry.labels();
+                                settings_window
+                            });
+                            settings_window.show();
+                        } else if Some(code) == self.settings.keys.contact_sheet.get_keycode() {
+                            self.toggle_contact_sheet();
+                        } else if Some(code) == self.settings.keys.recents.get_keycode() {
+                            self.recents_open = !self.recents_open;
+                            self.contact_sheet = None;
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.loop_start.get_keycode() {
+                            if self.gif_frames.is_some() {
+                                self.loop_start = Some(self.shown_frame_index);
+                                if self.loop_end.is_some_and(|loop_end| loop_end < self.shown_frame_index) {
+                                    self.loop_end = None;
+                                }
+                            }
+                        } else if Some(code) == self.settings.keys.loop_end.get_keycode() {
+                            if self.gif_frames.is_some() {
+                                self.loop_end = Some(self.shown_frame_index);
+                                if self.loop_start.is_some_and(|loop_start| loop_start > self.shown_frame_index) {
+                                    self.loop_start = None;
+                                }
+                            }
+                        } else if Some(code) == self.settings.keys.clear_loop.get_keycode() {
+                            self.loop_start = None;
+                            self.loop_end = None;
+                        } else if Some(code) == self.settings.keys.zoom_in.get_keycode() {
+                            self.zoom_to(self.panning_data.zoom_level - 1, self.keyboard_zoom_focus());
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.zoom_out.get_keycode() {
+                            self.zoom_to(self.panning_data.zoom_level + 1, self.keyboard_zoom_focus());
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.colormap.get_keycode() {
+                            self.colormap = self.colormap.next();
+                            if let Some(renderer) = &mut self.renderer {
+                                renderer.set_colormap(self.colormap);
+                            }
+                            self.show_toast(format!("False colors: {}", self.colormap));
+                        } else if Some(code) == self.settings.keys.next_image.get_keycode() {
+                            self.navigate(1);
+                        } else if Some(code) == self.settings.keys.prev_image.get_keycode() {
+                            self.navigate(-1);
+                        } else if Some(code) == self.settings.keys.smooth_filtering.get_keycode() {
+                            // like changing it in the settings window, saved along with the other settings there
+                            self.settings.smooth_filtering = !self.settings.smooth_filtering;
+                            self.show_toast(String::from(if self.settings.smooth_filtering { "Smooth filtering" } else { "Nearest filtering" }));
+                        } else if Some(code) == self.settings.keys.image_info.get_keycode() {
+                            self.image_info = match self.image_info {
+                                Some(_) => None,
+                                None => Some(self.read_image_info()),
+                            };
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.swap_image.get_keycode() {
+                            self.swap_with_previous_image();
+                        } else if Some(code) == self.settings.keys.channel.get_keycode() {
+                            self.channel = self.channel.next();
+                            if let Some(renderer) = &mut self.renderer {
+                                renderer.set_channel(self.channel);
+                            }
+                            self.show_toast(format!("Channel: {}", self.channel));
+                        } else if Some(code) == self.settings.keys.frame_diff.get_keycode() && self.gif_frames.is_some() {
+                            self.frame_diff = !self.frame_diff;
+                            self.load_frame_texture(self.shown_frame_index as usize);
+                            self.show_toast(String::from(if self.frame_diff { "Showing changes from the previous frame" } else { "Showing frames" }));
+                        } else if Some(code) == self.settings.keys.rotate_cw.get_keycode() {
+                            self.rotate_by(ROTATION_STEP);
+                        } else if Some(code) == self.settings.keys.rotate_ccw.get_keycode() {
+                            self.rotate_by(-ROTATION_STEP);
+                        } else if Some(code) == self.settings.keys.rotate_quarter.get_keycode() {
+                            // on to the next quarter turn, an angle in between snaps to it
+                            self.rotate_by(90.0 - self.rotation.rem_euclid(90.0));
+                        } else if Some(code) == self.settings.keys.flip_horizontal.get_keycode() {
+                            self.set_flip((!self.flip.0, self.flip.1));
+                        } else if Some(code) == self.settings.keys.flip_vertical.get_keycode() {
+                            self.set_flip((self.flip.0, !self.flip.1));
+                        } else if Some(code) == self.settings.keys.save_rotated.get_keycode() {
+                            self.save_rotated();
+                        } else if Some(code) == self.settings.keys.export_loop_range.get_keycode() {
+                            self.export_loop_range();
+                        } else if Some(code) == self.settings.keys.copy_view.get_keycode() {
+                            self.copy_view();
+                        } else if Some(code) == self.settings.keys.copy_image.get_keycode() {
+                            self.copy_image();
+                        } else if Some(code) == self.settings.keys.paste_image.get_keycode() {
+                            self.paste_image();
+                        } else if Some(code) == self.settings.keys.copy_data_url.get_keycode() {
+                            self.copy_data_url();
+                        } else if Some(code) == self.settings.keys.save_sidecar.get_keycode() {
+                            self.save_sidecar();
+                        } else if let Some(rating) = self.rating_for_key(code) {
+                            self.mark(|sidecar| sidecar.rating = rating);
+                        } else if Some(code) == self.settings.keys.pick.get_keycode() {
+                            // pressing it again takes the flag back
+                            let flag = if self.flag == Flag::Pick { Flag::None } else { Flag::Pick };
+                            self.mark(|sidecar| sidecar.flag = flag);
+                        } else if Some(code) == self.settings.keys.reject.get_keycode() {
+                            let flag = if self.flag == Flag::Reject { Flag::None } else { Flag::Reject };
+                            self.mark(|sidecar| sidecar.flag = flag);
+                        } else if Some(code) == self.settings.keys.next_picked.get_keycode() {
+                            self.open_next_marked(false);
+                        } else if Some(code) == self.settings.keys.next_unmarked.get_keycode() {
+                            self.open_next_marked(true);
+                        } else if Some(code) == self.settings.keys.sharpen.get_keycode() {
+                            self.sharpen = !self.sharpen;
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.negative.get_keycode() {
+                            self.negative = !self.negative;
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.reload.get_keycode() {
+                            self.reload_image(self.settings.reload_keeps_view, true);
+                        } else if Some(code) == self.settings.keys.fit.get_keycode() {
+                            self.fit_to_window();
+                        } else if Some(code) == self.settings.keys.actual_size.get_keycode() {
+                            self.zoom_to_actual_size();
+                        } else if Some(code) == self.settings.keys.fit_width.get_keycode() {
+                            self.toggle_fit_mode(FitMode::Width);
+                        } else if Some(code) == self.settings.keys.fit_height.get_keycode() {
+                            self.toggle_fit_mode(FitMode::Height);
+                        } else if Some(code) == self.settings.keys.exposure_up.get_keycode() {
+                            self.adjust_exposure(EXPOSURE_STEP);
+                        } else if Some(code) == self.settings.keys.exposure_down.get_keycode() {
+                            self.adjust_exposure(-EXPOSURE_STEP);
+                        } else if Some(code) == self.settings.keys.background.get_keycode() {
+                            self.background = match self.background {
+                                Background::Transparent => Background::Checkerboard,
+                                Background::Checkerboard => Background::Solid,
+                                Background::Solid => Background::Transparent,
+                            };
+                            if let Some(renderer) = &mut self.renderer {
+                                renderer.set_background(self.background);
+                            }
+                            window_ref.request_redraw();
+                        } else if Some(code) == self.settings.keys.quit.get_keycode() {
+                            if self.settings.quit_key_enabled {
+                                if panel_open {
+                                    // backs out of the contact sheet or recents first, the next press quits
+                                    self.contact_sheet = None;
+                                    self.recents_open = false;
+                                    window_ref.request_redraw();
+                                } else if !self.panning_data.panning {
+                                    // ignore while panning so a stray press doesn't close the window mid-drag
+                                    event_loop.exit();
                                 }
-                            } else if Some(code) == settings_window.get_settings().keys.next_frame.get_keycode() {
-                                if self.gif_frames.is_some() && event_loop.control_flow() == ControlFlow::Wait {
-                                    // Paused
-                                    self.gif_next_frame(event_loop, false);
+                            }
+                        } else if Some(code) == self.settings.keys.pause.get_keycode() {
+                            if let Some(gif_frames) = &self.gif_frames {
+                                if self.next_frame_time.is_none() {
+                                    // finished playing, so it starts over instead
+                                    self.current_frame_index = self.loop_bounds(gif_frames.len()).0;
+                                    self.loop_iteration = 0;
+                                    self.paused = false;
+                                    self.gif_next_frame();
+                                } else {
+                                    self.paused = !self.paused;
                                 }
-                            } else if Some(code) == settings_window.get_settings().keys.prev_frame.get_keycode() && self.gif_frames.is_some() && event_loop.control_flow() == ControlFlow::Wait {
-                                // Paused
-                                self.gif_prev_frame(event_loop, false,);
+                                // shows or hides the frame counter
+                                window_ref.request_redraw();
                             }
-                            // actions
-                            for (action, key) in self.settings_window.as_ref().unwrap().configurable_settings.actions.iter().zip(self.settings_window.as_ref().unwrap().configurable_settings.keys.actions.iter()) {
-                                if Some(code) == key.get_keycode() {
-                                    let _ = action.run_action();
+                        } else if Some(code) == self.settings.keys.next_frame.get_keycode() {
+                            let step = self.frame_key_step();
+                            if self.page_count.is_some() {
+                                self.step_page(step);
+                            } else if self.gif_frames.is_some() && self.paused {
+                                self.step_frames(step);
+                            }
+                        } else if Some(code) == self.settings.keys.prev_frame.get_keycode() {
+                            let step = self.frame_key_step();
+                            if self.page_count.is_some() {
+                                self.step_page(-step);
+                            } else if self.gif_frames.is_some() && self.paused {
+                                self.step_frames(-step);
+                            }
+                        }
+                        // actions
+                        for index in 0..self.settings.actions.len() {
+                            if Some(code) == self.settings.keys.actions[index].get_keycode() {
+                                if !self.has_image_file() {
+                                    self.show_toast(String::from("Actions need an image file, pasted images have none"));
+                                } else if self.settings.confirm_actions[index] {
+                                    self.pending_action = Some(index);
+                                    window_ref.request_redraw();
+                                } else {
+                                    self.run_action(event_loop, index);
                                 }
                             }
                         }
                     }
                 }
                 WindowEvent::CloseRequested => {
-                    println!("The close button was pressed; stopping");
+                    log::debug!("The close button was pressed; stopping");
+                    self.shutdown_gpu();
                     event_loop.exit();
 
                 },
-                WindowEvent::MouseInput {state, button, .. } => {
-                    // dbg!(button, state);
-
-                    if button == MouseButton::Right {
-                        match state {
-                            ElementState::Pressed => {
-                                self.panning_data.panning = true;
-                                let (x, y): (u32, u32) = window_ref.inner_size().into();
-                                window_ref.set_cursor_position(PhysicalPosition::new(x/2, y/2)).expect("Error setting cursor position");
-                                window_ref.set_cursor_visible(false);
+                WindowEvent::MouseInput {state, button: MouseButton::Right, .. } if !panel_open => {
+                    match state {
+                        ElementState::Pressed => {
+                            self.panning_data.panning = true;
+                            self.pan_inertia.stop();
+                            let (x, y): (u32, u32) = window_ref.inner_size().into();
+                            window_ref.set_cursor_position(PhysicalPosition::new(x/2, y/2)).expect("Error setting cursor position");
+                            window_ref.set_cursor_visible(false);
+                        }
+                        ElementState::Released => {
+                            self.panning_data.panning = false;
+                            window_ref.set_cursor_visible(true);
+                            if self.settings.pan_inertia {
+                                self.pan_inertia.release();
+                            } else {
+                                self.pan_inertia.stop();
                             }
-                            ElementState::Released => {
-                                self.panning_data.panning = false;
-                                window_ref.set_cursor_visible(true);
 
-                            }
                         }
-
                     }
                 }
                 WindowEvent::Resized(new_size) => {
-                    // self.panning_data.pan_offset = PhysicalPosition::new(0, 0);
-                    // self.panning_data.zoom_level = 0;
+                    // minimizing shrinks the window to nothing on most platforms
+                    self.minimized = new_size.width == 0 || new_size.height == 0;
+                    self.update_hidden();
+                    // a drag sends many of these per frame, reconfiguring the surface for each one only adds up work
+                    self.pending_resize = Some(new_size);
+                    self.resize_settle_time = Some(Instant::now() + RESIZE_SETTLE);
+                    if let Some(renderer) = &mut self.renderer {
+                        renderer.set_preview(true);
+                    }
                     window_ref.request_redraw();
+                }
+                WindowEvent::HoveredFile(_) => {
+                    self.drop_hovered = true;
+                    window_ref.request_redraw();
+                }
+                WindowEvent::HoveredFileCancelled => {
+                    self.drop_hovered = false;
+                    window_ref.request_redraw();
+                }
+                WindowEvent::DroppedFile(path) => {
+                    self.drop_hovered = false;
+                    self.open_path(&path);
+                    self.dropped_path = Some(path);
+                    window_ref.request_redraw();
+                }
+                WindowEvent::ScaleFactorChanged {..} => {
+                    // moved to a monitor with a different dpi, the physical size changes with it
                     if let Some(renderer) = &mut self.renderer {
-                        renderer.resize(new_size);
-                        // Render immediately during resize for real-time updates
-                        // let _ = renderer.render();
+                        renderer.resize(window_ref.inner_size());
+                    }
+                    if self.panning_data.actual_size {
+                        self.update_view();
                     }
+                    window_ref.request_redraw();
                 }
-                WindowEvent::MouseWheel {delta, ..} => {
-                    dbg!(delta);
-
-                    let max_zoom_level = 100;
+                WindowEvent::MouseWheel {delta, ..} if !panel_open => {
+                    log::trace!("Scrolled by {delta:?}");
                     match delta {
                         LineDelta(_, y) => {
-                            if !y.is_sign_positive() {
-                                if self.panning_data.zoom_level < max_zoom_level {
-                                    self.panning_data.zoom_level += 1;
-                                }
-                            } else if self.panning_data.zoom_level > -max_zoom_level {
-                                self.panning_data.zoom_level -= 1;
-                            }
-                            
-                            // Update renderer zoom
-                            if let Some(renderer) = &mut self.renderer {
-                                let image_aspect = self.img_width as f32 / self.img_height as f32;
-                                renderer.set_zoom(self.panning_data.zoom_level, image_aspect);
-                            }
+                            let zoom_out = if self.settings.reverse_zoom { y.is_sign_positive() } else { !y.is_sign_positive() };
+                            let zoom_level = self.panning_data.zoom_level + if zoom_out { 1 } else { -1 };
+                            // the point under the cursor stays put
+                            self.zoom_to(zoom_level, self.last_cursor_position);
                         }
-                        MouseScrollDelta::PixelDelta(_) => {
-                            // TODO: add this
-                            // or dont it only affects trackpad users
+                        MouseScrollDelta::PixelDelta(delta) => {
+                            self.pixel_scroll += delta.y;
+                            let steps = (self.pixel_scroll / PIXEL_SCROLL_STEP).trunc();
+                            if steps != 0.0 {
+                                self.pixel_scroll -= steps * PIXEL_SCROLL_STEP;
+                                // scrolling up zooms in like the wheel does, unless that's reversed
+                                let steps = steps as i32;
+                                let zoom_level = self.panning_data.zoom_level + if self.settings.reverse_zoom { steps } else { -steps };
+                                self.zoom_to(zoom_level, self.last_cursor_position);
+                            }
                         }
                     }
                     window_ref.request_redraw();
                 }
-                WindowEvent::CursorMoved {position, .. } => {
-                    if self.panning_data.panning {
-                        // adjust panning offset
-                        let (mouse_pos_x, mouse_pos_y): (i32, i32) = position.into();
+                WindowEvent::CursorMoved {position, .. } if !self.panning_data.panning => {
+                    self.last_cursor_position = Some(position);
+                }
+                WindowEvent::CursorMoved {position, .. } if self.panning_data.panning => {
+                    // adjust panning offset
+                    let (mouse_pos_x, mouse_pos_y): (i32, i32) = position.into();
 
-                        let (window_size_x, window_size_y): (i32, i32) = window_ref.inner_size().into();
+                    let (window_size_x, window_size_y): (i32, i32) = window_ref.inner_size().into();
 
+                    if self.modifiers.control_key() {
+                        // dragging with control held turns the image instead, right is clockwise
+                        self.rotate_by((mouse_pos_x - window_size_x/2) as f32 * ROTATION_DRAG_SPEED);
+                    } else {
                         // Negate offset so moving mouse right moves image right
-                        let pan_multiplier = self.settings_window.as_ref().unwrap().configurable_settings.pan_multiplier;
+                        let pan_multiplier = self.settings.pan_multiplier;
                         let offset_x = -( mouse_pos_x - window_size_x/2)as f32 * pan_multiplier;
                         let offset_y = -( mouse_pos_y - window_size_y/2)as f32 * pan_multiplier;
-                        // if applying offset will make offset greater than image size, don't apply offset
-                        if (self.panning_data.pan_offset.x + offset_x).abs() < self.img_width as f32{
-                            self.panning_data.pan_offset.x += offset_x;
-                        }
-                        if (self.panning_data.pan_offset.y + offset_y).abs() < self.img_height as f32 {
-                            self.panning_data.pan_offset.y += offset_y;
-                        }
+                        let offset = PhysicalPosition::new(offset_x, offset_y);
+                        self.pan_by(offset);
+                        self.pan_inertia.record(offset);
+                    }
 
-                        // Update renderer pan
-                        if let Some(renderer) = &mut self.renderer {
-                            renderer.set_pan(self.panning_data.pan_offset, self.img_width, self.img_height);
+                    window_ref.set_cursor_position(PhysicalPosition::new(window_size_x/2, window_size_y/2)).expect("Error setting cursor position");
+                }
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    self.modifiers = modifiers.state();
+                }
+                WindowEvent::Occluded(occluded) => {
+                    self.occluded = occluded;
+                    self.update_hidden();
+                }
+                // there's no surface to draw to while minimized
+                WindowEvent::RedrawRequested if self.hidden_since.is_none() => {
+                    let mut clicked_path = None;
+                    let mut error_choice = None;
+                    let mut confirmed = None;
+                    let mut seek = (None, false);
+                    let mut insets = Insets::default();
+                    let mut drawn = false;
+                    let overlay_active = self.overlay_active();
+                    let dimensions = self.oriented_dimensions();
+                    let overlay_output = match &mut self.overlay {
+                        Some(overlay) if overlay_active => Some(overlay.run(window_ref, self.settings.overlay_scale, |ctx| {
+                            if let Some(contact_sheet) = &mut self.contact_sheet {
+                                clicked_path = contact_sheet.ui(ctx);
+                            } else if self.recents_open {
+                                clicked_path = recents_ui(ctx, &self.recent_files);
+                            } else if let Some(error_screen) = &self.error_screen {
+                                error_choice = error_ui(ctx, error_screen);
+                            }
+                            if let Some(toast) = &self.toast {
+                                toast_ui(ctx, toast);
+                            }
+                            if self.raw_preview {
+                                raw_preview_ui(ctx);
+                            }
+                            if self.drop_hovered {
+                                drop_hint_ui(ctx);
+                            }
+                            if let Some(image_info) = &self.image_info {
+                                // fits the image next to the panel instead of behind it, with the same gap it keeps to the edge
+                                let panel = image_info_ui(ctx, dimensions, image_info);
+                                insets.left = (panel.right() + 16.0) / ctx.content_rect().width();
+                            }
+                            if self.rating > 0 || self.flag != Flag::None {
+                                marks_ui(ctx, self.rating, self.flag);
+                            }
+                            let show_timing = self.settings.show_frame_timing;
+                            if let Some(frames) = self.loading_frames {
+                                // the next animation is still decoding, its frame count is known already
+                                match frames {
+                                    FrameCount::Known(frames) => frame_counter_ui(ctx, "Frame", Some(0), Some(frames), None),
+                                    _ => frame_counter_ui(ctx, "Frame", None, None, None),
+                                }
+                            } else if let Some(gif_frames) = self.gif_frames.as_ref().filter(|_| self.seek_bar_until.is_some()) {
+                                // counts frames itself, so the frame counter would only sit in its way
+                                seek = seek_bar_ui(ctx, self.shown_frame_index as usize, gif_frames.len());
+                            } else if let Some(gif_frames) = self.gif_frames.as_ref().filter(|_| self.paused || show_timing) {
+                                let index = self.shown_frame_index as usize;
+                                let timing = show_timing.then(|| frame_timing(gif_frames, index));
+                                frame_counter_ui(ctx, "Frame", Some(index), Some(gif_frames.len()), timing);
+                            } else if let Some(page_count) = self.page_count {
+                                frame_counter_ui(ctx, "Page", Some(self.page_index), Some(page_count), None);
+                            }
+                            if let Some(index) = self.pending_action {
+                                confirmed = confirm_action_ui(ctx, self.action_registry.resolve(&self.settings.actions[index]), &self.image_path);
+                            }
+                        })),
+                        _ => None,
+                    };
+                    let (seek_to, released) = seek;
+                    if let Some(index) = seek_to {
+                        if self.seeking.is_none() {
+                            self.seeking = Some(!self.paused);
+                            self.paused = true;
                         }
-
+                        self.current_frame_index = index as u32;
+                        self.gif_next_frame();
+                    }
+                    if released {
+                        if self.seeking.take() == Some(true) && self.settings.seek_resumes_playback {
+                            self.paused = false;
+                        }
+                        self.seek_bar_until = Some(Instant::now() + SEEK_BAR_DURATION);
                         window_ref.request_redraw();
-
-                        window_ref.set_cursor_position(PhysicalPosition::new(window_size_x/2, window_size_y/2)).expect("Error setting cursor position");
                     }
-                }
-                WindowEvent::RedrawRequested => {
+                    if let (Some(renderer), Some(size)) = (&mut self.renderer, self.pending_resize.take()) {
+                        renderer.resize(size);
+                        if self.panning_data.actual_size {
+                            self.update_view();
+                        }
+                    }
+                    // zoom and the template can change between redraws
+                    self.update_title();
+                    self.apply_pixel_aspect();
                     if let Some(renderer) = &mut self.renderer {
-                        match renderer.render() {
-                            Ok(()) => {}
+                        // settings can change at any time from the settings window
+                        renderer.set_zoom_policy(self.settings.zoom_policy());
+                        renderer.set_sharpen(if self.sharpen { self.settings.sharpen_strength } else { 0.0 });
+                        renderer.set_negative(self.negative.then(|| self.settings.film_base.color(self.settings.manual_film_base)));
+                        renderer.set_tone_mapping(self.settings.tone_mapping);
+                        renderer.set_frame(self.settings.image_frame());
+                        renderer.set_background_color(self.settings.background_color);
+                        renderer.set_insets(insets);
+                        match renderer.render(overlay_output.as_ref()) {
+                            Ok(()) => drawn = true,
                             Err(wgpu::SurfaceError::Lost) => {
                                 let size = window_ref.inner_size();
                                 renderer.resize(size);
                             }
                             Err(wgpu::SurfaceError::OutOfMemory) => {
-                                eprintln!("Out of memory!");
-                                event_loop.exit();
+                                log::error!("Out of memory!");
+                                if self.error_screen.is_some() {
+                                    // can't even draw the error overlay, fall back to a native dialog before giving up
+                                    rfd::MessageDialog::new()
+                                        .set_level(rfd::MessageLevel::Error)
+                                        .set_title("luminix")
+                                        .set_description("Ran out of GPU memory, luminix has to close.")
+                                        .show();
+                                    event_loop.exit();
+                                } else {
+                                    // free everything the image holds so the overlay has room to draw
+                                    renderer.unload_texture();
+                                    self.current_image = None;
+                                    self.current_hdr = None;
+                                    self.gif_frames = None;
+                                    self.next_frame_time = None;
+                                    self.error_screen = Some(ErrorScreen {
+                                        heading: "Can't show this image",
+                                        message: format!(
+                                            "Ran out of GPU memory while showing {} ({}x{}).",
+                                            self.image_path.display(), self.img_width, self.img_height,
+                                        ),
+                                    });
+                                    window_ref.request_redraw();
+                                }
                             }
-                            Err(e) => eprintln!("Render error: {e:?}"),
+                            Err(e) => log::error!("Render error: {e:?}"),
                         }
                     }
+                    if overlay_output.is_some_and(|output| output.repaint) {
+                        window_ref.request_redraw();
+                    }
+                    if let Some(path) = self.cli_args.screenshot.take_if(|_| drawn) {
+                        self.save_screenshot(event_loop, &path);
+                    }
+                    if let Some(path) = clicked_path {
+                        self.contact_sheet = None;
+                        self.recents_open = false;
+                        self.open_path(&path);
+                    }
+                    if let Some(confirmed) = confirmed {
+                        let index = self.pending_action.take().unwrap();
+                        if confirmed {
+                            self.run_action(event_loop, index);
+                        }
+                        window_ref.request_redraw();
+                    }
+                    match error_choice {
+                        Some(ErrorChoice::BrowseFolder) => self.toggle_contact_sheet(),
+                        Some(ErrorChoice::Quit) => event_loop.exit(),
+                        None => {}
+                    }
                 }
                 _ => (),
             }
         }
     }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        // every other way of quitting ends up here, draining twice after a close request costs nothing
+        self.shutdown_gpu();
+        if self.keeping_awake {
+            sleep_prevention::keep_awake(false);
+        }
+        if self.settings.remember_display_state {
+            self.save_display_state();
+        }
+        if self.settings.remember_window_geometry {
+            self.save_window_geometry();
+        }
+    }
 }
 
 impl App {
-    fn gif_next_frame(&mut self, event_loop: &ActiveEventLoop, schedule_next_frame: bool) {
-        if let Some(gif_frames) = self.gif_frames.clone() {
-            println!("------------------------");
-            let current_frame = &gif_frames[self.current_frame_index as usize];
-            
-            // Update current image
-            self.current_image = Some(ImageData {
-                rgba_data: current_frame.rgba_data.clone(),
-                width: current_frame.width,
-                height: current_frame.height,
-            });
-            
-            // Load new texture into renderer
-            if let Some(renderer) = &mut self.renderer {
-                renderer.load_texture(&current_frame.rgba_data, current_frame.width, current_frame.height);
+    /// Picks up the display toggles from the last session
+    fn restore_display_state(&mut self) {
+        let DisplayState { fit_mode, background, colormap, sharpen, negative } = self.settings.display_state;
+        self.fit_mode = fit_mode;
+        self.background = background;
+        self.colormap = colormap;
+        self.sharpen = sharpen;
+        self.negative = negative;
+    }
+    
+    /// Writes the display toggles into the settings file for the next launch.
+    /// Reads the file again first, so edits that weren't applied in the settings window stay unsaved
+    fn save_display_state(&self) {
+        let mut settings = ConfigurableSettings::load();
+        settings.display_state = DisplayState {
+            fit_mode: self.fit_mode,
+            background: self.background,
+            colormap: self.colormap,
+            sharpen: self.sharpen,
+            negative: self.negative,
+        };
+        settings.save();
+    }
+    
+    /// Writes the window's size and position into the settings file for the next launch, reading the file again first
+    /// like [`Self::save_display_state`]
+    fn save_window_geometry(&self) {
+        let Some(window) = &self.window else { return };
+        let size = window.inner_size();
+        // minimized windows report no size on some platforms
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let mut settings = ConfigurableSettings::load();
+        settings.last_window_size = Some((size.width, size.height));
+        settings.last_window_position = window.outer_position().ok().map(|position| (position.x, position.y));
+        settings.save();
+    }
+    
+    /// Shows `loaded_image` as `image_path` and resets the view, an empty path is for an image without a file like a pasted one
+    fn show_image(&mut self, image_path: PathBuf, loaded_image: LoadedImage) {
+        if image_path != self.image_path && self.has_image_file() {
+            self.remember_previous_image();
+        }
+        let has_file = !image_path.as_os_str().is_empty();
+        // for the indicator, the decoder falls back to demosaicing files whose preview is missing or broken
+        self.raw_preview = matches!(loaded_image, LoadedImage::RawPreview(_));
+        self.set_image(loaded_image);
+        self.pixel_aspect = image_loader::pixel_aspect(&image_path);
+        self.apply_pixel_aspect();
+        // the decoders show the first page
+        self.page_count = image_loader::tiff_page_count(&image_path);
+        self.page_index = 0;
+        if has_file {
+            self.recent_files.add(&image_path, self.settings.recent_files_limit);
+            self.recent_files.save();
+        }
+        
+        self.exposure = 0.0;
+        self.renderer.as_mut().unwrap().set_exposure(self.exposure);
+        let sidecar = has_file.then(|| Sidecar::load(&image_path).unwrap_or_else(|e| {
+            log::error!("Failed to read {}. Error message: {e}", Sidecar::path(&image_path).display());
+            None
+        })).flatten().unwrap_or_default();
+        // ratings show regardless, the setting is about the view
+        self.rating = sidecar.rating;
+        self.flag = sidecar.flag;
+        let mut view = if self.settings.read_sidecars { sidecar } else { Sidecar::default() };
+        // a sidecar's view was saved with the orientation already applied
+        if self.settings.auto_orient && has_file && !view.has_view() {
+            if let Some((rotation, flip_horizontal)) = metadata::exif_orientation(&image_path) {
+                view.rotation = rotation;
+                view.flip_horizontal = flip_horizontal;
             }
-
-            // schedule the next frame
-            self.current_frame_index = (self.current_frame_index + 1) % u32::try_from(gif_frames.len()).unwrap_or_default();
-            self.next_frame_time = Some(Instant::now() + Duration::from_millis(u64::from(
-                gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().0 / gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().1
-            )));
-            println!("{:?}", u64::from(gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().0 / gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().1));
-            dbg!(self.current_frame_index);
-            self.window.as_ref().unwrap().request_redraw();
-            if schedule_next_frame {
-                event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_frame_time.expect("REASON")));
+        }
+        self.apply_sidecar(view);
+        // after the sidecar, the fit depends on rotation and crop
+        self.reset_view();
+        if let Some((zoom_level, (pan_x, pan_y))) = self.swap_view.take() {
+            self.panning_data.zoom_level = zoom_level;
+            self.panning_data.pan_offset = PhysicalPosition::new(pan_x * self.img_width as f32, pan_y * self.img_height as f32);
+            self.update_view();
+        }
+        self.loop_start = None;
+        self.loop_end = None;
+        
+        self.window.as_ref().unwrap().request_redraw();
+        if let Some(settings_window) = &mut self.settings_window {
+            settings_window.current_image_path = Some(image_path.clone()).filter(|_| has_file);
+            settings_window.current_image_dimensions = (self.img_width, self.img_height);
+            // the image info follows the image
+            settings_window.window.request_redraw();
+        }
+        if !has_file {
+            self.file_watcher = None;
+        } else if self.cli_args.watch && (self.file_watcher.is_none() || image_path != self.image_path) {
+            self.file_watcher = FileWatcher::new(&image_path, self.event_loop_proxy.clone().unwrap())
+                .inspect_err(|e| log::error!("Failed to watch {} for changes. Error message: {e}", image_path.display()))
+                .ok();
+        }
+        self.image_path = image_path;
+        self.folder_position = OnceCell::new();
+        self.refresh_image_info();
+        self.update_title();
+    }
+    
+    /// Fills in the title template from the settings, only touching the window when the result changed
+    fn update_title(&mut self) {
+        let template = &self.settings.title_template;
+        let folder_position = if template.contains("{index}") || template.contains("{count}") {
+            // listing the folder is only worth it when the template shows it
+            *self.folder_position.get_or_init(|| {
+                let paths = image_paths_in_dir(image_dir(&self.image_path)).ok()?;
+                let index = paths.iter().position(|path| path.file_name() == self.image_path.file_name())?;
+                Some((index + 1, paths.len()))
+            })
+        } else {
+            None
+        };
+        let magnification = self.renderer.as_ref().map_or(1.0, WgpuRenderer::magnification);
+        let frame = match (self.loading_frames, &self.gif_frames) {
+            // the animation on its way is what the frame tokens are about, "?" while its count is unknown
+            (Some(FrameCount::Known(frames)), _) => Some((String::from("1"), frames.to_string())),
+            (Some(FrameCount::Unknown), _) => Some((String::from("?"), String::from("?"))),
+            (_, Some(gif_frames)) => Some(((self.shown_frame_index + 1).to_string(), gif_frames.len().to_string())),
+            (_, None) => None,
+        };
+        // a pasted image has no file to name
+        let image_path = if self.has_image_file() { &self.image_path } else { Path::new("Pasted image") };
+        let title = window_title(template, image_path, self.oriented_dimensions(), magnification, folder_position, frame);
+        if title != self.window_title {
+            self.window.as_ref().unwrap().set_title(&title);
+            self.window_title = title;
+        }
+    }
+    
+    /// Decodes the shown image again in the background, for when it changed on disk.
+    /// `report_errors` shows failures in a toast, otherwise they're only logged
+    fn reload_image(&mut self, keep_view: bool, report_errors: bool) {
+        let image_path = self.image_path.clone();
+        self.open_path(&image_path);
+        self.pending_reload = Some(PendingReload { keep_view, report_errors });
+    }
+    
+    /// Swaps in the decoded again image, keeping pan and zoom with `keep_view`
+    fn apply_reload(&mut self, loaded_image: LoadedImage, keep_view: bool) {
+        self.set_image(loaded_image);
+        self.pixel_aspect = image_loader::pixel_aspect(&self.image_path);
+        self.apply_pixel_aspect();
+        self.page_count = image_loader::tiff_page_count(&self.image_path);
+        self.page_index = 0;
+        self.refresh_image_info();
+        if keep_view {
+            self.update_view();
+        } else {
+            self.reset_view();
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Asks the system to stay awake while the setting is on and the image window is focused, and lets it sleep again otherwise
+    fn update_keep_awake(&mut self) {
+        let keep_awake = self.settings.prevent_sleep && self.focused;
+        if keep_awake != self.keeping_awake {
+            sleep_prevention::keep_awake(keep_awake);
+            self.keeping_awake = keep_awake;
+        }
+    }
+    
+    /// When the image gets dimmed if there's no input until then, `None` if dimming is off
+    fn dim_time(&self) -> Option<Instant> {
+        let idle_time = Duration::from_secs(u64::from(self.settings.idle_dim_seconds));
+        if idle_time.is_zero() {
+            return None;
+        }
+        Some(self.last_input? + idle_time)
+    }
+    
+    fn set_dimmed(&mut self, dimmed: bool) {
+        self.dimmed = dimmed;
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_dim(if dimmed { IDLE_DIM } else { 0.0 });
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Holds playback while the window can't be seen and picks it up on return.
+    /// The shown frame keeps the time it had left, instead of frames catching up in a burst or being skipped
+    fn update_hidden(&mut self) {
+        let hidden = self.minimized || self.occluded;
+        match (hidden, self.hidden_since) {
+            (true, None) => self.hidden_since = Some(Instant::now()),
+            (false, Some(hidden_since)) => {
+                self.hidden_since = None;
+                let now = Instant::now();
+                if let Some(next_frame_time) = &mut self.next_frame_time {
+                    *next_frame_time = now + next_frame_time.saturating_duration_since(hidden_since);
+                }
+                self.window.as_ref().unwrap().request_redraw();
             }
+            _ => {}
         }
     }
-    fn gif_prev_frame(&mut self, event_loop: &ActiveEventLoop, schedule_next_frame: bool) {
-        if let Some(gif_frames) = self.gif_frames.clone() {
-            println!("------------------------");
-            let current_frame = &gif_frames[self.current_frame_index as usize];
-            
-            // Update current image
-            self.current_image = Some(ImageData {
-                rgba_data: current_frame.rgba_data.clone(),
-                width: current_frame.width,
-                height: current_frame.height,
-            });
-            
-            // Load new texture into renderer
-            if let Some(renderer) = &mut self.renderer {
-                renderer.load_texture(&current_frame.rgba_data, current_frame.width, current_frame.height);
+    
+    /// Lets both windows finish their submitted GPU work, before the renderers are dropped
+    fn shutdown_gpu(&self) {
+        if let Some(renderer) = &self.renderer {
+            renderer.shutdown();
+        }
+        if let Some(settings_window) = &self.settings_window {
+            settings_window.shutdown();
+        }
+    }
+    
+    /// Shows `message` over the image for a few seconds
+    fn show_toast(&mut self, message: String) {
+        self.toast = Some(Toast { message, until: Instant::now() + TOAST_DURATION });
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Passes the image's pixel aspect on to the renderer, or square pixels when the setting ignores it
+    fn apply_pixel_aspect(&mut self) {
+        let pixel_aspect = self.pixel_aspect.filter(|_| self.settings.honor_pixel_aspect).unwrap_or(1.0);
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_pixel_aspect(pixel_aspect);
+        }
+    }
+    
+    /// Uploads the image and restarts animation playback
+    fn set_image(&mut self, loaded_image: LoadedImage) {
+        self.error_screen = None;
+        let renderer = self.renderer.as_mut().unwrap();
+        // a solid background shows transparency too and is kept once chosen
+        if self.settings.auto_background && self.background != Background::Solid {
+            self.background = if loaded_image.has_transparency() { Background::Checkerboard } else { Background::Transparent };
+        }
+        renderer.set_background(self.background);
+        self.current_hdr = None;
+        match loaded_image {
+            LoadedImage::Static(image) | LoadedImage::RawPreview(image) => {
+                renderer.load_texture(&image.rgba_data, image.width, image.height, self.settings.alpha_mode);
+                self.img_width = image.width;
+                self.img_height = image.height;
+                self.current_image = Some(image);
+                self.gif_frames = None;
+                self.next_frame_time = None;
             }
-
-            // schedule the next frame
-            if self.current_frame_index > 0 {
-                self.current_frame_index -= 1;
-            } else {
-                self.current_frame_index = u32::try_from(gif_frames.len()).unwrap_or_default() - 1;
+            LoadedImage::Animated { frames: gif_frames, loop_count } => {
+                let first_frame = &gif_frames[0];
+                // GIF transparency is all or nothing and APNG and WebP store straight alpha, so there's nothing premultiplied to respect
+                renderer.load_texture(&first_frame.rgba_data, first_frame.width, first_frame.height, AlphaMode::Straight);
+                self.img_width = first_frame.width;
+                self.img_height = first_frame.height;
+                self.current_image = Some(ImageData {
+                    rgba_data: first_frame.rgba_data.clone(),
+                    width: first_frame.width,
+                    height: first_frame.height,
+                });
+                self.current_frame_index = 0;
+                self.shown_frame_index = 0;
+                self.next_frame_time = Some(Instant::now() + first_frame.delay.into());
+                self.paused = false;
+                self.loop_count = loop_count;
+                self.loop_iteration = 0;
+                self.gif_frames = Some(gif_frames);
             }
-
-            self.next_frame_time = Some(Instant::now() + Duration::from_millis(u64::from(gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().0 / gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().1)));
-            println!("{:?}", u64::from(gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().0 / gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().1));
-            dbg!(self.current_frame_index);
-            self.window.as_ref().unwrap().request_redraw();
-            if schedule_next_frame {
-                event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_frame_time.expect("REASON")));
+            LoadedImage::Hdr(image) => {
+                renderer.load_hdr_texture(&image.rgba_data, image.width, image.height, image.alpha_mode);
+                self.img_width = image.width;
+                self.img_height = image.height;
+                self.current_image = None;
+                self.current_hdr = Some(image);
+                self.gif_frames = None;
+                self.next_frame_time = None;
             }
         }
     }
-}
-
-
-fn main() {
-    // check if valid args before anything else
-    if env::args().collect::<Vec<_>>().len() != 2 {
-        eprintln!("Usage: luminix <image_path>");
-        return;
-    };
     
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let mut app = App::default();
-    event_loop.run_app(&mut app).expect("error running event loop");
+    /// Shows `image_path` once it's decoded in the background, the current image stays until then.
+    /// Opening another image before that cancels this one
+    fn open_path(&mut self, image_path: &Path) {
+        self.navigation_step = None;
+        self.loading_frames = None;
+        self.pending_path = Some(image_path.to_path_buf());
+        self.pending_reload = None;
+        self.loader.as_ref().unwrap().request(image_path);
+    }
+    
+    /// Drops the image being decoded in the background, the shown one stays
+    fn cancel_loading(&mut self) {
+        if let Some(loader) = &self.loader {
+            loader.cancel();
+        }
+        self.pending_path = None;
+        self.pending_reload = None;
+        self.replacing_removed = None;
+        if self.loading_frames.take().is_some() {
+            self.update_title();
+            self.window.as_ref().unwrap().request_redraw();
+        }
+    }
+    
+    /// Opens the image `step` places after the one that's loading, or the shown one when nothing is
+    fn navigate(&mut self, step: isize) {
+        let from = self.pending_path.clone().unwrap_or_else(|| self.image_path.clone());
+        self.replacing_removed = None;
+        self.step_image_from(&from, step);
+    }
+    
+    /// Opens the image `step` places after `from` in the images given on the command line, or in its folder
+    /// when it's not one of them, wrapping around at the ends.
+    /// `from` doesn't have to be listed anymore, a deleted image steps from where it would sort.
+    /// Images that fail to load are stepped over until it comes back around to the shown one, or to `replacing_removed`.
+    /// Returns false when it came back around without opening anything
+    fn step_image_from(&mut self, from: &Path, step: isize) -> bool {
+        let playlist_index = self.cli_args.playlist.iter().position(|path| path == from);
+        let paths = match playlist_index {
+            Some(_) => self.cli_args.playlist.clone(),
+            None => image_paths_in_dir(image_dir(from)).unwrap_or_default(),
+        };
+        if paths.is_empty() {
+            return false;
+        }
+        let count = paths.len() as isize;
+        let position = playlist_index.or_else(|| paths.iter().position(|path| path.file_name() == from.file_name()));
+        let index = match position {
+            Some(index) => index as isize + step,
+            None => {
+                let after = paths.partition_point(|path| sort_key(path) < sort_key(from)) as isize;
+                if step > 0 { after + step - 1 } else { after + step }
+            }
+        };
+        let next = paths[index.rem_euclid(count) as usize].clone();
+        let stop = self.replacing_removed.as_deref().unwrap_or(&self.image_path);
+        if next.file_name() == stop.file_name() && image_dir(&next) == image_dir(stop) {
+            // back where it started, whatever was on its way isn't wanted anymore
+            self.cancel_loading();
+            return false;
+        }
+        self.open_path(&next);
+        self.navigation_step = Some(step);
+        true
+    }
+    
+    /// Zooms back to the fit and pans to where reading starts, the top for fit width and the left for fit height
+    fn reset_view(&mut self) {
+        self.panning_data = PanningData::default();
+        self.pan_inertia.stop();
+        self.update_view();
+        let Some(renderer) = &self.renderer else { return };
+        let (extent_x, extent_y) = renderer.image_extent();
+        // moves the image's leading edge to the window's edge, positive extents below 1.0 stay centered
+        match self.fit_mode {
+            FitMode::Inside => {}
+            FitMode::Width => self.panning_data.pan_offset.y = (1.0 - extent_y).min(0.0) / 2.0 * self.img_height as f32,
+            FitMode::Height => self.panning_data.pan_offset.x = (1.0 - extent_x).min(0.0) / 2.0 * self.img_width as f32,
+        }
+        self.update_view();
+    }
+    
+    /// Zooms and pans to the view given with `--zoom` and `--pan`, clamped to what the keyboard and mouse could reach
+    fn apply_cli_view(&mut self) {
+        if let Some(zoom) = self.cli_args.zoom {
+            self.zoom_to(zoom_level_for(zoom), None);
+        }
+        if let Some((pan_x, pan_y)) = self.cli_args.pan {
+            let (bound_x, bound_y) = self.pan_bounds();
+            self.panning_data.pan_offset = PhysicalPosition::new(
+                pan_x.clamp(-bound_x, bound_x) * self.img_width as f32,
+                pan_y.clamp(-bound_y, bound_y) * self.img_height as f32,
+            );
+            self.update_view();
+        }
+    }
+    
+    /// Zooms and pans back to showing the whole image, leaving fit width or height too
+    fn fit_to_window(&mut self) {
+        self.fit_mode = FitMode::Inside;
+        self.panning_data = PanningData::default();
+        self.pan_inertia.stop();
+        if let Some(renderer) = &mut self.renderer {
+            renderer.fit_to_window();
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Switches to `fit_mode`, or back to fitting inside the window if it's already active
+    fn toggle_fit_mode(&mut self, fit_mode: FitMode) {
+        self.fit_mode = if self.fit_mode == fit_mode { FitMode::Inside } else { fit_mode };
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_fit_mode(self.fit_mode);
+        }
+        self.reset_view();
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// How far the image can be panned along each axis, as a multiple of its size.
+    /// Lets the image go just past the window's edge, or further when it's larger than the window
+    fn pan_bounds(&self) -> (f32, f32) {
+        let (extent_x, extent_y) = self.renderer.as_ref().map_or((1.0, 1.0), WgpuRenderer::image_extent);
+        (((extent_x + 1.0) / 2.0).max(1.0), ((extent_y + 1.0) / 2.0).max(1.0))
+    }
+    
+    /// Largest pan offset along x and y, see `pan_bounds`
+    fn pan_limits(&self) -> (f32, f32) {
+        let (bound_x, bound_y) = self.pan_bounds();
+        (self.img_width as f32 * bound_x, self.img_height as f32 * bound_y)
+    }
+    
+    /// Moves the image by `offset`, returns whether it moved freely along x and y.
+    /// Past the pan bounds an axis stops, drags with resistance or wraps around, depending on the overscroll setting
+    fn pan_by(&mut self, offset: PhysicalPosition<f32>) -> (bool, bool) {
+        let (limit_x, limit_y) = self.pan_limits();
+        let overscroll = self.settings.overscroll;
+        let pan_offset = &mut self.panning_data.pan_offset;
+        let moved_x = pan_axis(&mut pan_offset.x, offset.x, limit_x, overscroll);
+        let moved_y = pan_axis(&mut pan_offset.y, offset.y, limit_y, overscroll);
+        
+        self.update_view();
+        self.window.as_ref().unwrap().request_redraw();
+        (moved_x, moved_y)
+    }
+    
+    /// Eases an image pulled past the pan bounds back to their edge, returns whether it's still on its way
+    fn rubber_band_return(&mut self, now: Instant) -> bool {
+        let (limit_x, limit_y) = self.pan_limits();
+        let pan_offset = self.panning_data.pan_offset;
+        let target = PhysicalPosition::new(pan_offset.x.clamp(-limit_x, limit_x), pan_offset.y.clamp(-limit_y, limit_y));
+        if target == pan_offset {
+            self.rubber_band_step = None;
+            return false;
+        }
+        let dt = now.saturating_duration_since(self.rubber_band_step.unwrap_or(now)).as_secs_f32();
+        self.rubber_band_step = Some(now);
+        let progress = 1.0 - (-RUBBER_BAND_RETURN_SPEED * dt).exp();
+        let ease = |from: f32, to: f32| {
+            let next = from + (to - from) * progress;
+            // snaps the last fraction of a pixel, the approach never quite arrives on its own
+            if (to - next).abs() < 0.5 { to } else { next }
+        };
+        self.panning_data.pan_offset = PhysicalPosition::new(ease(pan_offset.x, target.x), ease(pan_offset.y, target.y));
+        self.update_view();
+        self.window.as_ref().unwrap().request_redraw();
+        true
+    }
+    
+    /// Passes zoom level and pan offset on to the renderer
+    fn update_view(&mut self) {
+        let Some(renderer) = &mut self.renderer else { return };
+        let pan_offset = self.panning_data.pan_offset;
+        // Normalize pan offset to -1.0 to 1.0 range based on image size
+        let pan_normalized = (pan_offset.x / self.img_width as f32, pan_offset.y / self.img_height as f32);
+        let zoom_factor = if self.panning_data.actual_size {
+            renderer.actual_size_zoom(self.img_width, self.img_height, self.window.as_ref().unwrap().inner_size())
+        } else {
+            zoom_factor(self.panning_data.zoom_level)
+        };
+        renderer.set_view(zoom_factor, pan_normalized);
+    }
+    
+    /// Zooms to one image pixel per screen pixel, the pan stays as it is like zooming with the keyboard.
+    /// The zoom level is set to the closest one, so zooming in or out steps on from there
+    fn zoom_to_actual_size(&mut self) {
+        let Some(renderer) = &mut self.renderer else { return };
+        let window_size = self.window.as_ref().unwrap().inner_size();
+        let zoom_factor = renderer.set_actual_size(self.img_width, self.img_height, window_size);
+        self.pan_inertia.stop();
+        self.panning_data.zoom_level = zoom_level_for(zoom_factor);
+        self.panning_data.actual_size = true;
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// The zoom factor that is shown, the zoom level's unless zoomed to actual size
+    fn current_zoom_factor(&self) -> f32 {
+        match &self.renderer {
+            Some(renderer) if self.panning_data.actual_size => {
+                renderer.actual_size_zoom(self.img_width, self.img_height, self.window.as_ref().unwrap().inner_size())
+            }
+            _ => zoom_factor(self.panning_data.zoom_level),
+        }
+    }
+    
+    /// Changes the zoom level while keeping the image point under `focus` (in window pixels) in place.
+    /// Without a focus the pan stays as it is, which keeps the image's center in place
+    fn zoom_to(&mut self, zoom_level: i32, focus: Option<PhysicalPosition<f64>>) {
+        let old_zoom_factor = self.current_zoom_factor();
+        // negative levels magnify, so the largest zoom is the lowest level
+        let (min_level, max_level) = (zoom_level_for(self.settings.max_zoom), zoom_level_for(self.settings.min_zoom));
+        self.panning_data.zoom_level = zoom_level.clamp(min_level, max_level.max(min_level));
+        self.panning_data.actual_size = false;
+        let ratio = zoom_factor(self.panning_data.zoom_level) / old_zoom_factor;
+        if let Some(focus) = focus {
+            let size = self.window.as_ref().unwrap().inner_size();
+            let focus_x = 2.0 * focus.x as f32 / size.width as f32 - 1.0;
+            let focus_y = 1.0 - 2.0 * focus.y as f32 / size.height as f32;
+            // the image center's translation in normalized device coordinates, see the vertex shader,
+            // scaled around the focus like the image is
+            let (center_x, center_y) = self.renderer.as_ref().map_or((0.0, 0.0), WgpuRenderer::fit_center);
+            let pan_offset = &mut self.panning_data.pan_offset;
+            let translation_x = center_x - 2.0 * pan_offset.x / self.img_width as f32;
+            let translation_y = center_y + 2.0 * pan_offset.y / self.img_height as f32;
+            pan_offset.x = -(focus_x - ratio * (focus_x - translation_x) - center_x) / 2.0 * self.img_width as f32;
+            pan_offset.y = (focus_y - ratio * (focus_y - translation_y) - center_y) / 2.0 * self.img_height as f32;
+            // the limits depend on the new zoom, zooming out near an edge can leave the image past them
+            self.update_view();
+            let (limit_x, limit_y) = self.pan_limits();
+            let pan_offset = &mut self.panning_data.pan_offset;
+            *pan_offset = PhysicalPosition::new(pan_offset.x.clamp(-limit_x, limit_x), pan_offset.y.clamp(-limit_y, limit_y));
+        }
+        self.update_view();
+    }
+    
+    /// Where keyboard zoom is anchored, the last cursor position if the setting asks for it
+    fn keyboard_zoom_focus(&self) -> Option<PhysicalPosition<f64>> {
+        self.last_cursor_position.filter(|_| self.settings.keyboard_zoom_follows_cursor)
+    }
+    
+    fn rotate_by(&mut self, degrees: f32) {
+        self.rotation = (self.rotation + degrees).rem_euclid(360.0);
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_rotation(self.rotation);
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Mirrors the image horizontally and vertically in the shader, on top of rotation, zoom and pan
+    fn set_flip(&mut self, flip: (bool, bool)) {
+        self.flip = flip;
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_flip(flip.0, flip.1);
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// Sets rotation, flip and crop, the default sidecar shows the image as it is
+    fn apply_sidecar(&mut self, sidecar: Sidecar) {
+        // a hand edited sidecar can hold NaN or inf, which would end up in the rotation uniform
+        self.rotation = Some(sidecar.rotation).filter(|rotation| rotation.is_finite()).map_or(0.0, |rotation| rotation.rem_euclid(360.0));
+        self.flip = (sidecar.flip_horizontal, sidecar.flip_vertical);
+        self.crop = sidecar.crop.unwrap_or_default().clamped();
+        let renderer = self.renderer.as_mut().unwrap();
+        renderer.set_rotation(self.rotation);
+        renderer.set_flip(self.flip.0, self.flip.1);
+        renderer.set_crop(self.crop);
+    }
+    
+    /// Writes the current rotation, flip and crop next to the image, so it opens the same way next time
+    fn save_sidecar(&mut self) {
+        if !self.has_image_file() {
+            self.show_toast(String::from("Pasted images have no file to save a sidecar next to"));
+            return;
+        }
+        let sidecar = Sidecar {
+            rotation: self.rotation,
+            flip_horizontal: self.flip.0,
+            flip_vertical: self.flip.1,
+            crop: Some(self.crop).filter(|crop| *crop != Crop::default()),
+            rating: self.rating,
+            flag: self.flag,
+        };
+        let message = match sidecar.save(&self.image_path) {
+            Ok(path) => format!("Saved {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to save the sidecar of {}. Error message: {e}", self.image_path.display());
+                format!("Couldn't save: {e}")
+            }
+        };
+        self.show_toast(message);
+    }
+    
+    /// Changes the rating or flag of the shown image and stores it in its sidecar, keeping everything else the sidecar holds
+    fn mark(&mut self, change: impl FnOnce(&mut Sidecar)) {
+        if !self.has_image_file() {
+            self.show_toast(String::from("Pasted images have no file to rate"));
+            return;
+        }
+        let mut sidecar = match Sidecar::load(&self.image_path) {
+            Ok(sidecar) => sidecar.unwrap_or_default(),
+            // overwriting it would lose whatever is in there
+            Err(e) => {
+                self.show_toast(format!("Couldn't read {}: {e}", Sidecar::path(&self.image_path).display()));
+                return;
+            }
+        };
+        change(&mut sidecar);
+        match sidecar.save(&self.image_path) {
+            Ok(_) => {
+                self.rating = sidecar.rating;
+                self.flag = sidecar.flag;
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            Err(e) => {
+                log::error!("Failed to save the sidecar of {}. Error message: {e}", self.image_path.display());
+                self.show_toast(format!("Couldn't save the rating: {e}"));
+            }
+        }
+    }
+    
+    /// Opens the next image in the folder, wrapping around, that is picked or rated, or with `unmarked` that has neither
+    fn open_next_marked(&mut self, unmarked: bool) {
+        let paths = image_paths_in_dir(image_dir(&self.image_path)).unwrap_or_default();
+        let current = paths.iter().position(|path| path.file_name() == self.image_path.file_name());
+        let start = current.map_or(0, |index| index + 1);
+        let others = paths.len() - usize::from(current.is_some());
+        let next = (0..others).map(|offset| &paths[(start + offset) % paths.len()]).find(|path| {
+            let sidecar = Sidecar::load(path).ok().flatten().unwrap_or_default();
+            if unmarked { sidecar.is_unmarked() } else { sidecar.is_picked() }
+        });
+        match next {
+            Some(next) => self.open_path(&next.clone()),
+            None => self.show_toast(String::from(if unmarked { "No other unrated images" } else { "No other picked or rated images" })),
+        }
+    }
+    
+    /// Asks where to and saves the shown image or frame with the rotation baked in, as a PNG to keep the transparent corners
+    fn save_rotated(&mut self) {
+        self.tone_map_hdr();
+        let Some(image) = &self.current_image else {
+            self.show_toast(String::from("Only 8 bit images can be saved rotated"));
+            return;
+        };
+        let stem = self.image_path.file_stem().unwrap_or_default().to_string_lossy();
+        let Some(target) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_directory(image_dir(&self.image_path))
+            .set_file_name(format!("{stem}-rotated.png"))
+            .save_file() else { return };
+        let message = match export::rotate_image(image, self.rotation).save_with_format(&target, image::ImageFormat::Png) {
+            Ok(()) => format!("Saved {}", target.display()),
+            Err(e) => {
+                log::error!("Failed to save {}. Error message: {e}", target.display());
+                format!("Couldn't save: {e}")
+            }
+        };
+        self.show_toast(message);
+    }
+    
+    /// Asks where to and writes the frames between the loop start and end to a new GIF, the whole animation without a loop range.
+    /// Encoding runs on its own thread, reporting progress through toasts
+    fn export_loop_range(&mut self) {
+        let Some(gif_frames) = &self.gif_frames else {
+            self.show_toast(String::from("Only animations can be exported as GIF"));
+            return;
+        };
+        let (loop_start, loop_end) = self.loop_bounds(gif_frames.len());
+        let frames = gif_frames[loop_start as usize..=loop_end as usize].to_vec();
+        let stem = self.image_path.file_stem().unwrap_or_default().to_string_lossy();
+        let Some(target) = rfd::FileDialog::new()
+            .add_filter("GIF", &["gif"])
+            .set_directory(image_dir(&self.image_path))
+            .set_file_name(format!("{stem}-{}-{}.gif", loop_start + 1, loop_end + 1))
+            .save_file() else { return };
+        
+        self.show_toast(format!("Exporting {} frames", frames.len()));
+        let event_loop_proxy = self.event_loop_proxy.clone().unwrap();
+        thread::spawn(move || {
+            let total = frames.len();
+            let result = export::encode_gif(&frames, &target, |done| {
+                let _ = event_loop_proxy.send_event(UserEvent::ExportProgress { done, total });
+            });
+            let message = match result {
+                Ok(()) => format!("Saved {}", target.display()),
+                Err(e) => {
+                    log::error!("Failed to export {}. Error message: {e}", target.display());
+                    format!("Couldn't export: {e}")
+                }
+            };
+            let _ = event_loop_proxy.send_event(UserEvent::ExportFinished(message));
+        });
+    }
+    
+    /// Copies what the window shows to the clipboard, as it's zoomed, panned, rotated and adjusted
+    fn copy_view(&mut self) {
+        let Some(renderer) = &self.renderer else { return };
+        let capture = match renderer.capture_view() {
+            Ok(capture) => capture,
+            Err(e) => {
+                log::error!("Failed to capture the view. Error message: {e}");
+                self.show_toast(format!("Couldn't capture the view: {e}"));
+                return;
+            }
+        };
+        let (width, height) = capture.dimensions();
+        let image = arboard::ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: capture.into_raw().into(),
+        };
+        let message = match self.clipboard().and_then(|clipboard| clipboard.set_image(image)) {
+            Ok(()) => format!("Copied the view ({width}x{height})"),
+            Err(e) => {
+                log::error!("Failed to copy the view to the clipboard. Error message: {e}");
+                format!("Couldn't copy to the clipboard: {e}")
+            }
+        };
+        self.show_toast(message);
+    }
+    
+    /// Saves the first view that was drawn for `--screenshot` and closes luminix, exiting with an error when that fails
+    fn save_screenshot(&mut self, event_loop: &ActiveEventLoop, path: &Path) {
+        if self.launch_failed {
+            // the image couldn't be loaded, the error screen isn't worth capturing
+            if let Some(error_screen) = &self.error_screen {
+                log::error!("{}", error_screen.message);
+            }
+        } else {
+            match self.renderer.as_ref().unwrap().capture_view() {
+                Ok(capture) => if let Err(e) = capture.save(path) {
+                    log::error!("Failed to save {}. Error message: {e}", path.display());
+                    self.launch_failed = true;
+                }
+                Err(e) => {
+                    log::error!("Failed to capture the view. Error message: {e}");
+                    self.launch_failed = true;
+                }
+            }
+        }
+        event_loop.exit();
+    }
+    
+    /// Copies the shown image or frame as it's decoded, without the view's zoom, rotation and adjustments
+    fn copy_image(&mut self) {
+        self.tone_map_hdr();
+        let Some(image) = &self.current_image else {
+            self.show_toast("Only 8-bit images can be copied".to_owned());
+            return;
+        };
+        let (width, height) = (image.width, image.height);
+        let image = arboard::ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: image.rgba_data.clone().into(),
+        };
+        let message = match self.clipboard().and_then(|clipboard| clipboard.set_image(image)) {
+            Ok(()) => format!("Copied the image ({width}x{height})"),
+            Err(e) => {
+                log::error!("Failed to copy the image to the clipboard. Error message: {e}");
+                format!("Couldn't copy to the clipboard: {e}")
+            }
+        };
+        self.show_toast(message);
+    }
+    
+    /// Shows the image on the clipboard, as if it was opened from a file that doesn't exist
+    fn paste_image(&mut self) {
+        let image = match self.clipboard().and_then(|clipboard| clipboard.get_image()) {
+            Ok(image) => image,
+            Err(arboard::Error::ContentNotAvailable) => {
+                self.show_toast(String::from("The clipboard has no image"));
+                return;
+            }
+            Err(e) => {
+                log::error!("Failed to read an image from the clipboard. Error message: {e}");
+                self.show_toast(format!("Couldn't paste from the clipboard: {e}"));
+                return;
+            }
+        };
+        // an image still loading in the background would replace it
+        self.cancel_loading();
+        self.show_image(PathBuf::new(), LoadedImage::Static(ImageData {
+            width: image.width as u32,
+            height: image.height as u32,
+            rgba_data: image.bytes.into_owned(),
+        }));
+    }
+    
+    /// File details and EXIF fields of the shown image, for the info overlay
+    fn read_image_info(&self) -> Vec<(&'static str, String)> {
+        let mut rows = Vec::new();
+        if self.has_image_file() {
+            rows.extend(metadata::file_summary(&self.image_path));
+            rows.extend(metadata::exif_summary(&self.image_path));
+        }
+        rows
+    }
+    
+    /// Width and height as the image is shown, swapped when it's turned a quarter, like photos that EXIF stands upright
+    fn oriented_dimensions(&self) -> (u32, u32) {
+        if self.rotation == 90.0 || self.rotation == 270.0 {
+            (self.img_height, self.img_width)
+        } else {
+            (self.img_width, self.img_height)
+        }
+    }
+    
+    /// Reads the info overlay's rows again for a new or changed image, while it's shown
+    fn refresh_image_info(&mut self) {
+        if self.image_info.is_some() {
+            self.image_info = Some(self.read_image_info());
+        }
+    }
+    
+    /// Whether the shown image was opened from a file, pasted images and the time before the first one is shown have none
+    fn has_image_file(&self) -> bool {
+        !self.image_path.as_os_str().is_empty()
+    }
+    
+    /// The clipboard, opened on first use
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.clipboard.insert(clipboard))
+    }
+    
+    /// Copies the shown image or frame as base64 text for embedding in HTML and CSS, re-encoded in the configured format
+    fn copy_data_url(&mut self) {
+        self.tone_map_hdr();
+        let Some(image) = &self.current_image else {
+            self.show_toast("Only 8-bit images can be copied as data URLs".to_owned());
+            return;
+        };
+        let data_url = match export::data_url(image, self.settings.data_url_format, self.settings.data_url_prefix) {
+            Ok(data_url) => data_url,
+            Err(e) => {
+                log::error!("Failed to encode the image for a data URL. Error message: {e}");
+                self.show_toast(format!("Couldn't encode the image: {e}"));
+                return;
+            }
+        };
+        let length = data_url.len();
+        let message = match self.clipboard().and_then(|clipboard| clipboard.set_text(data_url)) {
+            Ok(()) if length > LARGE_DATA_URL => format!("Copied a data URL of {} KB, large enough to slow down editors and pages", length / 1024),
+            Ok(()) => format!("Copied a data URL of {} KB", length.div_ceil(1024)),
+            Err(e) => {
+                log::error!("Failed to copy the data URL to the clipboard. Error message: {e}");
+                format!("Couldn't copy to the clipboard: {e}")
+            }
+        };
+        self.show_toast(message);
+    }
+    
+    /// Puts the shown HDR image into `current_image` as it looks right now, for everything working on 8 bit pixels
+    fn tone_map_hdr(&mut self) {
+        if let Some(hdr) = &self.current_hdr {
+            self.current_image = Some(hdr.tone_mapped(self.settings.tone_mapping, self.exposure));
+        }
+    }
+    
+    fn adjust_exposure(&mut self, stops: f32) {
+        self.exposure += stops;
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_exposure(self.exposure);
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// The rating a key sets, 0 for the one clearing it
+    fn rating_for_key(&self, code: KeyCode) -> Option<u8> {
+        let keys = &self.settings.keys;
+        [&keys.clear_rating, &keys.rate_1, &keys.rate_2, &keys.rate_3, &keys.rate_4, &keys.rate_5].iter()
+            .position(|key| Some(code) == key.get_keycode())
+            .map(|rating| rating as u8)
+    }
+    
+    /// Whether anything is drawn with the overlay, which then also gets the window's input first
+    fn overlay_active(&self) -> bool {
+        self.contact_sheet.is_some() || self.recents_open || self.error_screen.is_some() || self.pending_action.is_some() || self.toast.is_some()
+            || (self.paused && self.gif_frames.is_some()) || self.raw_preview || self.page_count.is_some()
+            || self.rating > 0 || self.flag != Flag::None || self.drop_hovered
+            || self.image_info.is_some() || (self.seek_bar_until.is_some() && self.gif_frames.is_some()) || self.loading_frames.is_some()
+    }
+    
+    fn run_action(&mut self, event_loop: &ActiveEventLoop, index: usize) {
+        let action = self.action_registry.resolve(&self.settings.actions[index]);
+        let ctx = ActionContext {
+            image_path: &self.image_path,
+            dimensions: (self.img_width, self.img_height),
+            frame: self.gif_frames.as_ref().map(|_| self.shown_frame_index),
+        };
+        let result = action.run(&ctx);
+        let label = action.label();
+        match result {
+            // sorting copies an image away and moves on, like a move does by removing it
+            Ok(()) if matches!(self.settings.actions[index], Action::CopyTo(_)) => {
+                self.step_image_from(&self.image_path.clone(), 1);
+            }
+            Ok(()) => {}
+            Err(e) => {
+                log::error!("Failed to run action \"{label}\". Error message: {e}");
+                self.show_toast(format!("{label} failed: {e}"));
+            }
+        }
+        if !self.image_path.exists() {
+            self.image_removed(event_loop);
+        }
+    }
+    
+    /// Moves on to the image that came after the shown one in its folder once it's gone,
+    /// or does what the settings say when the folder has no images left
+    fn image_removed(&mut self, event_loop: &ActiveEventLoop) {
+        let paths = image_paths_in_dir(image_dir(&self.image_path)).unwrap_or_default();
+        match replacement_for_removed(&paths, &self.image_path).cloned() {
+            Some(next) => {
+                self.open_path(&next);
+                // like the next image key, images that fail to load are stepped over
+                self.navigation_step = Some(1);
+                self.replacing_removed = Some(next);
+            }
+            None => self.no_images_left(event_loop),
+        }
+    }
+    
+    /// Clears the removed image and does what the settings ask for once its folder has nothing left to show
+    fn no_images_left(&mut self, event_loop: &ActiveEventLoop) {
+        let dir = image_dir(&self.image_path).display().to_string();
+        self.clear_image();
+        match self.settings.last_image_removed {
+            LastImageRemoved::Close => event_loop.exit(),
+            LastImageRemoved::Placeholder => {
+                self.error_screen = Some(ErrorScreen {
+                    heading: "No images left",
+                    message: format!("{dir} has no more images to show."),
+                });
+            }
+            LastImageRemoved::ContactSheet => {
+                if self.contact_sheet.is_none() {
+                    self.toggle_contact_sheet();
+                }
+            }
+        }
+    }
+    
+    fn slide_interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.settings.slideshow_interval_seconds.max(1)))
+    }
+    
+    /// Keeps the image that's about to be replaced for the swap key. Pixels are kept unless they're gone
+    /// or only a later page of a document, which is decoded again when swapped back
+    fn remember_previous_image(&mut self) {
+        let pixels = match (self.gif_frames.take(), self.current_hdr.take()) {
+            (Some(gif_frames), _) => Some(LoadedImage::Animated { frames: gif_frames, loop_count: self.loop_count }),
+            (None, Some(hdr)) => Some(LoadedImage::Hdr(hdr)),
+            (None, None) => self.current_image.take().filter(|_| self.page_index == 0).map(LoadedImage::Static),
+        };
+        self.previous_image = Some((self.image_path.clone(), pixels));
+    }
+    
+    /// Shows the previously shown image again, right away if its pixels were kept
+    fn swap_with_previous_image(&mut self) {
+        let Some((image_path, pixels)) = self.previous_image.take() else {
+            self.show_toast("No previous image to swap with".to_owned());
+            return;
+        };
+        if self.settings.swap_keeps_view {
+            let pan_offset = self.panning_data.pan_offset;
+            let pan = (pan_offset.x / self.img_width as f32, pan_offset.y / self.img_height as f32);
+            self.swap_view = Some((self.panning_data.zoom_level, pan));
+        }
+        match pixels {
+            Some(loaded_image) => self.show_image(image_path, loaded_image),
+            None => {
+                // replaced by the shown image once this one loads, kept until then so a failed load can be retried
+                self.previous_image = Some((image_path.clone(), None));
+                self.open_path(&image_path);
+            }
+        }
+    }
+    
+    /// Opens the next image of the slideshow and starts timing it
+    fn next_slide(&mut self) {
+        let Some(slideshow) = &mut self.slideshow else { return };
+        if let Some(image_path) = slideshow.advance().map(Path::to_path_buf) {
+            // a rotation of one would only flicker
+            if image_path != self.image_path {
+                self.open_path(&image_path);
+            }
+        }
+        self.next_slide_time = Some(Instant::now() + self.slide_interval());
+    }
+    
+    /// Catches the slideshow up with the hotfolders, moving on if the shown image is gone
+    fn refresh_slideshow(&mut self) {
+        let Some(slideshow) = &mut self.slideshow else { return };
+        let newest = slideshow.rescan().pop().filter(|_| self.settings.hotfolder_refresh == HotfolderRefresh::ShowNew);
+        let image_path = match newest {
+            Some(newest) => {
+                slideshow.show(newest.clone());
+                Some(newest)
+            }
+            None if !slideshow.current_exists() => slideshow.advance().map(Path::to_path_buf),
+            None => return,
+        };
+        match image_path {
+            Some(image_path) => {
+                self.open_path(&image_path);
+                self.next_slide_time = Some(Instant::now() + self.slide_interval());
+            }
+            // waits for the folders to fill up again
+            None => self.clear_image(),
+        }
+    }
+    
+    /// Reports a failure that leaves no window to show the error screen in with a native dialog, then closes luminix
+    fn startup_failed(&mut self, event_loop: &ActiveEventLoop, message: &str) {
+        report_startup_failure(message);
+        self.launch_failed = true;
+        event_loop.exit();
+    }
+    
+    /// Replaces the image with the error screen for `image_path`, which becomes the shown path
+    /// so browsing the folder starts where the image would have been
+    fn show_load_error(&mut self, image_path: PathBuf, error: &LoadImageError) {
+        self.error_screen = Some(ErrorScreen {
+            heading: "Can't open this image",
+            message: format!("Failed to load {}: {error}", image_path.display()),
+        });
+        self.image_path = image_path;
+        self.clear_image();
+    }
+    
+    /// Frees the shown image, leaving an empty window
+    fn clear_image(&mut self) {
+        // an image still loading would replace the placeholder
+        self.cancel_loading();
+        if let Some(renderer) = &mut self.renderer {
+            renderer.unload_texture();
+        }
+        self.current_image = None;
+        self.current_hdr = None;
+        self.gif_frames = None;
+        self.next_frame_time = None;
+        let window = self.window.as_ref().unwrap();
+        window.set_title("luminix");
+        window.request_redraw();
+    }
+    
+    fn toggle_contact_sheet(&mut self) {
+        self.recents_open = false;
+        if self.contact_sheet.take().is_none() {
+            match ContactSheet::new(&self.image_path, self.event_loop_proxy.clone().unwrap()) {
+                Ok(contact_sheet) => self.contact_sheet = Some(contact_sheet),
+                Err(e) => log::error!("Failed to open contact sheet. Error message: {e}"),
+            }
+        }
+        self.window.as_ref().unwrap().request_redraw();
+    }
+    
+    /// First and last frame to play, the whole animation unless a loop range is set
+    fn loop_bounds(&self, frame_count: usize) -> (u32, u32) {
+        let last_frame = u32::try_from(frame_count).unwrap_or_default().saturating_sub(1);
+        (self.loop_start.unwrap_or(0).min(last_frame), self.loop_end.unwrap_or(last_frame).min(last_frame))
+    }
+    
+    /// Decodes the page `step` pages away, wrapping around at either end. Pages of a different size are fitted anew
+    fn step_page(&mut self, step: isize) {
+        let Some(page_count) = self.page_count else {
+            return;
+        };
+        let page_index = (self.page_index as isize + step).rem_euclid(page_count as isize) as usize;
+        match image_loader::load_tiff_page(&self.image_path, page_index) {
+            Ok(page) => {
+                let size_changed = (page.width, page.height) != (self.img_width, self.img_height);
+                self.set_image(LoadedImage::Static(page));
+                self.page_index = page_index;
+                if size_changed {
+                    self.reset_view();
+                } else {
+                    self.update_view();
+                }
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            Err(e) => self.show_toast(format!("Can't show page {}: {e}", page_index + 1)),
+        }
+    }
+    
+    /// Uploads GIF frame `index`, or its difference to the frame before it while the diff view is on
+    fn load_frame_texture(&mut self, index: usize) {
+        let (Some(gif_frames), Some(renderer)) = (&self.gif_frames, &mut self.renderer) else {
+            return;
+        };
+        let frame = &gif_frames[index];
+        // the first frame follows the last one when looping
+        let previous = &gif_frames[index.checked_sub(1).unwrap_or(gif_frames.len() - 1)];
+        let difference = self.frame_diff.then(|| frame_difference(frame, previous)).flatten();
+        renderer.load_texture(difference.as_deref().unwrap_or(&frame.rgba_data), frame.width, frame.height, AlphaMode::Straight);
+    }
+    
+    /// How far the frame keys move, further with Shift held
+    fn frame_key_step(&self) -> isize {
+        let step = if self.modifiers.shift_key() { self.settings.frame_jump } else { self.settings.frame_step };
+        isize::try_from(step).unwrap_or(isize::MAX)
+    }
+    
+    /// Shows the frame `step` frames away from the shown one, wrapping around within the loop range
+    fn step_frames(&mut self, step: isize) {
+        let Some(frame_count) = self.gif_frames.as_ref().map(Vec::len) else {
+            return;
+        };
+        let (loop_start, loop_end) = self.loop_bounds(frame_count);
+        let length = (loop_end - loop_start) as isize + 1;
+        // the loop range may have been set around a frame other than the shown one
+        let offset = (self.shown_frame_index.clamp(loop_start, loop_end) - loop_start) as isize;
+        self.current_frame_index = loop_start + (offset + step % length).rem_euclid(length) as u32;
+        self.gif_next_frame();
+    }
+    
+    fn gif_next_frame(&mut self) {
+        if let Some(gif_frames) = self.gif_frames.clone() {
+            let current_frame = &gif_frames[self.current_frame_index as usize];
+            
+            // Update current image
+            self.current_image = Some(ImageData {
+                rgba_data: current_frame.rgba_data.clone(),
+                width: current_frame.width,
+                height: current_frame.height,
+            });
+            
+            // Load new texture into renderer
+            self.load_frame_texture(self.current_frame_index as usize);
+
+            // schedule the next frame
+            self.shown_frame_index = self.current_frame_index;
+            let (loop_start, loop_end) = self.loop_bounds(gif_frames.len());
+            if self.shown_frame_index == loop_end && !self.paused {
+                self.loop_iteration += 1;
+                // a loop range set by hand plays until it's cleared
+                let finite = self.loop_count.filter(|_| !self.settings.endless_animations && self.loop_start.is_none() && self.loop_end.is_none());
+                if finite.is_some_and(|loop_count| self.loop_iteration >= loop_count) {
+                    self.next_frame_time = None;
+                    self.window.as_ref().unwrap().request_redraw();
+                    return;
+                }
+            }
+            self.current_frame_index = if self.current_frame_index >= loop_end || self.current_frame_index < loop_start {
+                loop_start
+            } else {
+                self.current_frame_index + 1
+            };
+            self.next_frame_time = Some(Instant::now() + Duration::from_millis(u64::from(
+                gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().0 / gif_frames[self.current_frame_index as usize].delay.numer_denom_ms().1
+            )));
+            log::trace!("Showing frame {}, frame {} is next", self.shown_frame_index, self.current_frame_index);
+            self.window.as_ref().unwrap().request_redraw();
+        }
+    }
+}
+
+
+/// Message shown over the window instead of an image
+struct ErrorScreen {
+    heading: &'static str,
+    message: String,
+}
+
+/// Reload of the shown image waiting on the background loader
+#[derive(Debug, Copy, Clone)]
+struct PendingReload {
+    keep_view: bool,
+    /// Failures show a toast, reloads after changes on disk only log them since the file is often still being written
+    report_errors: bool,
+}
+
+/// Short message drawn over the image until `until`
+struct Toast {
+    message: String,
+    until: Instant,
+}
+
+/// Buttons on the error overlay
+enum ErrorChoice {
+    BrowseFolder,
+    Quit,
+}
+
+fn error_ui(ctx: &egui::Context, error_screen: &ErrorScreen) -> Option<ErrorChoice> {
+    let mut choice = None;
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() / 3.0);
+            ui.heading(error_screen.heading);
+            ui.label(&error_screen.message);
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Browse folder").clicked() {
+                    choice = Some(ErrorChoice::BrowseFolder);
+                }
+                if ui.button("Close luminix").clicked() {
+                    choice = Some(ErrorChoice::Quit);
+                }
+            });
+        });
+    });
+    choice
+}
+
+/// List of recently opened images, returns the one the user clicked on
+fn recents_ui(ctx: &egui::Context, recent_files: &RecentFiles) -> Option<PathBuf> {
+    let mut clicked = None;
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.heading("Recent files");
+        ui.add_space(8.0);
+        if recent_files.paths().is_empty() {
+            ui.label("Nothing opened yet");
+            return;
+        }
+        egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+            for path in recent_files.paths() {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if ui.button(name).on_hover_text(path.display().to_string()).clicked() {
+                    clicked = Some(path.clone());
+                }
+            }
+        });
+    });
+    clicked
+}
+
+/// Changed pixels from yellow for slight changes to red for large ones, over a darkened gray copy of `frame`
+/// to keep the bearings. `None` when the frames differ in size
+fn frame_difference(frame: &GifData, previous: &GifData) -> Option<Vec<u8>> {
+    if (frame.width, frame.height) != (previous.width, previous.height) {
+        return None;
+    }
+    Some(frame.rgba_data.chunks_exact(4).zip(previous.rgba_data.chunks_exact(4)).flat_map(|(pixel, previous_pixel)| {
+        let change = pixel.iter().zip(previous_pixel).map(|(value, previous_value)| value.abs_diff(*previous_value)).max().unwrap_or(0);
+        if change == 0 {
+            let luma = pixel[..3].iter().map(|&value| u32::from(value)).sum::<u32>() * u32::from(pixel[3]) / (3 * 255);
+            let gray = (luma / 4) as u8;
+            [gray, gray, gray, u8::MAX]
+        } else {
+            [u8::MAX, u8::MAX - change, 0, u8::MAX]
+        }
+    }).collect())
+}
+
+/// Window size covering `percent` of the monitor's work area along its tighter axis with the image's aspect,
+/// and the position centering it there. `None` when `percent` is 0, for opening at the image's size
+fn screen_share_geometry(monitor: &MonitorHandle, (img_width, img_height): (u32, u32), percent: u32) -> Option<(PhysicalSize<u32>, PhysicalPosition<i32>)> {
+    if percent == 0 || img_width == 0 || img_height == 0 {
+        return None;
+    }
+    let share = percent.min(100) as f64 / 100.0;
+    let (area_position, area_size) = work_area(monitor);
+    let usable_width = area_size.width.max(1) as f64;
+    let usable_height = area_size.height.max(1) as f64;
+    let scale = (usable_width * share / img_width as f64).min(usable_height * share / img_height as f64);
+    let size = PhysicalSize::new((img_width as f64 * scale).round().max(1.0) as u32, (img_height as f64 * scale).round().max(1.0) as u32);
+    let position = PhysicalPosition::new(
+        area_position.x + (area_size.width.saturating_sub(size.width) / 2) as i32,
+        area_position.y + (area_size.height.saturating_sub(size.height) / 2) as i32,
+    );
+    Some((size, position))
+}
+
+/// The part of the monitor the taskbar leaves free, or the whole monitor when Windows can't say
+#[cfg(target_os = "windows")]
+fn work_area(monitor: &MonitorHandle) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MONITORINFO};
+    use winit::platform::windows::MonitorHandleExtWindows;
+
+    let mut info = MONITORINFO { cbSize: size_of::<MONITORINFO>() as u32, ..Default::default() };
+    // SAFETY: the handle comes from winit and `info` is sized as the call expects
+    if unsafe { GetMonitorInfoW(monitor.hmonitor() as _, &mut info) } == 0 {
+        log::warn!("Failed to get the work area of monitor {:?}", monitor.name());
+        return (monitor.position(), monitor.size());
+    }
+    let work = info.rcWork;
+    let size = PhysicalSize::new(work.right.saturating_sub(work.left).max(0) as u32, work.bottom.saturating_sub(work.top).max(0) as u32);
+    (PhysicalPosition::new(work.left, work.top), size)
+}
+
+/// The monitor less [`SCREEN_EDGE_MARGIN`] at its edges
+#[cfg(not(target_os = "windows"))]
+fn work_area(monitor: &MonitorHandle) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let margin = (SCREEN_EDGE_MARGIN * monitor.scale_factor()).round() as u32;
+    let (position, size) = (monitor.position(), monitor.size());
+    (
+        PhysicalPosition::new(position.x + margin as i32, position.y + margin as i32),
+        PhysicalSize::new(size.width.saturating_sub(margin * 2), size.height.saturating_sub(margin * 2)),
+    )
+}
+
+/// `position` moved just enough for a window of `size` to lie on the monitor holding its top left corner,
+/// or on the first monitor when it's on none of them, say after that monitor was unplugged
+fn on_screen_position(monitors: &[MonitorHandle], position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> PhysicalPosition<i32> {
+    let contains = |monitor: &&MonitorHandle| {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        (origin.x..origin.x + extent.width as i32).contains(&position.x) && (origin.y..origin.y + extent.height as i32).contains(&position.y)
+    };
+    let Some(monitor) = monitors.iter().find(contains).or(monitors.first()) else {
+        return position;
+    };
+    let (origin, extent) = (monitor.position(), monitor.size());
+    // a window larger than the monitor keeps its top left corner on it
+    let max_x = origin.x + extent.width.saturating_sub(size.width) as i32;
+    let max_y = origin.y + extent.height.saturating_sub(size.height) as i32;
+    PhysicalPosition::new(position.x.clamp(origin.x, max_x), position.y.clamp(origin.y, max_y))
+}
+
+/// The image of `paths` that takes the place of `removed`, the one sorting after it or the last one when nothing does.
+/// `None` when the folder has no images left
+fn replacement_for_removed<'a>(paths: &'a [PathBuf], removed: &Path) -> Option<&'a PathBuf> {
+    let removed_key = sort_key(removed);
+    paths.iter().find(|path| sort_key(path) > removed_key).or(paths.last())
+}
+
+/// When frame `index` starts counted from the first frame and how long it stays, both in milliseconds
+fn frame_timing(frames: &[GifData], index: usize) -> (f64, f64) {
+    let milliseconds = |frame: &GifData| {
+        let (numerator, denominator) = frame.delay.numer_denom_ms();
+        f64::from(numerator) / f64::from(denominator)
+    };
+    let timestamp = frames[..index].iter().map(milliseconds).sum();
+    (timestamp, milliseconds(&frames[index]))
+}
+
+/// "`unit` 3/10" in the corner, for frames of paused animations and pages of documents. `None` shows as "?"
+/// for animations whose frames couldn't be counted before decoding them.
+/// `timing` is the frame's timestamp and delay in milliseconds, for checking an animation's timing while authoring it
+fn frame_counter_ui(ctx: &egui::Context, unit: &str, index: Option<usize>, count: Option<usize>, timing: Option<(f64, f64)>) {
+    egui::Area::new(egui::Id::new("frame counter"))
+        .anchor(egui::Align2::LEFT_BOTTOM, [16.0, -16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let known = |number: Option<usize>| number.map_or(String::from("?"), |number| number.to_string());
+                ui.label(format!("{unit} {}/{}", known(index.map(|index| index + 1)), known(count)));
+                if let Some((timestamp, delay)) = timing {
+                    ui.label(format!("{timestamp:.0} ms, shown for {delay:.0} ms"));
+                }
+            });
+        });
+}
+
+/// Timeline along the bottom of the window for jumping to any frame of an animation.
+/// Returns the frame it was clicked or dragged to and whether the mouse let go of it this frame
+fn seek_bar_ui(ctx: &egui::Context, index: usize, count: usize) -> (Option<usize>, bool) {
+    let mut seek_to = None;
+    let mut released = false;
+    egui::Area::new(egui::Id::new("seek bar"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -16.0])
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    // most of the window's width, leaving room for the counter
+                    ui.spacing_mut().slider_width = (ctx.content_rect().width() - 160.0).max(80.0);
+                    let mut frame = index + 1;
+                    let response = ui.add(egui::Slider::new(&mut frame, 1..=count).show_value(false));
+                    if response.changed() {
+                        seek_to = Some(frame - 1);
+                    }
+                    released = response.drag_stopped() || response.clicked();
+                    ui.label(format!("{frame}/{count}"));
+                });
+            });
+        });
+    (seek_to, released)
+}
+
+fn raw_preview_ui(ctx: &egui::Context) {
+    egui::Area::new(egui::Id::new("raw preview"))
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label("RAW preview");
+            }).response.on_hover_text("The JPEG the camera embedded, demosaicing can be turned on in the settings");
+        });
+}
+
+fn drop_hint_ui(ctx: &egui::Context) {
+    egui::Area::new(egui::Id::new("drop hint"))
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.heading("Drop to open");
+            });
+        });
+}
+
+/// The dimensions as they're shown, which quarter turns swap, followed by `image_info`.
+/// Returns the area the panel covers
+fn image_info_ui(ctx: &egui::Context, (width, height): (u32, u32), image_info: &[(&'static str, String)]) -> egui::Rect {
+    egui::Area::new(egui::Id::new("image info"))
+        .anchor(egui::Align2::LEFT_TOP, [16.0, 16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::Grid::new("image info").num_columns(2).show(ui, |ui| {
+                    ui.label("Dimensions");
+                    ui.label(format!("{width}x{height}"));
+                    ui.end_row();
+                    for (label, value) in image_info {
+                        ui.label(*label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+            });
+        })
+        .response
+        .rect
+}
+
+fn marks_ui(ctx: &egui::Context, rating: u8, flag: Flag) {
+    egui::Area::new(egui::Id::new("marks"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if rating > 0 {
+                        ui.label(format!("Rated {rating}/5"));
+                    }
+                    match flag {
+                        Flag::None => {}
+                        Flag::Pick => { ui.colored_label(egui::Color32::LIGHT_GREEN, "Picked"); }
+                        Flag::Reject => { ui.colored_label(egui::Color32::LIGHT_RED, "Rejected"); }
+                    }
+                });
+            });
+        });
+}
+
+fn toast_ui(ctx: &egui::Context, toast: &Toast) {
+    egui::Area::new(egui::Id::new("toast"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -16.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(&toast.message);
+            });
+        });
+}
+
+/// Prompt shown before running an action that asks first, returns whether the user confirmed or cancelled
+fn confirm_action_ui(ctx: &egui::Context, action: &dyn ImageAction, image_path: &Path) -> Option<bool> {
+    let mut confirmed = None;
+    let modal = egui::Modal::new(egui::Id::new("confirm action")).show(ctx, |ui| {
+        ui.heading(format!("Run \"{}\"?", action.label()));
+        ui.label(image_path.display().to_string());
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("Run").clicked() || ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                confirmed = Some(true);
+            }
+            if ui.button("Cancel").clicked() {
+                confirmed = Some(false);
+            }
+        });
+    });
+    // clicking outside the prompt or pressing escape cancels
+    if modal.should_close() && confirmed.is_none() {
+        confirmed = Some(false);
+    }
+    confirmed
+}
+
+/// Moves one axis of the pan offset by `offset` within `-limit..limit`, with `overscroll` deciding what happens past that.
+/// Returns whether the axis moved freely, a glide stops otherwise
+fn pan_axis(pan: &mut f32, offset: f32, limit: f32, overscroll: Overscroll) -> bool {
+    let target = *pan + offset;
+    if target.abs() < limit {
+        *pan = target;
+        return true;
+    }
+    match overscroll {
+        Overscroll::HardStop => false,
+        Overscroll::RubberBand => {
+            // pulling further out follows the mouse only partly, pushing back in is direct
+            *pan = if target.abs() > pan.abs() { *pan + offset * RUBBER_BAND_RESISTANCE } else { target };
+            false
+        }
+        Overscroll::Wrap => {
+            *pan = (target + limit).rem_euclid(2.0 * limit) - limit;
+            true
+        }
+    }
+}
+
+/// The zoom level whose factor is closest to `zoom_factor`
+fn zoom_level_for(zoom_factor: f32) -> i32 {
+    // inverse of `zoom_factor`, negative levels magnify
+    let zoom_level = if zoom_factor >= 1.0 {
+        -((zoom_factor - 1.0) / 0.2).round()
+    } else {
+        ((1.0 / zoom_factor - 1.0) / 0.2).round()
+    };
+    zoom_level as i32
+}
+
+/// Converts a zoom level to a zoom factor relative to fitting the window.
+/// Each level scrolled down shrinks the image by another fifth, each level up grows it by a fifth
+fn zoom_factor(zoom_level: i32) -> f32 {
+    if zoom_level >= 0 {
+        1.0 / (1.0 + zoom_level as f32 * 0.2)
+    } else {
+        1.0 + -zoom_level as f32 * 0.2
+    }
+}
+
+/// Replaces the tokens of a title template, `{index}` and `{count}` are left empty when the folder couldn't be listed
+/// and `{frame}` and `{frames}` for still images
+fn window_title(
+    template: &str,
+    image_path: &Path,
+    (width, height): (u32, u32),
+    magnification: f32,
+    folder_position: Option<(usize, usize)>,
+    frame: Option<(String, String)>,
+) -> String {
+    let (index, count) = folder_position.map(|(index, count)| (index.to_string(), count.to_string())).unwrap_or_default();
+    let (frame, frames) = frame.unwrap_or_default();
+    template
+        .replace("{name}", &image_path.file_name().unwrap_or_default().to_string_lossy())
+        .replace("{path}", &image_path.display().to_string())
+        .replace("{dims}", &format!("{width}x{height}"))
+        .replace("{zoom}", &format!("{:.0}%", magnification * 100.0))
+        .replace("{index}", &index)
+        .replace("{count}", &count)
+        .replace("{frame}", &frame)
+        .replace("{frames}", &frames)
+}
+
+/// Logs a failure that stops luminix before it has a window and shows it in a native dialog
+fn report_startup_failure(message: &str) {
+    log::error!("{message}");
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("luminix")
+        .set_description(message)
+        .show();
+}
+
+fn main() {
+    // errors and warnings unless RUST_LOG asks for more or less, e.g. RUST_LOG=luminix=debug
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    // check if valid args before anything else
+    let cli_args = match CliArgs::parse(env::args().skip(1)) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            eprintln!("{e}\n{}", cli::USAGE);
+            return;
+        }
+    };
+    if cli_args.reset_settings {
+        // recovery for when the settings window can't be reached
+        ConfigurableSettings::default().save();
+        println!("Wrote default settings to {}", ConfigurableSettings::path().display());
+        return;
+    }
+    if !cli_args.set_keys.is_empty() || cli_args.list_keys {
+        let mut settings = ConfigurableSettings::load();
+        for (binding, key_name) in &cli_args.set_keys {
+            if let Err(e) = settings.keys.set_binding(binding, key_name) {
+                log::error!("Failed to bind {binding} to {key_name}. Error message: {e}");
+                return;
+            }
+        }
+        if !cli_args.set_keys.is_empty() {
+            settings.save();
+            println!("Saved key bindings to {}", ConfigurableSettings::path().display());
+        }
+        if cli_args.list_keys {
+            print!("{}", settings.keys.list());
+        }
+        return;
+    }
+    
+    let mut cli_args = cli_args;
+    if !cli_args.playlist.is_empty() {
+        cli_args.playlist.retain(|path| {
+            let exists = path.exists();
+            if !exists {
+                log::warn!("Skipping {}, it doesn't exist", path.display());
+            }
+            exists
+        });
+        // with none left the first path stays, so the error screen says what's wrong with it
+        if let Some(first) = cli_args.playlist.first() {
+            cli_args.image_path = Some(first.clone());
+        }
+    }
+    if cli_args.image_path.is_none() && !cli_args.slideshow {
+        // started without a file, like from a shortcut, cancelling closes luminix again
+        let Some(image_path) = rfd::FileDialog::new()
+            .add_filter("Images", &image_loader::supported_extensions())
+            .pick_file() else { return };
+        cli_args.image_path = Some(image_path);
+    }
+    let settings = ConfigurableSettings::load();
+    // only the path is handed over, options given to this launch don't apply to the running instance.
+    // Screenshots need a window of their own to capture
+    let instance = match &cli_args.image_path {
+        Some(image_path) if settings.single_instance && cli_args.screenshot.is_none() => single_instance::claim(image_path),
+        _ => single_instance::Instance::Standalone,
+    };
+    if let single_instance::Instance::Forwarded = instance {
+        return;
+    }
+    
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build().unwrap();
+    event_loop.set_control_flow(ControlFlow::Wait);
+    if let single_instance::Instance::Primary(listener) = instance {
+        single_instance::listen(listener, event_loop.create_proxy());
+    }
+    let slideshow = if cli_args.slideshow {
+        let slideshow = Slideshow::new(&settings.hotfolders, event_loop.create_proxy()).and_then(|mut slideshow| {
+            match cli_args.image_path.clone() {
+                Some(image_path) => slideshow.show(image_path),
+                None => cli_args.image_path = Some(slideshow.current().ok_or(SlideshowError::NoImages)?.to_path_buf()),
+            }
+            Ok(slideshow)
+        });
+        match slideshow {
+            Ok(slideshow) => Some(slideshow),
+            Err(e) => {
+                report_startup_failure(&format!("Can't start the slideshow: {e}"));
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let decoders = Arc::new(Decoders::new(settings.raw_full_render));
+    let loader_proxy = event_loop.create_proxy();
+    let loader = BackgroundLoader::new(decoders.clone(), move |event| loader_proxy.send_event(event).is_ok());
+    let mut app = App {
+        slideshow,
+        loader: Some(loader),
+        decoders,
+        settings,
+        recent_files: RecentFiles::load(),
+        event_loop_proxy: Some(event_loop.create_proxy()),
+        cli_args,
+        ..App::default()
+    };
+    if app.settings.remember_display_state {
+        app.restore_display_state();
+    }
+    event_loop.run_app(&mut app).expect("error running event loop");
+    if app.launch_failed {
+        drop(app);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_image_is_replaced_by_the_next_one() {
+        let paths = [PathBuf::from("a.png"), PathBuf::from("c.png")];
+        assert_eq!(replacement_for_removed(&paths, Path::new("b.png")), Some(&paths[1]));
+        // at the end of the folder it goes back one instead
+        assert_eq!(replacement_for_removed(&paths, Path::new("d.png")), Some(&paths[1]));
+    }
+
+    #[test]
+    fn empty_folder_has_no_replacement() {
+        assert_eq!(replacement_for_removed(&[], Path::new("a.png")), None);
+    }
 }
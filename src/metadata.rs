@@ -0,0 +1,89 @@
+//! Capture details read from EXIF, for showing how a photo was taken
+
+use exif::{In, Tag, Value};
+use image::ImageFormat;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// Format and size of the file, for the info overlay. The format is guessed from the extension like the decoders do
+pub fn file_summary(image_path: &Path) -> Vec<(&'static str, String)> {
+    let format = ImageFormat::from_path(image_path).ok().map(|format| format!("{format:?}"))
+        // RAW files and the like aren't image crate formats
+        .or_else(|| image_path.extension().map(|extension| extension.to_string_lossy().to_uppercase()));
+    let size = fs::metadata(image_path).ok().map(|metadata| file_size_text(metadata.len()));
+    [("Format", format), ("File size", size)].into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+}
+
+/// `bytes` in the largest unit that keeps it at least 1
+fn file_size_text(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Clockwise rotation in degrees and horizontal flip that stand the image upright, from its EXIF orientation.
+/// The flip comes first, like in the shader. `None` for files without EXIF or without the tag
+pub fn exif_orientation(image_path: &Path) -> Option<(f32, bool)> {
+    let file = File::open(image_path).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok()?;
+    let orientation = exif.get_field(Tag::Orientation, In::PRIMARY)?.value.get_uint(0)?;
+    match orientation {
+        1 => Some((0.0, false)),
+        2 => Some((0.0, true)),
+        3 => Some((180.0, false)),
+        4 => Some((180.0, true)),
+        5 => Some((270.0, true)),
+        6 => Some((90.0, false)),
+        7 => Some((90.0, true)),
+        8 => Some((270.0, false)),
+        _ => None,
+    }
+}
+
+/// Labels and values of the EXIF fields a photographer looks at, in display order. Fields the file doesn't have are
+/// left out, so the list is empty for files without EXIF
+pub fn exif_summary(image_path: &Path) -> Vec<(&'static str, String)> {
+    let Ok(file) = File::open(image_path) else {
+        return Vec::new();
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return Vec::new();
+    };
+    let text = |tag| exif.get_field(tag, In::PRIMARY).map(|field| match &field.value {
+        // shown without the quotes and padding the generic formatting keeps
+        Value::Ascii(strings) => strings.iter()
+            .map(|string| String::from_utf8_lossy(string).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_owned())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => field.display_value().with_unit(&exif).to_string(),
+    }).filter(|value| !value.is_empty());
+
+    let camera = match (text(Tag::Make), text(Tag::Model)) {
+        // most models already start with the make
+        (Some(make), Some(model)) if !model.to_lowercase().starts_with(&make.to_lowercase()) => Some(format!("{make} {model}")),
+        (make, model) => model.or(make),
+    };
+    [
+        ("Camera", camera),
+        ("Lens", text(Tag::LensModel)),
+        ("ISO", text(Tag::PhotographicSensitivity)),
+        ("Shutter", text(Tag::ExposureTime)),
+        ("Aperture", text(Tag::FNumber)),
+        ("Focal length", text(Tag::FocalLength)),
+        ("Taken", text(Tag::DateTimeOriginal).or_else(|| text(Tag::DateTime))),
+        ("Orientation", text(Tag::Orientation)),
+    ].into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+}
@@ -0,0 +1,58 @@
+use egui::{ClippedPrimitive, Context, TexturesDelta, ViewportId};
+use egui_winit::{EventResponse, State};
+use std::time::Duration;
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+/// egui UI drawn on top of the image in the main window
+pub struct Overlay {
+    pub ctx: Context,
+    state: State,
+}
+
+/// Everything the renderer needs to draw one overlay frame
+pub struct OverlayOutput {
+    pub primitives: Vec<ClippedPrimitive>,
+    pub textures_delta: TexturesDelta,
+    pub pixels_per_point: f32,
+    /// egui wants another frame right away, e.g. while scrolling
+    pub repaint: bool,
+}
+
+impl Overlay {
+    pub fn new(window: &Window, max_texture_side: usize) -> Self {
+        let ctx = Context::default();
+        let state = State::new(
+            ctx.clone(),
+            ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            None,
+            Some(max_texture_side),
+        );
+        Overlay { ctx, state }
+    }
+
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
+        self.state.on_window_event(window, event)
+    }
+
+    /// Runs one frame of `run_ui`, sized by the window's current scale factor times `scale`.
+    /// The scale factor is read again every frame, so moving to a display with other scaling keeps text sharp
+    pub fn run(&mut self, window: &Window, scale: f32, run_ui: impl FnMut(&Context)) -> OverlayOutput {
+        self.ctx.set_zoom_factor(scale);
+        let input = self.state.take_egui_input(window);
+        let output = self.ctx.run(input, run_ui);
+        self.state.handle_platform_output(window, output.platform_output);
+
+        let repaint = output.viewport_output
+            .get(&ViewportId::ROOT)
+            .is_some_and(|viewport| viewport.repaint_delay == Duration::ZERO);
+        OverlayOutput {
+            primitives: self.ctx.tessellate(output.shapes, output.pixels_per_point),
+            textures_delta: output.textures_delta,
+            pixels_per_point: output.pixels_per_point,
+            repaint,
+        }
+    }
+}
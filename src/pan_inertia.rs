@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use winit::dpi::PhysicalPosition;
+
+/// How far back pan movements count towards the release velocity
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+/// Fraction of the velocity lost per second
+const FRICTION: f32 = 4.0;
+/// Glides slower than this (in pan offset units per second) stop
+const MIN_SPEED: f32 = 20.0;
+
+/// Keeps the image moving with decaying velocity after a pan is released
+#[derive(Default)]
+pub struct PanInertia {
+    recent_moves: VecDeque<(Instant, PhysicalPosition<f32>)>,
+    velocity: Option<PhysicalPosition<f32>>,
+    last_step: Option<Instant>,
+}
+
+impl PanInertia {
+    /// Records a pan movement while dragging
+    pub fn record(&mut self, offset: PhysicalPosition<f32>) {
+        let now = Instant::now();
+        self.recent_moves.push_back((now, offset));
+        while self.recent_moves.front().is_some_and(|(time, _)| now - *time > VELOCITY_WINDOW) {
+            self.recent_moves.pop_front();
+        }
+    }
+
+    /// Starts gliding with the velocity of the last few movements
+    pub fn release(&mut self) {
+        let now = Instant::now();
+        let mut recent_moves = self.recent_moves.drain(..).filter(|(time, _)| now - *time <= VELOCITY_WINDOW);
+        // each move covers the time since the one before it, so the first only marks where the measurement starts
+        let Some((first_time, _)) = recent_moves.next() else {
+            return;
+        };
+        let (last_time, total) = recent_moves.fold((first_time, PhysicalPosition::new(0.0, 0.0)), |(_, total), (time, offset)| {
+            (time, PhysicalPosition::new(total.x + offset.x, total.y + offset.y))
+        });
+        let seconds = (last_time - first_time).as_secs_f32();
+        if seconds <= 0.0 {
+            return;
+        }
+        self.velocity = Some(PhysicalPosition::new(total.x / seconds, total.y / seconds)).filter(|velocity| speed(*velocity) >= MIN_SPEED);
+        self.last_step = Some(now);
+    }
+
+    pub fn stop(&mut self) {
+        self.recent_moves.clear();
+        self.velocity = None;
+        self.last_step = None;
+    }
+
+    pub fn is_gliding(&self) -> bool {
+        self.velocity.is_some()
+    }
+
+    /// Advances the glide to `now`, returns the pan offset to apply
+    pub fn step(&mut self, now: Instant) -> Option<PhysicalPosition<f32>> {
+        let velocity = self.velocity.as_mut()?;
+        let dt = now.saturating_duration_since(self.last_step.unwrap_or(now)).as_secs_f32();
+        self.last_step = Some(now);
+
+        let offset = PhysicalPosition::new(velocity.x * dt, velocity.y * dt);
+        let decay = (-FRICTION * dt).exp();
+        velocity.x *= decay;
+        velocity.y *= decay;
+        if speed(*velocity) < MIN_SPEED {
+            self.velocity = None;
+        }
+        Some(offset)
+    }
+
+    /// Stops the glide along the axes that ran into the pan bounds
+    pub fn hit_edge(&mut self, x: bool, y: bool) {
+        if let Some(velocity) = &mut self.velocity {
+            if x {
+                velocity.x = 0.0;
+            }
+            if y {
+                velocity.y = 0.0;
+            }
+            if speed(*velocity) < MIN_SPEED {
+                self.velocity = None;
+            }
+        }
+    }
+}
+
+fn speed(velocity: PhysicalPosition<f32>) -> f32 {
+    velocity.x.hypot(velocity.y)
+}
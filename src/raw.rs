@@ -0,0 +1,223 @@
+//! Camera RAW files, shown through the JPEG preview the camera embeds or demosaiced in full
+
+use crate::errors::LoadImageError;
+use crate::image_loader::{ImageData, ImageDecoder, LoadedImage};
+use image::ImageFormat;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Formats recognized by extension. All but RAF keep their previews in TIFF structures, RAF is only demosaiced
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "nrw", "arw", "srf", "sr2", "dng", "orf", "rw2", "pef", "srw", "raf"];
+
+/// TIFF tags that lead to embedded images
+const TAG_COMPRESSION: u16 = 0x103;
+const TAG_STRIP_OFFSETS: u16 = 0x111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x117;
+const TAG_SUB_IFDS: u16 = 0x14A;
+const TAG_JPEG_OFFSET: u16 = 0x201;
+const TAG_JPEG_LENGTH: u16 = 0x202;
+/// Panasonic's RW2 stores its preview inline under this tag
+const TAG_RW2_JPEG: u16 = 0x2E;
+/// Old style JPEG and JPEG compression, the latter is also used for lossless raw data which only the header tells apart
+const COMPRESSION_JPEG: [u32; 2] = [6, 7];
+/// Guards against IFD loops and garbage offsets in damaged files
+const MAX_IFDS: usize = 32;
+
+pub fn is_raw(image_path: &Path) -> bool {
+    image_path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| RAW_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Shows RAW files through their embedded preview, or demosaiced in full if asked to or if there's no usable preview
+pub struct RawDecoder {
+    pub full_render: bool,
+}
+
+impl ImageDecoder for RawDecoder {
+    fn can_decode(&self, image_path: &Path) -> bool {
+        is_raw(image_path)
+    }
+
+    fn decode(&self, image_path: &Path) -> Result<LoadedImage, LoadImageError> {
+        if !self.full_render {
+            match decode_preview(image_path) {
+                Ok(Some(preview)) => return Ok(LoadedImage::RawPreview(preview)),
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to read the preview of {}, demosaicing instead. Error message: {e}", image_path.display()),
+            }
+        }
+        Ok(LoadedImage::Static(decode_full(image_path)?))
+    }
+}
+
+/// Decodes the largest embedded JPEG, `None` if the file has none that decodes
+pub fn decode_preview(image_path: &Path) -> Result<Option<ImageData>, LoadImageError> {
+    let mut file = BufReader::new(File::open(image_path)?);
+    for (offset, length) in preview_candidates(image_path)? {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut jpeg = vec![0; length as usize];
+        file.read_exact(&mut jpeg)?;
+        match image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg) {
+            Ok(preview) => {
+                let preview = preview.to_rgba8();
+                let (width, height) = preview.dimensions();
+                return Ok(Some(ImageData { rgba_data: preview.into_raw(), width, height }));
+            }
+            // a smaller preview might still work
+            Err(e) => log::warn!("Skipping an embedded preview of {}. Error message: {e}", image_path.display()),
+        }
+    }
+    Ok(None)
+}
+
+/// Demosaics the sensor data, slow but accurate and independent of what the camera embedded
+pub fn decode_full(image_path: &Path) -> Result<ImageData, LoadImageError> {
+    let rgb = imagepipe::simple_decode_8bit(image_path, 0, 0).map_err(LoadImageError::Raw)?;
+    let rgba_data = rgb.data.chunks_exact(3).flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX]).collect();
+    Ok(ImageData { rgba_data, width: rgb.width as u32, height: rgb.height as u32 })
+}
+
+/// Byte ranges of embedded baseline or progressive JPEGs, largest first
+fn preview_candidates(image_path: &Path) -> io::Result<Vec<(u64, u64)>> {
+    let mut file = BufReader::new(File::open(image_path)?);
+    let file_length = file.get_ref().metadata()?.len();
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+    let big_endian = match &header[..2] {
+        b"II" => false,
+        b"MM" => true,
+        // not TIFF based, e.g. RAF
+        _ => return Ok(Vec::new()),
+    };
+    let mut tiff = TiffReader { file, big_endian };
+
+    let mut candidates = Vec::new();
+    let mut pending_ifds = vec![tiff.u32_at(&header[4..])];
+    let mut visited = 0;
+    while let Some(ifd_offset) = pending_ifds.pop() {
+        if ifd_offset == 0 || u64::from(ifd_offset) >= file_length || visited == MAX_IFDS {
+            continue;
+        }
+        visited += 1;
+        let ifd = tiff.read_ifd(ifd_offset)?;
+        let value = |tag| ifd.entries.iter().find(|entry| entry.tag == tag).map(|entry| entry.value);
+        if let (Some(offset), Some(length)) = (value(TAG_JPEG_OFFSET), value(TAG_JPEG_LENGTH)) {
+            candidates.push((u64::from(offset), u64::from(length)));
+        }
+        if value(TAG_COMPRESSION).is_some_and(|compression| COMPRESSION_JPEG.contains(&compression)) {
+            if let (Some(offset), Some(length)) = (value(TAG_STRIP_OFFSETS), value(TAG_STRIP_BYTE_COUNTS)) {
+                candidates.push((u64::from(offset), u64::from(length)));
+            }
+        }
+        if let Some(entry) = ifd.entries.iter().find(|entry| entry.tag == TAG_RW2_JPEG) {
+            candidates.push((u64::from(entry.value), u64::from(entry.count)));
+        }
+        if let Some(entry) = ifd.entries.iter().find(|entry| entry.tag == TAG_SUB_IFDS) {
+            pending_ifds.extend(tiff.sub_ifd_offsets(entry)?);
+        }
+        pending_ifds.push(ifd.next);
+    }
+
+    candidates.retain(|(offset, length)| *length > 0 && offset.saturating_add(*length) <= file_length);
+    let mut previews = Vec::new();
+    for (offset, length) in candidates {
+        if tiff.is_viewable_jpeg(offset)? {
+            previews.push((offset, length));
+        }
+    }
+    previews.sort_by_key(|(offset, length)| (std::cmp::Reverse(*length), *offset));
+    previews.dedup();
+    Ok(previews)
+}
+
+struct TiffReader {
+    file: BufReader<File>,
+    big_endian: bool,
+}
+
+struct Ifd {
+    entries: Vec<IfdEntry>,
+    next: u32,
+}
+
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    /// The value itself for single SHORT and LONG values, otherwise the offset of the data
+    value: u32,
+}
+
+impl TiffReader {
+    fn u16_at(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+    }
+
+    fn u32_at(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    }
+
+    fn read_ifd(&mut self, offset: u32) -> io::Result<Ifd> {
+        self.file.seek(SeekFrom::Start(u64::from(offset)))?;
+        let mut count = [0; 2];
+        self.file.read_exact(&mut count)?;
+        let mut entries = Vec::new();
+        for _ in 0..self.u16_at(&count) {
+            let mut entry = [0; 12];
+            self.file.read_exact(&mut entry)?;
+            let field_type = self.u16_at(&entry[2..]);
+            let count = self.u32_at(&entry[4..]);
+            // a single SHORT sits in the first two bytes of the value field
+            let value = if field_type == 3 && count == 1 { u32::from(self.u16_at(&entry[8..])) } else { self.u32_at(&entry[8..]) };
+            entries.push(IfdEntry { tag: self.u16_at(&entry), field_type, count, value });
+        }
+        let mut next = [0; 4];
+        self.file.read_exact(&mut next)?;
+        Ok(Ifd { entries, next: self.u32_at(&next) })
+    }
+
+    /// Offsets listed by a SubIFDs entry, inline when there's only one
+    fn sub_ifd_offsets(&mut self, entry: &IfdEntry) -> io::Result<Vec<u32>> {
+        // LONG or IFD typed
+        if entry.count <= 1 || !matches!(entry.field_type, 4 | 13) {
+            return Ok(vec![entry.value]);
+        }
+        self.file.seek(SeekFrom::Start(u64::from(entry.value)))?;
+        let mut offsets = Vec::new();
+        for _ in 0..entry.count.min(MAX_IFDS as u32) {
+            let mut offset = [0; 4];
+            self.file.read_exact(&mut offset)?;
+            offsets.push(self.u32_at(&offset));
+        }
+        Ok(offsets)
+    }
+
+    /// Whether the JPEG at `offset` is baseline or progressive, the lossless JPEGs holding raw sensor data are not
+    fn is_viewable_jpeg(&mut self, offset: u64) -> io::Result<bool> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut marker = [0; 2];
+        self.file.read_exact(&mut marker)?;
+        if marker != [0xFF, 0xD8] {
+            return Ok(false);
+        }
+        // walks the segments up to the frame header
+        loop {
+            let mut segment = [0; 4];
+            if self.file.read_exact(&mut segment).is_err() || segment[0] != 0xFF {
+                return Ok(false);
+            }
+            match segment[1] {
+                0xC0..=0xC2 => return Ok(true),
+                0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xDA | 0xD9 => return Ok(false),
+                _ => {
+                    let length = u16::from_be_bytes([segment[2], segment[3]]);
+                    self.file.seek_relative(i64::from(length) - 2)?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::settings_window::ConfigurableSettings;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Recently opened images, newest first.
+/// Kept in their own file next to the settings, so opening an image never saves settings that weren't applied yet
+#[derive(Serialize, Deserialize, Default)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    pub fn path() -> PathBuf {
+        ConfigurableSettings::path().with_file_name("luminix-recents.ron")
+    }
+
+    pub fn save(&self) {
+        let f = match File::create(Self::path()) {
+            Ok(f) => f,
+            Err(e) => {
+                log::error!("Failed to save luminix-recents.ron. Error message: {e}");
+                return;
+            }
+        };
+        if let Err(e) = ron::Options::default().to_io_writer_pretty(f, self, ron::ser::PrettyConfig::new()) {
+            log::error!("Failed to save luminix-recents.ron. Error message: {e}");
+        }
+    }
+
+    /// Reads the list and drops files that were deleted or moved since
+    pub fn load() -> RecentFiles {
+        // a missing file just means nothing was opened yet
+        let Ok(f) = File::open(Self::path()) else { return RecentFiles::default() };
+        let mut recent_files: RecentFiles = ron::de::from_reader(f).unwrap_or_else(|e| {
+            log::warn!("Failed to load luminix-recents.ron, starting with an empty list. Error message: {e}");
+            RecentFiles::default()
+        });
+        recent_files.paths.retain(|path| path.exists());
+        recent_files
+    }
+
+    /// Moves `image_path` to the front, keeping at most `limit` entries
+    pub fn add(&mut self, image_path: &Path, limit: usize) {
+        // absolute, so relative paths from the command line still work from another working directory
+        let image_path = image_path.canonicalize().unwrap_or_else(|_| image_path.to_path_buf());
+        self.paths.retain(|path| *path != image_path);
+        self.paths.insert(0, image_path);
+        self.paths.truncate(limit);
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
@@ -1,3 +1,7 @@
+/// Registry class every registered extension points at
+#[cfg(target_os = "windows")]
+const WINDOWS_PROG_ID: &str = "Luminix.Image";
+
 #[cfg(target_os = "windows")]
 pub fn register_file_association() -> anyhow::Result<()> {
     use winreg::enums::{HKEY_CURRENT_USER, KEY_WRITE};
@@ -10,17 +14,200 @@ pub fn register_file_association() -> anyhow::Result<()> {
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     let classes = hkcu.open_subkey_with_flags("Software\\Classes", KEY_WRITE)?;
     
-    
-    let (key, _) = classes.create_subkey(".png")?;
-    key.set_value("", &"Luminix.Image")?;
+    for extension in crate::image_loader::supported_extensions() {
+        let (key, _) = classes.create_subkey(format!(".{extension}"))?;
+        key.set_value("", &WINDOWS_PROG_ID)?;
+    }
 
-    let (image_key, _) = classes.create_subkey("Luminix.Image\\shell\\open\\command")?;
+    let (image_key, _) = classes.create_subkey(format!("{WINDOWS_PROG_ID}\\shell\\open\\command"))?;
     image_key.set_value("", &exe_str)?;
     
     Ok(())
 }
+
+/// Undoes [`register_file_association`], extensions another program took over since are left alone.
+/// Nothing is removed if the class opens a different luminix executable
+#[cfg(target_os = "windows")]
+pub fn unregister_file_association() -> anyhow::Result<()> {
+    use anyhow::Context;
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_READ, KEY_WRITE};
+    use winreg::RegKey;
+    use std::{env, io};
+
+    let exe_path = env::current_exe().context("Can't get path to self")?;
+    let exe_str = format!(r#""{}" "%1""#, exe_path.display());
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let classes = hkcu.open_subkey_with_flags("Software\\Classes", KEY_READ | KEY_WRITE)?;
+
+    match classes.open_subkey(format!("{WINDOWS_PROG_ID}\\shell\\open\\command")) {
+        Ok(command_key) => {
+            let command: String = command_key.get_value("").unwrap_or_default();
+            if !command.eq_ignore_ascii_case(&exe_str) {
+                anyhow::bail!("the association opens {command}, unregister from that luminix");
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    for extension in crate::image_loader::supported_extensions() {
+        let key = match classes.open_subkey_with_flags(format!(".{extension}"), KEY_READ | KEY_WRITE) {
+            Ok(key) => key,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if key.get_value::<String, _>("").is_ok_and(|prog_id| prog_id == WINDOWS_PROG_ID) {
+            key.delete_value("")?;
+        }
+    }
+
+    match classes.delete_subkey_all(WINDOWS_PROG_ID) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// MIME types luminix asks to be the default viewer for
+#[cfg(target_os = "linux")]
+const LINUX_MIME_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/tiff",
+    "image/bmp",
+    "image/vnd.microsoft.icon",
+    "image/x-tga",
+    "image/x-exr",
+    "image/vnd.radiance",
+    #[cfg(feature = "avif")]
+    "image/avif",
+];
+
+/// Name of the desktop entry the image types are pointed at
+#[cfg(target_os = "linux")]
+const DESKTOP_FILE: &str = "luminix.desktop";
+
+/// The user's applications folder, where desktop entries take effect without root
+#[cfg(target_os = "linux")]
+fn linux_applications_dir() -> anyhow::Result<std::path::PathBuf> {
+    use anyhow::Context;
+    use std::env;
+    use std::path::PathBuf;
+
+    let data_home = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .context("neither XDG_DATA_HOME nor HOME is set")?;
+    Ok(data_home.join("applications"))
+}
+
+/// `Exec` line starting the running executable with the opened files
+#[cfg(target_os = "linux")]
+fn linux_exec_line() -> anyhow::Result<String> {
+    use anyhow::Context;
+
+    let exe_path = std::env::current_exe().context("Can't get path to self")?;
+    // quoted as the desktop entry spec asks, then backslashes escaped again for the string value itself
+    let mut quoted = String::from("\"");
+    for c in exe_path.to_string_lossy().chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    Ok(format!("Exec={} %F", quoted.replace('\\', "\\\\")))
+}
+
+/// Makes luminix the default viewer by writing a desktop entry to the user's applications folder
+/// and pointing the image types at it through `xdg-mime`
 #[cfg(target_os = "linux")]
 pub fn register_file_association() -> anyhow::Result<()> {
-    todo!();
+    use anyhow::Context;
+    use std::fs;
+    use std::process::Command;
+
+    let applications = linux_applications_dir()?;
+    fs::create_dir_all(&applications).with_context(|| format!("Can't create {}", applications.display()))?;
+
+    let desktop_path = applications.join(DESKTOP_FILE);
+    let desktop_entry = format!(
+        "[Desktop Entry]\nType=Application\nName=luminix\nComment=Image viewer\n{}\nTerminal=false\nCategories=Graphics;Viewer;\nMimeType={};\n",
+        linux_exec_line()?,
+        LINUX_MIME_TYPES.join(";"),
+    );
+    fs::write(&desktop_path, desktop_entry).with_context(|| format!("Can't write {}", desktop_path.display()))?;
+
+    let status = Command::new("xdg-mime").arg("default").arg(DESKTOP_FILE).args(LINUX_MIME_TYPES).status()
+        .context("Can't run xdg-mime, is xdg-utils installed?")?;
+    if !status.success() {
+        anyhow::bail!("xdg-mime failed with {status}");
+    }
+    update_desktop_database(&applications);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Removes the desktop entry and drops it from the defaults in `mimeapps.list`, so the types fall back to other viewers.
+/// An entry written by a luminix elsewhere on disk is kept
+#[cfg(target_os = "linux")]
+pub fn unregister_file_association() -> anyhow::Result<()> {
+    use anyhow::Context;
+    use std::path::PathBuf;
+    use std::{env, fs, io};
+
+    let applications = linux_applications_dir()?;
+    let desktop_path = applications.join(DESKTOP_FILE);
+    match fs::read_to_string(&desktop_path) {
+        Ok(desktop_entry) => {
+            let exec_line = linux_exec_line()?;
+            if !desktop_entry.lines().any(|line| line == exec_line) {
+                anyhow::bail!("{} starts a different luminix executable, unregister from that one", desktop_path.display());
+            }
+            fs::remove_file(&desktop_path).with_context(|| format!("Can't remove {}", desktop_path.display()))?;
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("Can't read {}", desktop_path.display())),
+    }
+
+    // where xdg-mime writes the defaults
+    let config_home = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .context("neither XDG_CONFIG_HOME nor HOME is set")?;
+    let mimeapps_path = config_home.join("mimeapps.list");
+    let mimeapps = match fs::read_to_string(&mimeapps_path) {
+        Ok(mimeapps) => mimeapps,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Can't read {}", mimeapps_path.display())),
+    };
+    let mut cleaned = String::new();
+    let mut in_defaults = false;
+    for line in mimeapps.lines() {
+        if line.starts_with('[') {
+            in_defaults = line.trim() == "[Default Applications]";
+        } else if let Some((mime_type, apps)) = line.split_once('=').filter(|(_, apps)| in_defaults && apps.split(';').any(|app| app == DESKTOP_FILE)) {
+            let others: Vec<_> = apps.split(';').filter(|app| !app.is_empty() && *app != DESKTOP_FILE).collect();
+            if others.is_empty() {
+                continue;
+            }
+            cleaned.push_str(&format!("{mime_type}={};\n", others.join(";")));
+            continue;
+        }
+        cleaned.push_str(line);
+        cleaned.push('\n');
+    }
+    if cleaned != mimeapps {
+        fs::write(&mimeapps_path, cleaned).with_context(|| format!("Can't write {}", mimeapps_path.display()))?;
+    }
+    update_desktop_database(&applications);
+    Ok(())
+}
+
+/// Refreshes the MIME cache for "Open With" menus, the defaults take effect without it so a missing tool is fine
+#[cfg(target_os = "linux")]
+fn update_desktop_database(applications: &std::path::Path) {
+    match std::process::Command::new("update-desktop-database").arg(applications).status() {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => log::error!("Failed to run update-desktop-database. Error message: {e}"),
+        _ => {}
+    }
+}
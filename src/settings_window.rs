@@ -1,19 +1,28 @@
-use crate::errors::{CommandExecutionError, RunActionError};
-use crate::register_file_association::register_file_association;
+use crate::errors::{CommandExecutionError, FileActionError, RunActionError, SetKeyError};
+use crate::image_action::{ActionContext, ImageAction};
+use crate::metadata::exif_summary;
+use crate::wgpu_renderer::{AlphaMode, Background, Colormap, FitMode, ImageFrame, ToneMapping, ZoomPolicy, MAX_SHARPEN, MIN_FILM_BASE};
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use crate::register_file_association::{register_file_association, unregister_file_association};
 use derivative::Derivative;
 use egui::{self, hex_color, Align, Context, InputState, Key, KeyboardShortcut, Layout, ModifierNames, PointerButton, RichText, Separator, SliderClamping, Style, Ui, Vec2, ViewportBuilder, Widget};
 use egui_extras::{Column, TableBuilder};
 use egui_keybind::{Bind, Keybind};
 use egui_winit::State;
-use serde::{Deserialize, Serialize};
+use serde::de::{EnumAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::any::TypeId;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::ops::{Index, IndexMut, Range};
-use std::{array, env, fmt};
-use strum::{EnumCount, EnumIter, EnumMessage, IntoEnumIterator};
+use std::fs::{self, File};
+use std::ops::{Index, IndexMut, Range, RangeInclusive};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::{array, env, fmt, io};
+use strum::{AsRefStr, EnumCount, EnumIter, EnumMessage, IntoEnumIterator};
 use wgpu::{self, Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration};
+#[cfg(target_os = "windows")]
 use winit::dpi::PhysicalSize;
+#[cfg(target_os = "windows")]
 use winit::window::Icon;
 use winit::event::WindowEvent;
 use winit::event_loop::ActiveEventLoop;
@@ -23,7 +32,18 @@ use winit::platform::windows::{IconExtWindows, WindowExtWindows};
 
 pub struct SettingsWindow {
     pub state: State,
-    pub configurable_settings: ConfigurableSettings,
+    /// Image shown in the main window, used to test commands
+    pub current_image_path: Option<PathBuf>,
+    pub current_image_dimensions: (u32, u32),
+    /// EXIF summary of `current_image_path`, read again when that changes
+    image_info: Option<(PathBuf, Vec<(&'static str, String)>)>,
+    /// Heights the command editors took up last frame, to size their table rows
+    command_editor_heights: [f32; ACTION_AMOUNT],
+    /// Outcome of the last press of the file association button
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    file_association_status: Option<String>,
+    /// Labels of the registered actions an action slot can be set to
+    pub registered_actions: Vec<String>,
     pub ctx: Context,
     pub window: winit::window::Window,
     // WGPU components
@@ -34,14 +54,254 @@ pub struct SettingsWindow {
     queue: Option<Queue>,
     config: Option<SurfaceConfiguration>,
     egui_rpass: Option<egui_wgpu::Renderer>,
+    /// Whether the last frame failed and egui was reset to retry it
+    retrying_frame: bool,
 }
 
 const ACTION_AMOUNT: usize = 2;
+const MIN_OVERLAY_SCALE: f32 = 0.5;
+const MAX_OVERLAY_SCALE: f32 = 3.0;
+/// Largest border and shadow around the image, in screen pixels
+const MAX_FRAME_WIDTH: f32 = 32.0;
+const MAX_FRAME_SHADOW: f32 = 64.0;
+/// Furthest `min_zoom` can zoom out and `max_zoom` in, as multiples of the fitted size
+const MIN_ZOOM: f32 = 0.01;
+const MAX_ZOOM: f32 = 100.0;
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConfigurableSettings {
     pub keys: Keys,
+    #[serde(deserialize_with = "deserialize_array_lenient")]
     pub actions: [Action; ACTION_AMOUNT],
-    pub pan_multiplier: f32
+    /// Ask before running the action with the same index
+    #[serde(deserialize_with = "deserialize_array_lenient")]
+    pub confirm_actions: [bool; ACTION_AMOUNT],
+    pub pan_multiplier: f32,
+    /// Keep the image moving for a moment after a pan is released
+    pub pan_inertia: bool,
+    /// What panning does at the edge of the pan bounds
+    pub overscroll: Overscroll,
+    /// Scrolling down zooms in
+    pub reverse_zoom: bool,
+    /// Zoom keys keep the point last under the cursor in place instead of the image's center
+    pub keyboard_zoom_follows_cursor: bool,
+    /// Smallest the image can be zoomed out to, as a multiple of its fitted size. At most 1.0 so the fit stays reachable
+    pub min_zoom: f32,
+    /// Largest the image can be zoomed in to, as a multiple of its fitted size. At least 1.0
+    pub max_zoom: f32,
+    /// Off by default so that Escape keeps doing nothing unless the user opts in
+    pub quit_key_enabled: bool,
+    pub smooth_filtering: bool,
+    /// Zoom only in whole multiples of the image's pixels, for crisp pixel art
+    pub integer_scaling: bool,
+    /// Magnification (screen pixels per image pixel) at which filtering switches to nearest
+    pub nearest_filter_threshold: f32,
+    pub show_pixel_grid: bool,
+    /// Magnification (screen pixels per image pixel) at which the pixel grid appears
+    pub pixel_grid_threshold: f32,
+    /// How much the sharpen key boosts edges, capped since strong sharpening rings around them
+    pub sharpen_strength: f32,
+    /// Keeps the frame counter up during playback with each frame's timestamp and delay, for animation authors
+    pub show_frame_timing: bool,
+    /// Loop animations forever, ignoring the loop count stored in the file
+    pub endless_animations: bool,
+    /// Keep playing after letting go of the seek bar, if the animation was playing when the drag started
+    pub seek_resumes_playback: bool,
+    /// Frames or pages the frame keys move by
+    pub frame_step: u32,
+    /// Frames or pages the frame keys move by with Shift held
+    pub frame_jump: u32,
+    /// Film stock whose base color the negative key corrects out
+    pub film_base: FilmBase,
+    /// sRGB color of the bare film for [`FilmBase::Manual`], as sampled from the unexposed edge of a scan
+    pub manual_film_base: [f32; 3],
+    /// The swap key carries zoom and pan over to the other image, for comparing the same spot
+    pub swap_keeps_view: bool,
+    /// Reopen with the display toggles as they were when luminix was last closed
+    pub remember_display_state: bool,
+    /// Toggles saved on exit while `remember_display_state` is on
+    pub display_state: DisplayState,
+    /// Encoding of the image copied by the data URL key
+    pub data_url_format: DataUrlFormat,
+    /// Copy a whole `data:` URL instead of just the base64 text
+    pub data_url_prefix: bool,
+    /// Draw a border and shadow around the image where the window shows past its edges
+    pub image_frame: bool,
+    /// Border width in screen pixels, 0 for only the shadow
+    pub frame_width: f32,
+    /// How far the shadow reaches past the border in screen pixels, 0 for only the border
+    pub frame_shadow: f32,
+    /// sRGB border color with straight alpha
+    pub frame_color: [f32; 4],
+    /// Pick the checkerboard for images with transparency and a plain background for opaque ones on load
+    pub auto_background: bool,
+    /// sRGB color behind the image when the background is solid
+    pub background_color: [f32; 3],
+    /// How EXR and HDR images are mapped to the displayable range
+    pub tone_mapping: ToneMapping,
+    /// Whether 8 bit images store straight or premultiplied alpha, files don't say so themselves.
+    /// EXR images are always premultiplied and GIFs have no partial transparency, so neither follows this
+    pub alpha_mode: AlphaMode,
+    pub last_image_removed: LastImageRemoved,
+    /// Most images kept in the recent files list, 0 stops recording them
+    pub recent_files_limit: usize,
+    /// Transparent window through DX12's visual swapchain, read at startup and only used on Windows
+    pub visual_swapchain: bool,
+    /// Bring the window to the front when it opens or is handed another image
+    pub activate_on_open: bool,
+    /// Window title with `{name}`, `{path}`, `{dims}`, `{zoom}`, `{index}`, `{count}`, `{frame}` and `{frames}` filled in
+    pub title_template: String,
+    /// Keep zoom and pan when the reload key re-reads the image
+    pub reload_keeps_view: bool,
+    /// Applies rotation, flip and crop from `<image>.luminix.json` when opening an image
+    pub read_sidecars: bool,
+    /// Stands photos upright by their EXIF orientation when the sidecar doesn't set a view
+    pub auto_orient: bool,
+    /// Keeps the system from sleeping or starting the screensaver while the image window is focused, Windows only
+    pub prevent_sleep: bool,
+    /// Seconds without input until the image is dimmed, 0 never dims
+    pub idle_dim_seconds: u32,
+    /// Size of text and panels drawn over the image, on top of the display's own scaling
+    pub overlay_scale: f32,
+    /// Opening another image while luminix runs shows it in the running window instead of a new one
+    pub single_instance: bool,
+    /// Folders `--slideshow` cycles through, watched for images being added and removed
+    pub hotfolders: Vec<PathBuf>,
+    /// How long each image stays in the slideshow
+    pub slideshow_interval_seconds: u32,
+    /// What the slideshow does with images added to the hotfolders
+    pub hotfolder_refresh: HotfolderRefresh,
+    /// Demosaic RAW files instead of showing the camera's embedded preview, read at startup
+    pub raw_full_render: bool,
+    /// Stretches images whose pixels aren't square, as stored in PNG and JPEG headers, to their intended proportions
+    pub honor_pixel_aspect: bool,
+    /// Percentage of the screen the window opens at, keeping the image's aspect and centered. 0 opens it at the image's size
+    pub window_screen_percent: u32,
+    /// Reopen the window where and as large as it was when luminix was last closed, instead of sizing it to the image
+    pub remember_window_geometry: bool,
+    /// Inner size in physical pixels, saved on exit while `remember_window_geometry` is on
+    pub last_window_size: Option<(u32, u32)>,
+    /// Outer position in physical pixels, Wayland doesn't tell it
+    pub last_window_position: Option<(i32, i32)>,
+}
+
+/// How panning behaves once the image reaches the edge of where it can be panned
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug, EnumIter)]
+pub enum Overscroll {
+    /// The image stops at the edge
+    #[default]
+    HardStop,
+    /// The image can be pulled past the edge with resistance and springs back on release
+    RubberBand,
+    /// The image comes back in from the opposite edge, for seamless tiles
+    Wrap,
+}
+impl Display for Overscroll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Overscroll::HardStop => write!(f, "Stop"),
+            Overscroll::RubberBand => write!(f, "Rubber band"),
+            Overscroll::Wrap => write!(f, "Wrap around"),
+        }
+    }
+}
+
+/// Color of unexposed film, which the negative inversion divides out before inverting
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug, EnumIter)]
+pub enum FilmBase {
+    /// Black and white film, a plain inversion
+    Neutral,
+    /// The orange mask of Kodak color negative film
+    #[default]
+    Kodak,
+    /// Fujifilm color negatives, a little less saturated orange
+    Fujifilm,
+    Manual,
+}
+impl FilmBase {
+    /// sRGB color of the film base, `manual` is used for [`FilmBase::Manual`]
+    pub fn color(self, manual: [f32; 3]) -> [f32; 3] {
+        match self {
+            FilmBase::Neutral => [1.0, 1.0, 1.0],
+            FilmBase::Kodak => [0.93, 0.60, 0.40],
+            FilmBase::Fujifilm => [0.88, 0.62, 0.46],
+            FilmBase::Manual => manual,
+        }
+    }
+}
+impl Display for FilmBase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FilmBase::Neutral => write!(f, "Black and white"),
+            FilmBase::Kodak => write!(f, "Kodak color negative"),
+            FilmBase::Fujifilm => write!(f, "Fujifilm color negative"),
+            FilmBase::Manual => write!(f, "Manual"),
+        }
+    }
+}
+
+/// Display toggles that otherwise start out off with every launch
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct DisplayState {
+    pub fit_mode: FitMode,
+    /// Only used with `auto_background` off, otherwise the image decides
+    pub background: Background,
+    pub colormap: Colormap,
+    pub sharpen: bool,
+    pub negative: bool,
+}
+
+/// How a slideshow over hotfolders reacts to new images
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug, EnumIter)]
+pub enum HotfolderRefresh {
+    /// New images wait for their turn in file name order
+    #[default]
+    Rotation,
+    /// New images are shown as soon as they arrive, then the rotation continues from them
+    ShowNew,
+}
+impl Display for HotfolderRefresh {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HotfolderRefresh::Rotation => write!(f, "In their turn"),
+            HotfolderRefresh::ShowNew => write!(f, "Right away"),
+        }
+    }
+}
+
+/// Format the data URL key encodes the image in, PNG keeps it lossless and transparent, JPEG keeps the text short
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug, EnumIter)]
+pub enum DataUrlFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+impl Display for DataUrlFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DataUrlFormat::Png => write!(f, "PNG"),
+            DataUrlFormat::Jpeg => write!(f, "JPEG"),
+        }
+    }
+}
+
+/// What happens once the shown image was moved away and its folder has no images left
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Debug, EnumIter)]
+pub enum LastImageRemoved {
+    Close,
+    #[default]
+    Placeholder,
+    ContactSheet,
+}
+impl Display for LastImageRemoved {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LastImageRemoved::Close => write!(f, "Close luminix"),
+            LastImageRemoved::Placeholder => write!(f, "Show a message"),
+            LastImageRemoved::ContactSheet => write!(f, "Open the contact sheet"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq,Debug, EnumIter)]
@@ -49,21 +309,44 @@ pub enum Action {
     #[default]
     None,
     Command(ShellCommand),
+    CopyTo(Destination),
+    MoveTo(Destination),
+    /// An action from the [`ActionRegistry`](crate::image_action::ActionRegistry), stored by its label
+    Registered(String),
 }
 impl Display for Action {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Action::Command(_) => {write!(f, "Command")}
+            Action::CopyTo(_) => {write!(f, "Copy to folder")}
+            Action::MoveTo(_) => {write!(f, "Move to folder")}
+            Action::Registered(_) => {write!(f, "Registered action")}
             Action::None => {write!(f, "None")}
         }
     }
 }
-impl Action {
-    pub fn run_action(&self) -> Result<(), RunActionError> {
+impl ImageAction for Action {
+    fn label(&self) -> String {
+        match self {
+            Action::Registered(label) => label.clone(),
+            _ => self.to_string(),
+        }
+    }
+    fn run(&self, ctx: &ActionContext) -> Result<(), RunActionError> {
         match &self {
             Action::None => {Ok(())}
             Action::Command(shell_command) => {
-                shell_command.execute().map_err(RunActionError::from)
+                shell_command.execute(ctx).map_err(RunActionError::from)
+            }
+            Action::CopyTo(destination) => {
+                destination.copy_image(ctx.image_path).map_err(RunActionError::from)
+            }
+            Action::MoveTo(destination) => {
+                destination.move_image(ctx.image_path).map_err(RunActionError::from)
+            }
+            // only reached when the registry had nothing under this label
+            Action::Registered(label) => {
+                Err(RunActionError::Unregistered(label.clone()))
             }
         }
     }
@@ -87,11 +370,15 @@ impl Display for ShellCommand {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { write!(f, "{}", self.0) }
 }
 impl ShellCommand {
-    fn execute(&self) -> Result<(), CommandExecutionError>{
-        let image_path = env::args().nth(1).expect("THIS IS A BUG! Cannot access 2nd program argument, which is checked for validity at the start of the program.");
-        let commmand_with_replaced_placeholder = self.0.replace("%1", &format!("\"{image_path}\""));
+    fn execute(&self, ctx: &ActionContext) -> Result<(), CommandExecutionError>{
+        let (width, height) = ctx.dimensions;
+        let commmand_with_replaced_placeholder = self.0
+            .replace("%1", &format!("\"{}\"", ctx.image_path.display()))
+            .replace("%w", &width.to_string())
+            .replace("%h", &height.to_string())
+            .replace("%f", &ctx.frame.unwrap_or(0).to_string());
         let mut split_command = shell_words::split(&commmand_with_replaced_placeholder)?.into_iter();
-        dbg!(split_command.clone());
+        log::debug!("Running {:?}", split_command.as_slice());
         let executable = split_command.nth(0).ok_or(CommandExecutionError::InvalidArgs)?;
         std::process::Command::new(executable)
             .args(split_command)
@@ -103,7 +390,45 @@ fn clone_none<T>(_: &Option<T>) -> Option<T> {
     None
 }
 
-#[derive(Clone, Serialize, Deserialize, EnumIter, EnumCount, EnumMessage)]
+/// Folder that images are copied or moved into, chosen once in the settings window
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+pub struct Destination(Option<PathBuf>);
+impl Destination {
+    fn copy_image(&self, image_path: &Path) -> Result<(), FileActionError> {
+        let target = self.free_target_path(image_path)?;
+        fs::copy(image_path, target)?;
+        Ok(())
+    }
+    fn move_image(&self, image_path: &Path) -> Result<(), FileActionError> {
+        let target = self.free_target_path(image_path)?;
+        match fs::rename(image_path, &target) {
+            // rename doesn't work across file systems, so copy first and only remove the original once the copy succeeded
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                fs::copy(image_path, &target)?;
+                fs::remove_file(image_path)?;
+            }
+            result => result?,
+        }
+        Ok(())
+    }
+    /// Path inside the destination folder that doesn't exist yet, so nothing is ever overwritten.
+    /// Clashing names get a ` (n)` suffix like most file managers do.
+    fn free_target_path(&self, image_path: &Path) -> Result<PathBuf, FileActionError> {
+        let folder = self.0.as_ref().ok_or(FileActionError::NoDestination)?;
+        let file_name = image_path.file_name().ok_or(FileActionError::InvalidImagePath)?;
+        let mut target = folder.join(file_name);
+        let stem = image_path.file_stem().unwrap_or(file_name).to_string_lossy();
+        let extension = image_path.extension().map(|extension| format!(".{}", extension.to_string_lossy())).unwrap_or_default();
+        let mut n = 1;
+        while target.try_exists()? {
+            target = folder.join(format!("{stem} ({n}){extension}"));
+            n += 1;
+        }
+        Ok(target)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, EnumIter, EnumCount, EnumMessage, AsRefStr)]
 #[allow(non_camel_case_types)]
 enum KeysValue {
     #[strum(message="Open settings")]
@@ -114,16 +439,162 @@ enum KeysValue {
     next_frame,
     #[strum(message="Previous frame")]
     prev_frame,
+    #[strum(message="Quit")]
+    quit,
+    #[strum(message="Contact sheet")]
+    contact_sheet,
+    #[strum(message="Cycle background: transparent, checkerboard, solid")]
+    background,
+    #[strum(message="Increase HDR exposure")]
+    exposure_up,
+    #[strum(message="Decrease HDR exposure")]
+    exposure_down,
+    #[strum(message="Set loop start to current frame")]
+    loop_start,
+    #[strum(message="Set loop end to current frame")]
+    loop_end,
+    #[strum(message="Loop all frames again")]
+    clear_loop,
+    #[strum(message="Fit width")]
+    fit_width,
+    #[strum(message="Fit height")]
+    fit_height,
+    #[strum(message="Reload image")]
+    reload,
+    #[strum(message="Toggle sharpening")]
+    sharpen,
+    #[strum(message="Recent files")]
+    recents,
+    #[strum(message="Rotate clockwise")]
+    rotate_cw,
+    #[strum(message="Rotate counterclockwise")]
+    rotate_ccw,
+    #[strum(message="Rotate 90° clockwise")]
+    rotate_quarter,
+    #[strum(message="Flip horizontally")]
+    flip_horizontal,
+    #[strum(message="Flip vertically")]
+    flip_vertical,
+    #[strum(message="Save rotated copy")]
+    save_rotated,
+    #[strum(message="Zoom in")]
+    zoom_in,
+    #[strum(message="Zoom out")]
+    zoom_out,
+    #[strum(message="Cycle false color maps")]
+    colormap,
+    #[strum(message="Save view to sidecar")]
+    save_sidecar,
+    #[strum(message="Export loop range as GIF")]
+    export_loop_range,
+    #[strum(message="Copy screenshot of the view")]
+    copy_view,
+    #[strum(message="Copy image")]
+    copy_image,
+    #[strum(message="Paste image")]
+    paste_image,
+    #[strum(message="Show difference to previous frame")]
+    frame_diff,
+    #[strum(message="Rate 1")]
+    rate_1,
+    #[strum(message="Rate 2")]
+    rate_2,
+    #[strum(message="Rate 3")]
+    rate_3,
+    #[strum(message="Rate 4")]
+    rate_4,
+    #[strum(message="Rate 5")]
+    rate_5,
+    #[strum(message="Clear rating")]
+    clear_rating,
+    #[strum(message="Flag as pick")]
+    pick,
+    #[strum(message="Flag as reject")]
+    reject,
+    #[strum(message="Next picked or rated image")]
+    next_picked,
+    #[strum(message="Next unrated image")]
+    next_unmarked,
+    #[strum(message="Invert film negative")]
+    negative,
+    #[strum(message="Copy image as data URL")]
+    copy_data_url,
+    #[strum(message="Cycle through channels")]
+    channel,
+    #[strum(message="Swap with the previous image")]
+    swap_image,
+    #[strum(message="Fit to window")]
+    fit,
+    #[strum(message="Actual size (1:1)")]
+    actual_size,
+    #[strum(message="Next image")]
+    next_image,
+    #[strum(message="Previous image")]
+    prev_image,
+    #[strum(message="Image info")]
+    image_info,
+    #[strum(message="Toggle smooth filtering")]
+    smooth_filtering,
     #[strum(message="Actions")]
     actions(usize),
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Keys {
     pub settings: KeyWrapper,
     pub pause: KeyWrapper,
     pub next_frame: KeyWrapper,
     pub prev_frame: KeyWrapper,
+    pub quit: KeyWrapper,
+    pub contact_sheet: KeyWrapper,
+    pub background: KeyWrapper,
+    pub exposure_up: KeyWrapper,
+    pub exposure_down: KeyWrapper,
+    pub loop_start: KeyWrapper,
+    pub loop_end: KeyWrapper,
+    pub clear_loop: KeyWrapper,
+    pub fit_width: KeyWrapper,
+    pub fit_height: KeyWrapper,
+    pub reload: KeyWrapper,
+    pub sharpen: KeyWrapper,
+    pub recents: KeyWrapper,
+    pub rotate_cw: KeyWrapper,
+    pub rotate_ccw: KeyWrapper,
+    pub rotate_quarter: KeyWrapper,
+    pub flip_horizontal: KeyWrapper,
+    pub flip_vertical: KeyWrapper,
+    pub save_rotated: KeyWrapper,
+    pub zoom_in: KeyWrapper,
+    pub zoom_out: KeyWrapper,
+    pub colormap: KeyWrapper,
+    pub save_sidecar: KeyWrapper,
+    pub export_loop_range: KeyWrapper,
+    pub copy_view: KeyWrapper,
+    pub copy_image: KeyWrapper,
+    pub paste_image: KeyWrapper,
+    pub frame_diff: KeyWrapper,
+    pub rate_1: KeyWrapper,
+    pub rate_2: KeyWrapper,
+    pub rate_3: KeyWrapper,
+    pub rate_4: KeyWrapper,
+    pub rate_5: KeyWrapper,
+    pub clear_rating: KeyWrapper,
+    pub pick: KeyWrapper,
+    pub reject: KeyWrapper,
+    pub next_picked: KeyWrapper,
+    pub next_unmarked: KeyWrapper,
+    pub negative: KeyWrapper,
+    pub copy_data_url: KeyWrapper,
+    pub channel: KeyWrapper,
+    pub swap_image: KeyWrapper,
+    pub fit: KeyWrapper,
+    pub actual_size: KeyWrapper,
+    pub next_image: KeyWrapper,
+    pub prev_image: KeyWrapper,
+    pub image_info: KeyWrapper,
+    pub smooth_filtering: KeyWrapper,
+    #[serde(deserialize_with = "deserialize_array_lenient")]
     pub actions: [KeyWrapper; ACTION_AMOUNT],
 }
 impl Index<KeysValue> for Keys {
@@ -134,6 +605,54 @@ impl Index<KeysValue> for Keys {
             KeysValue::pause => &self.pause,
             KeysValue::next_frame => &self.next_frame,
             KeysValue::prev_frame => &self.prev_frame,
+            KeysValue::quit => &self.quit,
+            KeysValue::contact_sheet => &self.contact_sheet,
+            KeysValue::background => &self.background,
+            KeysValue::exposure_up => &self.exposure_up,
+            KeysValue::exposure_down => &self.exposure_down,
+            KeysValue::loop_start => &self.loop_start,
+            KeysValue::loop_end => &self.loop_end,
+            KeysValue::clear_loop => &self.clear_loop,
+            KeysValue::fit_width => &self.fit_width,
+            KeysValue::fit_height => &self.fit_height,
+            KeysValue::reload => &self.reload,
+            KeysValue::sharpen => &self.sharpen,
+            KeysValue::recents => &self.recents,
+            KeysValue::rotate_cw => &self.rotate_cw,
+            KeysValue::rotate_ccw => &self.rotate_ccw,
+            KeysValue::rotate_quarter => &self.rotate_quarter,
+            KeysValue::flip_horizontal => &self.flip_horizontal,
+            KeysValue::flip_vertical => &self.flip_vertical,
+            KeysValue::save_rotated => &self.save_rotated,
+            KeysValue::zoom_in => &self.zoom_in,
+            KeysValue::zoom_out => &self.zoom_out,
+            KeysValue::colormap => &self.colormap,
+            KeysValue::save_sidecar => &self.save_sidecar,
+            KeysValue::export_loop_range => &self.export_loop_range,
+            KeysValue::copy_view => &self.copy_view,
+            KeysValue::copy_image => &self.copy_image,
+            KeysValue::paste_image => &self.paste_image,
+            KeysValue::frame_diff => &self.frame_diff,
+            KeysValue::rate_1 => &self.rate_1,
+            KeysValue::rate_2 => &self.rate_2,
+            KeysValue::rate_3 => &self.rate_3,
+            KeysValue::rate_4 => &self.rate_4,
+            KeysValue::rate_5 => &self.rate_5,
+            KeysValue::clear_rating => &self.clear_rating,
+            KeysValue::pick => &self.pick,
+            KeysValue::reject => &self.reject,
+            KeysValue::next_picked => &self.next_picked,
+            KeysValue::next_unmarked => &self.next_unmarked,
+            KeysValue::negative => &self.negative,
+            KeysValue::copy_data_url => &self.copy_data_url,
+            KeysValue::channel => &self.channel,
+            KeysValue::swap_image => &self.swap_image,
+            KeysValue::fit => &self.fit,
+            KeysValue::actual_size => &self.actual_size,
+            KeysValue::next_image => &self.next_image,
+            KeysValue::prev_image => &self.prev_image,
+            KeysValue::image_info => &self.image_info,
+            KeysValue::smooth_filtering => &self.smooth_filtering,
             KeysValue::actions(i) => &self.actions[i],
         }
     }
@@ -145,12 +664,97 @@ impl IndexMut<KeysValue> for Keys {
             KeysValue::pause => &mut self.pause,
             KeysValue::next_frame => &mut self.next_frame,
             KeysValue::prev_frame => &mut self.prev_frame,
+            KeysValue::quit => &mut self.quit,
+            KeysValue::contact_sheet => &mut self.contact_sheet,
+            KeysValue::background => &mut self.background,
+            KeysValue::exposure_up => &mut self.exposure_up,
+            KeysValue::exposure_down => &mut self.exposure_down,
+            KeysValue::loop_start => &mut self.loop_start,
+            KeysValue::loop_end => &mut self.loop_end,
+            KeysValue::clear_loop => &mut self.clear_loop,
+            KeysValue::fit_width => &mut self.fit_width,
+            KeysValue::fit_height => &mut self.fit_height,
+            KeysValue::reload => &mut self.reload,
+            KeysValue::sharpen => &mut self.sharpen,
+            KeysValue::recents => &mut self.recents,
+            KeysValue::rotate_cw => &mut self.rotate_cw,
+            KeysValue::rotate_ccw => &mut self.rotate_ccw,
+            KeysValue::rotate_quarter => &mut self.rotate_quarter,
+            KeysValue::flip_horizontal => &mut self.flip_horizontal,
+            KeysValue::flip_vertical => &mut self.flip_vertical,
+            KeysValue::save_rotated => &mut self.save_rotated,
+            KeysValue::zoom_in => &mut self.zoom_in,
+            KeysValue::zoom_out => &mut self.zoom_out,
+            KeysValue::colormap => &mut self.colormap,
+            KeysValue::save_sidecar => &mut self.save_sidecar,
+            KeysValue::export_loop_range => &mut self.export_loop_range,
+            KeysValue::copy_view => &mut self.copy_view,
+            KeysValue::copy_image => &mut self.copy_image,
+            KeysValue::paste_image => &mut self.paste_image,
+            KeysValue::frame_diff => &mut self.frame_diff,
+            KeysValue::rate_1 => &mut self.rate_1,
+            KeysValue::rate_2 => &mut self.rate_2,
+            KeysValue::rate_3 => &mut self.rate_3,
+            KeysValue::rate_4 => &mut self.rate_4,
+            KeysValue::rate_5 => &mut self.rate_5,
+            KeysValue::clear_rating => &mut self.clear_rating,
+            KeysValue::pick => &mut self.pick,
+            KeysValue::reject => &mut self.reject,
+            KeysValue::next_picked => &mut self.next_picked,
+            KeysValue::next_unmarked => &mut self.next_unmarked,
+            KeysValue::negative => &mut self.negative,
+            KeysValue::copy_data_url => &mut self.copy_data_url,
+            KeysValue::channel => &mut self.channel,
+            KeysValue::swap_image => &mut self.swap_image,
+            KeysValue::fit => &mut self.fit,
+            KeysValue::actual_size => &mut self.actual_size,
+            KeysValue::next_image => &mut self.next_image,
+            KeysValue::prev_image => &mut self.prev_image,
+            KeysValue::image_info => &mut self.image_info,
+            KeysValue::smooth_filtering => &mut self.smooth_filtering,
             KeysValue::actions(i) => &mut self.actions[i],
         }
     }
 }
-#[derive(Clone, Serialize, Deserialize)]
+impl Keys {
+    /// Every binding by the name `--set-key` takes, actions are called action1, action2, ...
+    fn named_bindings() -> impl Iterator<Item = (String, KeysValue)> {
+        KeysValue::iter()
+            .filter(|keys_value| !matches!(keys_value, KeysValue::actions(_)))
+            .map(|keys_value| (keys_value.as_ref().to_owned(), keys_value))
+            .chain((0..ACTION_AMOUNT).map(|i| (format!("action{}", i + 1), KeysValue::actions(i))))
+    }
+    
+    /// One `binding = key` line per binding, for `--list-keys`
+    pub fn list(&self) -> String {
+        Self::named_bindings()
+            .map(|(name, keys_value)| format!("{name} = {}\n", self[keys_value].format(&ModifierNames::NAMES, false)))
+            .collect()
+    }
+    
+    /// Binds the key called `key_name` to `binding`. Takes egui key names ("K", "Space"), winit key codes ("KeyK") or "None" to unbind
+    pub fn set_binding(&mut self, binding: &str, key_name: &str) -> Result<(), SetKeyError> {
+        let (_, keys_value) = Self::named_bindings()
+            .find(|(name, _)| name == binding)
+            .ok_or_else(|| SetKeyError::UnknownBinding(binding.to_owned()))?;
+        let key_code = if key_name.eq_ignore_ascii_case("none") {
+            None
+        } else if key_name.len() > 1 && key_name.contains('+') {
+            return Err(SetKeyError::Modifiers(key_name.to_owned()));
+        } else if let Some(key) = Key::from_name(key_name) {
+            Some(egui_key_to_winit(key))
+        } else {
+            let key_code = KeyCode::deserialize(serde::de::value::StrDeserializer::<serde::de::value::Error>::new(key_name));
+            Some(key_code.map_err(|_| SetKeyError::UnknownKey(key_name.to_owned()))?)
+        };
+        self[keys_value] = KeyWrapper { key_code };
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct KeyWrapper {
+    #[serde(deserialize_with = "deserialize_key_code_lenient")]
     key_code: Option<KeyCode>
 }
 impl KeyWrapper {
@@ -185,18 +789,287 @@ impl Bind for KeyWrapper {
         }
     }
 }
+/// Accepts any amount of entries, extra ones are dropped and missing ones are filled with defaults,
+/// so a settings file from a build with a different `ACTION_AMOUNT` still loads
+fn deserialize_array_lenient<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    let mut entries = Vec::<T>::deserialize(deserializer)?;
+    if entries.len() > N {
+        log::warn!("luminix-settings.ron has {} entries where at most {N} are supported, ignoring the rest", entries.len());
+    }
+    entries.resize_with(N, T::default);
+    let Ok(entries) = entries.try_into() else { unreachable!("entries were resized to {N}") };
+    Ok(entries)
+}
+
+/// Unknown key names (e.g. typos in a hand edited file) unbind the key instead of failing the whole file
+fn deserialize_key_code_lenient<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<KeyCode>, D::Error> {
+    struct LenientKeyCode(Option<KeyCode>);
+    impl<'de> Deserialize<'de> for LenientKeyCode {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_enum("KeyCode", &[], LenientKeyCodeVisitor)
+        }
+    }
+
+    struct LenientKeyCodeVisitor;
+    impl<'de> Visitor<'de> for LenientKeyCodeVisitor {
+        type Value = LenientKeyCode;
+        fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "a key code")
+        }
+        fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+            let (VariantName(name), variant) = data.variant()?;
+            variant.unit_variant()?;
+            let key_code = KeyCode::deserialize(serde::de::value::StrDeserializer::<A::Error>::new(&name)).ok();
+            if key_code.is_none() {
+                log::warn!("Unknown key {name} in luminix-settings.ron, leaving it unbound");
+            }
+            Ok(LenientKeyCode(key_code))
+        }
+    }
+
+    struct VariantName(String);
+    impl<'de> Deserialize<'de> for VariantName {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct VariantNameVisitor;
+            impl Visitor<'_> for VariantNameVisitor {
+                type Value = VariantName;
+                fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                    write!(f, "a key name")
+                }
+                fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                    Ok(VariantName(v.to_owned()))
+                }
+            }
+            deserializer.deserialize_identifier(VariantNameVisitor)
+        }
+    }
+
+    Ok(Option::<LenientKeyCode>::deserialize(deserializer)?.and_then(|key_code| key_code.0))
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Keys {
+            settings: KeyWrapper::new(KeyCode::KeyK),
+            pause: KeyWrapper::new(KeyCode::Space),
+            next_frame: KeyWrapper::new(KeyCode::Period),
+            prev_frame: KeyWrapper::new(KeyCode::Comma),
+            quit: KeyWrapper::new(KeyCode::Escape),
+            contact_sheet: KeyWrapper::new(KeyCode::KeyG),
+            background: KeyWrapper::new(KeyCode::KeyB),
+            exposure_up: KeyWrapper::new(KeyCode::BracketRight),
+            exposure_down: KeyWrapper::new(KeyCode::BracketLeft),
+            loop_start: KeyWrapper::new(KeyCode::KeyI),
+            loop_end: KeyWrapper::new(KeyCode::KeyO),
+            clear_loop: KeyWrapper::new(KeyCode::KeyU),
+            fit_width: KeyWrapper::new(KeyCode::KeyW),
+            fit_height: KeyWrapper::new(KeyCode::KeyH),
+            reload: KeyWrapper::new(KeyCode::F5),
+            sharpen: KeyWrapper::new(KeyCode::KeyS),
+            recents: KeyWrapper::new(KeyCode::KeyR),
+            rotate_cw: KeyWrapper::new(KeyCode::KeyE),
+            rotate_ccw: KeyWrapper::new(KeyCode::KeyQ),
+            rotate_quarter: KeyWrapper::new(KeyCode::KeyT),
+            flip_horizontal: KeyWrapper::new(KeyCode::KeyM),
+            flip_vertical: KeyWrapper::new(KeyCode::KeyV),
+            save_rotated: KeyWrapper::new_empty(),
+            zoom_in: KeyWrapper::new(KeyCode::Equal),
+            zoom_out: KeyWrapper::new(KeyCode::Minus),
+            colormap: KeyWrapper::new(KeyCode::KeyC),
+            save_sidecar: KeyWrapper::new_empty(),
+            export_loop_range: KeyWrapper::new_empty(),
+            copy_view: KeyWrapper::new_empty(),
+            copy_image: KeyWrapper::new_empty(),
+            paste_image: KeyWrapper::new_empty(),
+            frame_diff: KeyWrapper::new(KeyCode::KeyD),
+            rate_1: KeyWrapper::new(KeyCode::Digit1),
+            rate_2: KeyWrapper::new(KeyCode::Digit2),
+            rate_3: KeyWrapper::new(KeyCode::Digit3),
+            rate_4: KeyWrapper::new(KeyCode::Digit4),
+            rate_5: KeyWrapper::new(KeyCode::Digit5),
+            clear_rating: KeyWrapper::new(KeyCode::Digit0),
+            pick: KeyWrapper::new(KeyCode::KeyP),
+            reject: KeyWrapper::new(KeyCode::KeyX),
+            next_picked: KeyWrapper::new_empty(),
+            next_unmarked: KeyWrapper::new_empty(),
+            negative: KeyWrapper::new(KeyCode::KeyN),
+            copy_data_url: KeyWrapper::new_empty(),
+            channel: KeyWrapper::new(KeyCode::KeyL),
+            swap_image: KeyWrapper::new(KeyCode::KeyA),
+            fit: KeyWrapper::new(KeyCode::KeyF),
+            actual_size: KeyWrapper::new(KeyCode::KeyZ),
+            next_image: KeyWrapper::new(KeyCode::ArrowRight),
+            prev_image: KeyWrapper::new(KeyCode::ArrowLeft),
+            image_info: KeyWrapper::new(KeyCode::Tab),
+            smooth_filtering: KeyWrapper::new_empty(),
+            actions: array::from_fn(|_| KeyWrapper::new_empty()),
+        }
+    }
+}
+impl ConfigurableSettings {
+    pub fn zoom_policy(&self) -> ZoomPolicy {
+        ZoomPolicy {
+            smooth_filtering: self.smooth_filtering,
+            integer_scaling: self.integer_scaling,
+            nearest_threshold: self.nearest_filter_threshold,
+            pixel_grid_threshold: self.show_pixel_grid.then_some(self.pixel_grid_threshold),
+        }
+    }
+    
+    /// Border and shadow around the image, `None` while they're turned off
+    pub fn image_frame(&self) -> Option<ImageFrame> {
+        self.image_frame.then_some(ImageFrame { width: self.frame_width, shadow: self.frame_shadow, color: self.frame_color })
+    }
+    
+    /// luminix-settings.ron next to the executable
+    pub fn path() -> PathBuf {
+        env::current_exe().unwrap().parent().unwrap().join("luminix-settings.ron")
+    }
+    
+    pub fn save(&self) {
+
+        let binding = Self::path();
+        let input_path = binding.as_path();
+        
+        let f = File::options()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(input_path)
+            .expect("Failed opening file for writing settings");
+        
+        ron::Options::default()
+            .to_io_writer_pretty(f, self, ron::ser::PrettyConfig::new().compact_arrays(true))
+            .expect("Failed to write to file");
+    }
+        
+    pub fn load() -> ConfigurableSettings {
+
+        let binding = Self::path();
+        let input_path = binding.as_path();
+        let contents = match fs::read_to_string(input_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::warn!("Failed to load luminix-settings.ron, falling back to default configuration values. Error message: {e}");
+                return ConfigurableSettings::default()
+            }
+        };
+        Self::parse(&contents)
+    }
+
+    /// Reads the contents of luminix-settings.ron, falling back to the defaults if it doesn't parse at all
+    fn parse(contents: &str) -> ConfigurableSettings {
+        let mut settings: ConfigurableSettings = ron::from_str(contents).unwrap_or_else(|e| {
+            log::warn!("Failed to load luminix-settings.ron, falling back to default configuration values. Error message: {e}");
+            ConfigurableSettings::default()
+        });
+        settings.validate();
+        settings
+    }
+
+    /// Clamps values a hand edited file could have put out of the range the settings window allows
+    fn validate(&mut self) {
+        let defaults = ConfigurableSettings::default();
+        if !self.pan_multiplier.is_finite() {
+            log::warn!("Invalid pan_multiplier {} in luminix-settings.ron, using {}", self.pan_multiplier, defaults.pan_multiplier);
+            self.pan_multiplier = defaults.pan_multiplier;
+        }
+        validate_range("nearest_filter_threshold", &mut self.nearest_filter_threshold, 1.0..=256.0, defaults.nearest_filter_threshold);
+        validate_range("pixel_grid_threshold", &mut self.pixel_grid_threshold, 2.0..=256.0, defaults.pixel_grid_threshold);
+        validate_range("sharpen_strength", &mut self.sharpen_strength, 0.0..=MAX_SHARPEN, defaults.sharpen_strength);
+        for (channel, default) in self.manual_film_base.iter_mut().zip(defaults.manual_film_base) {
+            validate_range("manual_film_base", channel, MIN_FILM_BASE..=1.0, default);
+        }
+        validate_range("frame_width", &mut self.frame_width, 0.0..=MAX_FRAME_WIDTH, defaults.frame_width);
+        validate_range("frame_shadow", &mut self.frame_shadow, 0.0..=MAX_FRAME_SHADOW, defaults.frame_shadow);
+        for (channel, default) in self.frame_color.iter_mut().zip(defaults.frame_color) {
+            validate_range("frame_color", channel, 0.0..=1.0, default);
+        }
+        for (channel, default) in self.background_color.iter_mut().zip(defaults.background_color) {
+            validate_range("background_color", channel, 0.0..=1.0, default);
+        }
+        validate_range("overlay_scale", &mut self.overlay_scale, MIN_OVERLAY_SCALE..=MAX_OVERLAY_SCALE, defaults.overlay_scale);
+        validate_range("min_zoom", &mut self.min_zoom, MIN_ZOOM..=1.0, defaults.min_zoom);
+        validate_range("max_zoom", &mut self.max_zoom, 1.0..=MAX_ZOOM, defaults.max_zoom);
+        if self.min_zoom >= self.max_zoom {
+            log::warn!("min_zoom {} in luminix-settings.ron isn't below max_zoom {}, using {} and {}", self.min_zoom, self.max_zoom, defaults.min_zoom, defaults.max_zoom);
+            self.min_zoom = defaults.min_zoom;
+            self.max_zoom = defaults.max_zoom;
+        }
+    }
+}
+fn validate_range(name: &str, value: &mut f32, range: RangeInclusive<f32>, default: f32) {
+    let validated = if value.is_nan() { default } else { value.clamp(*range.start(), *range.end()) };
+    if validated != *value {
+        log::warn!("{name} {value} in luminix-settings.ron is outside of {range:?}, using {validated}");
+        *value = validated;
+    }
+}
 impl Default for ConfigurableSettings {
     fn default() -> Self {
         ConfigurableSettings {
-            keys: Keys {
-                settings: KeyWrapper::new(KeyCode::KeyK),
-                pause: KeyWrapper::new(KeyCode::Space),
-                next_frame: KeyWrapper::new(KeyCode::Period),
-                prev_frame: KeyWrapper::new(KeyCode::Comma),
-                actions: array::from_fn(|_| KeyWrapper::new_empty()),
-            },
+            keys: Keys::default(),
             actions: array::from_fn(|_| Action::default()),
+            confirm_actions: [false; ACTION_AMOUNT],
             pan_multiplier: 1.0,
+            pan_inertia: false,
+            overscroll: Overscroll::default(),
+            reverse_zoom: false,
+            keyboard_zoom_follows_cursor: false,
+            min_zoom: 0.05,
+            max_zoom: 20.0,
+            quit_key_enabled: false,
+            smooth_filtering: false,
+            integer_scaling: false,
+            nearest_filter_threshold: 4.0,
+            show_pixel_grid: true,
+            pixel_grid_threshold: 16.0,
+            sharpen_strength: 0.5,
+            show_frame_timing: false,
+            endless_animations: false,
+            seek_resumes_playback: true,
+            frame_step: 1,
+            frame_jump: 10,
+            film_base: FilmBase::default(),
+            manual_film_base: [0.9, 0.6, 0.45],
+            image_frame: false,
+            frame_width: 1.0,
+            frame_shadow: 16.0,
+            frame_color: [1.0, 1.0, 1.0, 0.5],
+            swap_keeps_view: true,
+            remember_display_state: false,
+            display_state: DisplayState::default(),
+            data_url_format: DataUrlFormat::default(),
+            data_url_prefix: true,
+            auto_background: true,
+            background_color: [0.1, 0.1, 0.1],
+            tone_mapping: ToneMapping::default(),
+            alpha_mode: AlphaMode::default(),
+            last_image_removed: LastImageRemoved::default(),
+            recent_files_limit: 10,
+            title_template: String::from("luminix ({path})"),
+            visual_swapchain: true,
+            activate_on_open: true,
+            reload_keeps_view: true,
+            read_sidecars: true,
+            auto_orient: true,
+            prevent_sleep: false,
+            idle_dim_seconds: 0,
+            overlay_scale: 1.0,
+            single_instance: true,
+            hotfolders: Vec::new(),
+            slideshow_interval_seconds: 10,
+            hotfolder_refresh: HotfolderRefresh::default(),
+            raw_full_render: false,
+            honor_pixel_aspect: true,
+            window_screen_percent: 0,
+            remember_window_geometry: false,
+            last_window_size: None,
+            last_window_position: None,
         }
     }
 }
@@ -243,7 +1116,14 @@ impl SettingsWindow {
             queue: None,
             config: None,
             egui_rpass: None,
-            configurable_settings: Self::load_settings(),
+            retrying_frame: false,
+            command_editor_heights: [0.0; ACTION_AMOUNT],
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            file_association_status: None,
+            current_image_path: None,
+            current_image_dimensions: (0, 0),
+            image_info: None,
+            registered_actions: Vec::new(),
         };
         
         // Initialize WGPU
@@ -321,8 +1201,17 @@ impl SettingsWindow {
         self.egui_rpass = Some(egui_rpass);
     }
     
+    /// Waits for this window's GPU work to finish before it's dropped
+    pub fn shutdown(&self) {
+        if let Some(device) = &self.device {
+            crate::wgpu_renderer::drain_device(device);
+        }
+    }
+
     pub fn on_window_event(&mut self, event: &WindowEvent) -> egui_winit::EventResponse {
-        if let WindowEvent::Resized(size) = event {
+        if let WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } = event {
+            // on a dpi change the physical size changes too, and not every platform follows up with Resized
+            let size = self.window.inner_size();
             if let (Some(surface), Some(device), Some(config)) = (&mut self.surface, &self.device, &mut self.config) {
                 if size.width > 0 && size.height > 0 {
                     config.width = size.width;
@@ -337,9 +1226,8 @@ impl SettingsWindow {
         self.state.on_window_event(&self.window, event)
     }
     
-    pub fn on_redraw(&mut self) {
+    pub fn on_redraw(&mut self, settings: &mut ConfigurableSettings) {
         if self.window.inner_size().width == 0 || self.window.inner_size().height == 0 {
-            // println!("size is zero");
             return;
         }
         let ctx = self.ctx.clone();
@@ -356,13 +1244,22 @@ impl SettingsWindow {
                 ui.style_mut().visuals.faint_bg_color = hex_color!("#282828"); // change table background
                 ui.style_mut().override_text_valign = Some(Align::Center);
 
+                ui.group(|ui| {
+                    egui::CollapsingHeader::new(RichText::new("Image info").heading())
+                        .default_open(false)
+                        .show_unindented(ui, |ui| {
+                            ui.add(Separator::default().grow(6.0));
+                            self.image_info_table(ui);
+                        });
+                });
+                ui.add_space(5.0);
                 // Keybinds
                 ui.group(|ui| {
                     egui::CollapsingHeader::new(RichText::new("Keybinds").heading())
                         .default_open(true)
                         .show_unindented(ui, |ui| {
                             ui.add(Separator::default().grow(6.0));
-                            self.keybind_table(ui);
+                            Self::keybind_table(ui, settings);
                         });
                 });
                 ui.add_space(5.0);
@@ -372,7 +1269,16 @@ impl SettingsWindow {
                         .default_open(true)
                         .show_unindented(ui, |ui| {
                             ui.add(Separator::default().grow(6.0));
-                            self.action_table(ui);
+                            self.action_table(ui, settings);
+                        });
+                });
+                ui.add_space(5.0);
+                ui.group(|ui| {
+                    egui::CollapsingHeader::new(RichText::new("Input").heading())
+                        .default_open(true)
+                        .show_unindented(ui, |ui| {
+                            ui.add(Separator::default().grow(6.0));
+                            Self::input_settings(ui, settings);
                         });
                 });
                 ui.add_space(5.0);
@@ -381,114 +1287,480 @@ impl SettingsWindow {
                         .default_open(true)
                         .show_unindented(ui, |ui| {
                             ui.add(Separator::default().grow(6.0));
-                            self.misc_settings(ui);
+                            Self::misc_settings(ui, settings);
                         });
                 });
                 ui.add_space(10.0);
                 
                 ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
                     if ui.button("Apply").clicked() {
-                        self.save_settings();
+                        settings.save();
                     }
                 });
                 
-                // TODO: add linux & macos file association support
-                #[cfg(target_os = "windows")]
+                #[cfg(any(target_os = "windows", target_os = "linux"))]
                 ui.with_layout(Layout::bottom_up(Align::Center), |ui| {
-                    if ui.button("Register File association").clicked() {
-                        register_file_association().expect("Error registering file association");
+                    if let Some(status) = &self.file_association_status {
+                        ui.label(status);
                     }
+                    ui.horizontal(|ui| {
+                        if ui.button("Register File association").clicked() {
+                            self.file_association_status = Some(match register_file_association() {
+                                Ok(()) => "Registered luminix as the image viewer".to_owned(),
+                                Err(e) => format!("Couldn't register the file association: {e}"),
+                            });
+                        }
+                        if ui.button("Unregister").clicked() {
+                            self.file_association_status = Some(match unregister_file_association() {
+                                Ok(()) => "Removed luminix's file associations".to_owned(),
+                                Err(e) => format!("Couldn't remove the file association: {e}"),
+                            });
+                        }
+                    });
                 });
             });
         });
         
         // Handle platform output (clipboard, cursor, etc.)
         self.state.handle_platform_output(&self.window, output.platform_output);
+        // e.g. a command editor changed height and its table row has to follow
+        if output.viewport_output.get(&egui::ViewportId::ROOT).is_some_and(|viewport| viewport.repaint_delay == std::time::Duration::ZERO) {
+            self.window.request_redraw();
+        }
         
         // Tessellate the egui UI
         let tesselated = ctx.tessellate(output.shapes, output.pixels_per_point);
 
         // Check if we have all necessary WGPU components
+        let mut frame_failed = false;
         if let (Some(device), Some(queue), Some(surface), Some(egui_rpass)) = 
             (&self.device, &self.queue, &mut self.surface, &mut self.egui_rpass) {
             
             let frame = match surface.get_current_texture() {
                 Ok(frame) => frame,
                 Err(e) => {
-                    eprintln!("Failed to acquire next swap chain texture: {e:?}");
+                    log::error!("Failed to acquire next swap chain texture: {e:?}");
                     return;
                 }
             };
             
             let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
             
-            // Process the textures delta
-            for (id, image_delta) in &output.textures_delta.set {
-                egui_rpass.update_texture(device, queue, *id, image_delta);
-            }
-            
-
             let screen_descriptor = egui_wgpu::ScreenDescriptor {
                 size_in_pixels: [self.window.inner_size().width, self.window.inner_size().height],
                 pixels_per_point: output.pixels_per_point,
             };
             
-            // Create encoder, update buffers and render
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("egui_encoder"),
-            });
-            
-            // Update buffers
-            egui_rpass.update_buffers(device, queue, &mut encoder, &tesselated, &screen_descriptor);
-            
-            // Begin render pass with lifetime workaround
-            {
-                let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("egui_render"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.1,
-                                g: 0.1,
-                                b: 0.1,
-                                a: 1.0,
-                            }),
-                            store: wgpu::StoreOp::Store,
-                        },
-                        depth_slice: None,
-                    })],
-                    depth_stencil_attachment: None,
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
+            // Running out of memory or descriptors while uploading egui textures would otherwise panic,
+            // either inside egui_wgpu or in wgpu's default error handler. Capture both and skip the frame instead.
+            device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let recorded = panic::catch_unwind(AssertUnwindSafe(|| {
+                // Process the textures delta
+                for (id, image_delta) in &output.textures_delta.set {
+                    egui_rpass.update_texture(device, queue, *id, image_delta);
+                }
+                
+                // Create encoder, update buffers and render
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("egui_encoder"),
                 });
                 
-
-                egui_rpass.render(&mut render_pass.forget_lifetime(), &tesselated, &screen_descriptor);
+                // Update buffers
+                egui_rpass.update_buffers(device, queue, &mut encoder, &tesselated, &screen_descriptor);
                 
-            }
-            
-            queue.submit([encoder.finish()]);
-            frame.present();
+                // Begin render pass with lifetime workaround
+                {
+                    let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("egui_render"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.1,
+                                    g: 0.1,
+                                    b: 0.1,
+                                    a: 1.0,
+                                }),
+                                store: wgpu::StoreOp::Store,
+                            },
+                            depth_slice: None,
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    
+                    egui_rpass.render(&mut render_pass.forget_lifetime(), &tesselated, &screen_descriptor);
+                }
+                
+                encoder.finish()
+            }));
+            let validation_error = pollster::block_on(device.pop_error_scope());
+            let out_of_memory_error = pollster::block_on(device.pop_error_scope());
             
-            // Free deleted textures
-            for id in &output.textures_delta.free {
-                egui_rpass.free_texture(id);
+            match (recorded, validation_error.or(out_of_memory_error)) {
+                (Ok(commands), None) => {
+                    queue.submit([commands]);
+                    frame.present();
+                    self.retrying_frame = false;
+                    
+                    // Free deleted textures
+                    for id in &output.textures_delta.free {
+                        egui_rpass.free_texture(id);
+                    }
+                }
+                (Ok(_), Some(error)) => {
+                    log::warn!("Failed to render settings window, skipping frame. Error message: {error}");
+                    frame_failed = true;
+                }
+                (Err(_), _) => {
+                    // the panic message has already been printed by the panic hook
+                    log::warn!("Failed to render settings window, skipping frame");
+                    frame_failed = true;
+                }
+            }
+        }
+        
+        if frame_failed {
+            // egui only sends texture deltas once, so the failed uploads would never be retried.
+            // Start over with a fresh context and renderer, which re-uploads everything on the next frame.
+            self.reset_egui();
+            if self.retrying_frame {
+                // a fresh start didn't help either, waits for the next input or resize instead of redrawing in a loop
+                log::error!("Failed to render settings window again after a reset, giving up until it's redrawn");
+                self.retrying_frame = false;
+            } else {
+                self.retrying_frame = true;
+                self.window.request_redraw();
             }
         }
     }
     
-    fn misc_settings(&mut self, ui:&mut Ui) {
+    fn reset_egui(&mut self) {
+        self.ctx = Context::default();
+        self.state = State::new(
+            self.ctx.clone(),
+            self.ctx.viewport_id(),
+            &self.window,
+            None,
+            None,
+            None
+        );
+        if let (Some(device), Some(config)) = (&self.device, &self.config) {
+            self.egui_rpass = Some(egui_wgpu::Renderer::new(
+                device,
+                config.format,
+                egui_wgpu::RendererOptions {
+                    depth_stencil_format: None,
+                    msaa_samples: 1,
+                    dithering: true,
+                    predictable_texture_filtering: false,
+                },
+            ));
+        }
+    }
+    
+    /// EXIF details of the shown image, only read while the section is open
+    fn image_info_table(&mut self, ui: &mut Ui) {
+        let Some(image_path) = &self.current_image_path else {
+            ui.label("No image");
+            return;
+        };
+        if self.image_info.as_ref().is_none_or(|(path, _)| path != image_path) {
+            self.image_info = Some((image_path.clone(), exif_summary(image_path)));
+        }
+        let Some((_, fields)) = self.image_info.as_ref().filter(|(_, fields)| !fields.is_empty()) else {
+            ui.label("No metadata");
+            return;
+        };
+        egui::Grid::new("image info").num_columns(2).striped(true).show(ui, |ui| {
+            for (label, value) in fields {
+                ui.label(*label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+    }
+
+    fn input_settings(ui: &mut Ui, settings: &mut ConfigurableSettings) {
+        ui.label("Pan speed").on_hover_text("Negative values move the image against the mouse");
         ui.style_mut().spacing.slider_width = ui.available_size_before_wrap().x-50.0;
-        egui::widgets::Slider::new(&mut self.configurable_settings.pan_multiplier, -5.0..=5.0)
+        egui::widgets::Slider::new(&mut settings.pan_multiplier, -5.0..=5.0)
             .step_by(0.02)
             .drag_value_speed(0.001)
             .clamping(SliderClamping::Never)
             .ui(ui);
+        ui.checkbox(&mut settings.pan_inertia, "Pan inertia")
+            .on_hover_text("The image keeps gliding after releasing the mouse and slows down on its own");
+        ui.horizontal(|ui| {
+            ui.label("At the pan edge");
+            egui::ComboBox::from_id_salt("overscroll")
+                .selected_text(settings.overscroll.to_string())
+                .show_ui(ui, |ui| {
+                    for overscroll in Overscroll::iter() {
+                        ui.selectable_value(&mut settings.overscroll, overscroll, overscroll.to_string());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Frame keys step by");
+            egui::DragValue::new(&mut settings.frame_step)
+                .range(1..=1000)
+                .ui(ui);
+            ui.label("and with Shift by");
+            egui::DragValue::new(&mut settings.frame_jump)
+                .range(1..=1000)
+                .ui(ui);
+        }).response.on_hover_text("Frames of a paused animation or pages of a document, wrapping around at the ends");
+        ui.checkbox(&mut settings.endless_animations, "Loop animations forever")
+            .on_hover_text("Off stops animations on their last frame once they've played as often as the file asks");
+        ui.checkbox(&mut settings.seek_resumes_playback, "Resume playback after seeking")
+            .on_hover_text("Dragging the seek bar at the bottom of an animation pauses it, this plays it again on release");
+        ui.checkbox(&mut settings.reverse_zoom, "Reverse zoom direction")
+            .on_hover_text("Scrolling down zooms in instead of out");
+        ui.checkbox(&mut settings.keyboard_zoom_follows_cursor, "Zoom keys zoom towards the cursor")
+            .on_hover_text("Keeps the point the cursor was last over in place, the image's center is used until the cursor enters the window");
+        ui.horizontal(|ui| {
+            ui.label("Zoom between");
+            egui::DragValue::new(&mut settings.min_zoom)
+                .range(MIN_ZOOM..=1.0)
+                .speed(0.01)
+                .suffix("x")
+                .ui(ui);
+            ui.label("and");
+            egui::DragValue::new(&mut settings.max_zoom)
+                .range(1.0..=MAX_ZOOM)
+                .speed(0.1)
+                .suffix("x")
+                .ui(ui);
+        }).response.on_hover_text("How far the wheel and zoom keys go, as multiples of the size that fits the window");
+    }
+
+    /// Hotfolders and timing of `--slideshow`
+    fn slideshow_settings(ui: &mut Ui, settings: &mut ConfigurableSettings) {
+        ui.label("Slideshow hotfolders")
+            .on_hover_text("luminix --slideshow cycles through the images in these folders, picking up new ones and dropping deleted ones");
+        settings.hotfolders.retain(|folder| {
+            ui.horizontal(|ui| {
+                let keep = !ui.small_button("x").on_hover_text("Remove").clicked();
+                ui.label(folder.display().to_string());
+                keep
+            }).inner
+        });
+        if ui.button("Add folder").clicked() {
+            if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                if !settings.hotfolders.contains(&folder) {
+                    settings.hotfolders.push(folder);
+                }
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("Show each image for");
+            egui::DragValue::new(&mut settings.slideshow_interval_seconds)
+                .range(1..=86400)
+                .suffix(" s")
+                .ui(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Show new images");
+            egui::ComboBox::from_id_salt("hotfolder refresh")
+                .selected_text(settings.hotfolder_refresh.to_string())
+                .show_ui(ui, |ui| {
+                    for refresh in HotfolderRefresh::iter() {
+                        ui.selectable_value(&mut settings.hotfolder_refresh, refresh, refresh.to_string());
+                    }
+                });
+        });
+    }
+
+    fn misc_settings(ui: &mut Ui, settings: &mut ConfigurableSettings) {
+        ui.checkbox(&mut settings.quit_key_enabled, "Quit key closes luminix")
+            .on_hover_text("Only applies while the image window is focused");
+        
+        // zoom dependent filtering
+        ui.checkbox(&mut settings.smooth_filtering, "Smooth filtering");
+        ui.checkbox(&mut settings.integer_scaling, "Integer scaling")
+            .on_hover_text("Snaps zoom down to 1x, 2x, 3x... of the image's pixels and leaves the rest of the window empty. Always filters with nearest");
+        ui.horizontal(|ui| {
+            ui.label("Switch to nearest filtering at");
+            egui::DragValue::new(&mut settings.nearest_filter_threshold)
+                .range(1.0..=256.0)
+                .speed(0.1)
+                .suffix("x")
+                .ui(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Sharpening strength");
+            egui::DragValue::new(&mut settings.sharpen_strength)
+                .range(0.0..=MAX_SHARPEN)
+                .speed(0.01)
+                .ui(ui);
+        }).response.on_hover_text("Used while sharpening is toggled on with its key");
+        ui.horizontal(|ui| {
+            ui.label("Film negatives");
+            egui::ComboBox::from_id_salt("film base")
+                .selected_text(settings.film_base.to_string())
+                .show_ui(ui, |ui| {
+                    for film_base in FilmBase::iter() {
+                        ui.selectable_value(&mut settings.film_base, film_base, film_base.to_string());
+                    }
+                });
+        }).response.on_hover_text("Film base corrected out while the negative key inverts the image");
+        if settings.film_base == FilmBase::Manual {
+            for (channel, label) in settings.manual_film_base.iter_mut().zip(["Film base red", "Film base green", "Film base blue"]) {
+                ui.add(egui::Slider::new(channel, MIN_FILM_BASE..=1.0).text(label));
+            }
+            ui.color_edit_button_rgb(&mut settings.manual_film_base)
+                .on_hover_text("The color of the scan's unexposed edge");
+        }
+        ui.horizontal(|ui| {
+            ui.label("Copy data URLs as");
+            egui::ComboBox::from_id_salt("data url format")
+                .selected_text(settings.data_url_format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in DataUrlFormat::iter() {
+                        ui.selectable_value(&mut settings.data_url_format, format, format.to_string());
+                    }
+                });
+            ui.checkbox(&mut settings.data_url_prefix, "with the data: prefix");
+        }).response.on_hover_text("What the data URL key copies, without the prefix it's only the base64 text");
+        ui.horizontal(|ui| {
+            ui.label("Overlay size");
+            egui::DragValue::new(&mut settings.overlay_scale)
+                .range(MIN_OVERLAY_SCALE..=MAX_OVERLAY_SCALE)
+                .speed(0.01)
+                .suffix("x")
+                .ui(ui);
+        }).response.on_hover_text("Scales text and panels over the image, like the contact sheet and toasts, on top of the display's scaling");
+        ui.checkbox(&mut settings.show_pixel_grid, "Pixel grid");
+        ui.horizontal(|ui| {
+            ui.label("Show pixel grid at");
+            egui::DragValue::new(&mut settings.pixel_grid_threshold)
+                .range(2.0..=256.0)
+                .speed(0.1)
+                .suffix("x")
+                .ui(ui);
+        });
+        ui.checkbox(&mut settings.show_frame_timing, "Frame timing")
+            .on_hover_text("Shows the frame counter of animations during playback too, with when each frame starts and how long it stays");
+        ui.checkbox(&mut settings.image_frame, "Frame the image")
+            .on_hover_text("Draws a border and a soft shadow around the image, so its edges stand out when the desktop shows around it");
+        if settings.image_frame {
+            ui.horizontal(|ui| {
+                ui.label("Border");
+                egui::DragValue::new(&mut settings.frame_width)
+                    .range(0.0..=MAX_FRAME_WIDTH)
+                    .speed(0.1)
+                    .suffix(" px")
+                    .ui(ui);
+                ui.color_edit_button_rgba_unmultiplied(&mut settings.frame_color);
+                ui.label("Shadow");
+                egui::DragValue::new(&mut settings.frame_shadow)
+                    .range(0.0..=MAX_FRAME_SHADOW)
+                    .speed(0.1)
+                    .suffix(" px")
+                    .ui(ui);
+            });
+        }
+        ui.checkbox(&mut settings.remember_display_state, "Remember display toggles")
+            .on_hover_text("Reopens with the fit mode, background, colormap, sharpening and negative inversion as they were when luminix was closed");
+        ui.checkbox(&mut settings.auto_background, "Checkerboard only behind transparent images")
+            .on_hover_text("Chosen when an image is opened, the background key still cycles it");
+        ui.horizontal(|ui| {
+            ui.label("Solid background");
+            ui.color_edit_button_rgb(&mut settings.background_color);
+        });
+        ui.checkbox(&mut settings.reload_keeps_view, "Keep zoom and pan when reloading");
+        ui.checkbox(&mut settings.swap_keeps_view, "Keep zoom and pan when swapping images")
+            .on_hover_text("The swap key switches between the shown and the previously shown image");
+        ui.checkbox(&mut settings.single_instance, "Open images in the running window")
+            .on_hover_text("Launching luminix again hands the image to the window that's already open. Off opens a window per image");
+        Self::slideshow_settings(ui, settings);
+        ui.checkbox(&mut settings.raw_full_render, "Demosaic RAW files")
+            .on_hover_text("Slower but accurate, instead of the JPEG preview the camera embedded. Applies after restarting luminix");
+        ui.checkbox(&mut settings.honor_pixel_aspect, "Correct non-square pixels")
+            .on_hover_text("Stretches anamorphic images by the pixel aspect ratio stored in the file. Off shows every pixel square");
+        ui.checkbox(&mut settings.read_sidecars, "Read view sidecars")
+            .on_hover_text("Opens images with the rotation, flip and crop stored in <image>.luminix.json next to them");
+        ui.checkbox(&mut settings.auto_orient, "Turn photos upright")
+            .on_hover_text("Rotates and flips by the orientation the camera stored in the EXIF data, unless a sidecar sets the view. Off shows the pixels as they're stored");
+        ui.horizontal(|ui| {
+            ui.label("Remember");
+            egui::DragValue::new(&mut settings.recent_files_limit)
+                .range(0..=100)
+                .ui(ui);
+            ui.label("recent files");
+        });
+        #[cfg(target_os = "windows")]
+        ui.checkbox(&mut settings.visual_swapchain, "Transparent window (restart to apply)")
+            .on_hover_text("Draws through a DirectComposition visual so the desktop shows behind transparent images. \
+                Some GPUs and drivers flicker with it, turning it off gives an opaque window that works everywhere. \
+                --no-visual-swapchain turns it off for a single run");
+        #[cfg(target_os = "windows")]
+        ui.checkbox(&mut settings.prevent_sleep, "Keep the display awake")
+            .on_hover_text("Stops the system from sleeping or starting the screensaver while luminix is focused");
+        ui.checkbox(&mut settings.activate_on_open, "Focus the window when opening images")
+            .on_hover_text("Off leaves whatever is focused alone, for opening images from scripts or indexers in the background. \
+                --no-activate turns it off for a single run");
+        ui.horizontal(|ui| {
+            ui.label("Dim the image after");
+            egui::DragValue::new(&mut settings.idle_dim_seconds)
+                .range(0..=3600)
+                .suffix(" s")
+                .ui(ui);
+            ui.label("without input");
+        }).response.on_hover_text("0 never dims");
+        ui.horizontal(|ui| {
+            ui.label("Open the window at");
+            egui::DragValue::new(&mut settings.window_screen_percent)
+                .range(0..=100)
+                .suffix(" %")
+                .ui(ui);
+            ui.label("of the screen");
+        }).response.on_hover_text("Centered and shaped like the image. 0 opens it at the image's size");
+        ui.checkbox(&mut settings.remember_window_geometry, "Remember window size and position")
+            .on_hover_text("Reopens the window as it was when luminix was closed instead of sizing it to the image");
+        ui.horizontal(|ui| {
+            ui.label("Window title");
+            ui.text_edit_singleline(&mut settings.title_template)
+                .on_hover_text("{name} file name, {path} full path, {dims} image size, {zoom} magnification, {index}/{count} position in the folder, {frame}/{frames} animation frame");
+        });
+        ui.horizontal(|ui| {
+            ui.label("HDR tone mapping");
+            egui::ComboBox::from_id_salt("tone mapping")
+                .selected_text(settings.tone_mapping.to_string())
+                .show_ui(ui, |ui| {
+                    for tone_mapping in ToneMapping::iter() {
+                        ui.selectable_value(&mut settings.tone_mapping, tone_mapping, tone_mapping.to_string());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Image alpha");
+            egui::ComboBox::from_id_salt("alpha mode")
+                .selected_text(settings.alpha_mode.to_string())
+                .show_ui(ui, |ui| {
+                    for alpha_mode in AlphaMode::iter() {
+                        ui.selectable_value(&mut settings.alpha_mode, alpha_mode, alpha_mode.to_string());
+                    }
+                });
+        }).response.on_hover_text("Premultiplied is only right for files whose colors are already multiplied by alpha. \
+            Applies to images opened afterwards, the reload key applies it to the shown one");
+        ui.horizontal(|ui| {
+            ui.label("When the last image in a folder is moved away");
+            egui::ComboBox::from_id_salt("last image removed")
+                .selected_text(settings.last_image_removed.to_string())
+                .show_ui(ui, |ui| {
+                    for last_image_removed in LastImageRemoved::iter() {
+                        ui.selectable_value(&mut settings.last_image_removed, last_image_removed, last_image_removed.to_string());
+                    }
+                });
+        });
     }
     
-    fn action_table(&mut self, ui: &mut Ui) {
+    fn action_table(&mut self, ui: &mut Ui, settings: &mut ConfigurableSettings) {
         TableBuilder::new(ui)
             .column(Column::remainder())
             .column(Column::remainder())
@@ -497,14 +1769,16 @@ impl SettingsWindow {
             .cell_layout(Layout::default().with_cross_align(Align::LEFT).with_main_justify(true))
             .body(|body| {
                 // Action 1
-                let row_heights: Vec<f32> = self.configurable_settings.actions.iter().map(|action| {
+                let row_heights: Vec<f32> = settings.actions.iter().enumerate().map(|(row_index, action)| {
                    match action {
                        Action::Command(command) => {
-                           40.0 + match command.1 {
+                           // the editor grows with wrapped and multiline commands, its height is measured while drawing
+                           20.0 + self.command_editor_heights[row_index].max(20.0) + match command.1 {
                                None => 0.0,
                                Some(_) => 20.0,
                            }
                        }
+                       Action::CopyTo(_) | Action::MoveTo(_) | Action::Registered(_) => {40.0}
                        Action::None => {20.0}
                    }
                 }).collect();
@@ -512,8 +1786,14 @@ impl SettingsWindow {
                     let row_index = row.index();
                     // Label column
                     row.col(|ui| {
-                        let label = format!("Action {}", row_index + 1);
-                       ui.label(label);
+                        ui.with_layout(Layout::top_down(Align::LEFT).with_main_align(Align::Center).with_main_justify(true), |ui| {
+                            let label = format!("Action {}", row_index + 1);
+                            ui.label(label);
+                            if settings.actions[row_index] != Action::None {
+                                ui.checkbox(&mut settings.confirm_actions[row_index], "Ask first")
+                                    .on_hover_text("Show a confirmation prompt before running this action");
+                            }
+                        });
                     });
                     
                     // Setting Column
@@ -523,16 +1803,17 @@ impl SettingsWindow {
                             ui.with_layout(Layout::top_down_justified(Align::LEFT),|ui| {
                                 ui.set_height(ui.style().spacing.interact_size.y);
                                 egui::ComboBox::from_id_salt(format!("action settings index {row_index}"))
-                                    .selected_text(self.configurable_settings.actions[row_index].to_string())
+                                    .selected_text(settings.actions[row_index].to_string())
                                     .show_ui(ui, |ui| {
-                                        for action in Action::iter() {
-                                            ui.selectable_value(&mut self.configurable_settings.actions[row_index], action.clone(), action.to_string());
+                                        // registered actions are only offered when there are any to pick from
+                                        for action in Action::iter().filter(|action| !matches!(action, Action::Registered(_)) || !self.registered_actions.is_empty()) {
+                                            ui.selectable_value(&mut settings.actions[row_index], action.clone(), action.to_string());
                                         }
                                     });
                             });
                             
                             // if Command
-                            let action = &mut self.configurable_settings.actions[row_index];
+                            let action = &mut settings.actions[row_index];
                             if let Action::Command(command) = action {
                                 // help tooltip
                                 let default_style = Style::default();
@@ -542,16 +1823,42 @@ impl SettingsWindow {
                                 RichText::new("%1")
                                     .code()
                                     .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
-                                RichText::new(" as placeholder for image path in command.")
+                                RichText::new(" as placeholder for image path in command, ")
                                     .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
-                                // command selection menu
-                                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
-                                    // actual textedit
-                                    egui::TextEdit::singleline(command).code_editor().show(ui).response.on_hover_text(layout_job);
+                                RichText::new("%w %h")
+                                    .code()
+                                    .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
+                                RichText::new(" for its size and ")
+                                    .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
+                                RichText::new("%f")
+                                    .code()
+                                    .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
+                                RichText::new(" for the shown frame.")
+                                    .append_to(&mut layout_job, &default_style, egui::FontSelection::default(), Align::LEFT);
+                                // command selection menu, the button is laid out first so the editor gets the remaining width
+                                ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
                                     let test_button = ui.button("Test command");
+                                    // actual textedit, commands can be wrapped or split over lines since newlines separate arguments like spaces
+                                    let editor = egui::TextEdit::multiline(command)
+                                        .code_editor()
+                                        .desired_rows(1)
+                                        .desired_width(ui.available_width())
+                                        .show(ui)
+                                        .response
+                                        .on_hover_text(layout_job);
+                                    let height = editor.rect.height();
+                                    if (height - self.command_editor_heights[row_index]).abs() > 0.5 {
+                                        // row heights are fixed for this frame, draw again with the new one
+                                        self.command_editor_heights[row_index] = height;
+                                        ui.ctx().request_repaint();
+                                    }
                                     if test_button.clicked() {
-                                        if let Err(error) = command.execute() {
-                                            dbg!(&error);
+                                        let ctx = ActionContext {
+                                            image_path: self.current_image_path.as_deref().unwrap_or(Path::new("")),
+                                            dimensions: self.current_image_dimensions,
+                                            frame: None,
+                                        };
+                                        if let Err(error) = command.execute(&ctx) {
                                             command.1 = Some(error);
                                         } else {
                                             command.1 = None;
@@ -562,13 +1869,40 @@ impl SettingsWindow {
                                     ui.label(error_message.to_string());
                                 }
                             }
+                            
+                            // if CopyTo or MoveTo
+                            if let Action::CopyTo(destination) | Action::MoveTo(destination) = action {
+                                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                                    if ui.button("Choose folder").clicked() {
+                                        if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                                            destination.0 = Some(folder);
+                                        }
+                                    }
+                                    match &destination.0 {
+                                        Some(folder) => ui.label(folder.display().to_string()),
+                                        None => ui.label("No folder chosen"),
+                                    };
+                                });
+                            }
+                            
+                            // if Registered
+                            if let Action::Registered(label) = action {
+                                let selected_text = if label.is_empty() { "Choose an action" } else { label.as_str() };
+                                egui::ComboBox::from_id_salt(format!("registered action index {row_index}"))
+                                    .selected_text(selected_text.to_owned())
+                                    .show_ui(ui, |ui| {
+                                        for registered in &self.registered_actions {
+                                            ui.selectable_value(label, registered.clone(), registered);
+                                        }
+                                    });
+                            }
                         });
                     });
                 });
             });
     }
 
-    fn keybind_table(&mut self, ui: &mut Ui) {
+    fn keybind_table(ui: &mut Ui, settings: &mut ConfigurableSettings) {
         TableBuilder::new(ui)
             .column(Column::remainder())
             .column(Column::remainder())
@@ -576,16 +1910,13 @@ impl SettingsWindow {
             .id_salt("keys")
             .cell_layout(Layout::default().with_cross_align(Align::LEFT).with_main_justify(true))
             .body(|body| {
-                let row_amount = (KeysValue::COUNT - 1) + (self.configurable_settings.keys.actions.len());
-                body.rows(20.0, row_amount, |mut row| {
-                    let row_index = row.index();
-                    let keys_index = match row_index {
-                        0 => KeysValue::settings,
-                        1 => KeysValue::pause,
-                        2 => KeysValue::next_frame,
-                        3 => KeysValue::prev_frame,
-                        _ => KeysValue::actions(row_index-4)
-                    };
+                // one row per key, with the actions variant expanded into a row per action
+                let rows: Vec<KeysValue> = KeysValue::iter()
+                    .filter(|keys_value| !matches!(keys_value, KeysValue::actions(_)))
+                    .chain((0..settings.keys.actions.len()).map(KeysValue::actions))
+                    .collect();
+                body.rows(20.0, rows.len(), |mut row| {
+                    let keys_index = rows[row.index()].clone();
                     let row_label = if let KeysValue::actions(action_index) = keys_index {
                         format!("Action {}", action_index+1)
                     } else {
@@ -597,53 +1928,13 @@ impl SettingsWindow {
                     });
                     // keybind row
                     row.col(|ui| {
-                        ui.add(Keybind::new(&mut self.configurable_settings.keys[keys_index], row_label).with_reset(KeyWrapper::new_empty()).with_reset_key(Some(Key::Escape)));
+                        ui.add(Keybind::new(&mut settings.keys[keys_index], row_label).with_reset(KeyWrapper::new_empty()).with_reset_key(Some(Key::Escape)));
                     });
                 });
             });
     }
 
-    pub fn get_settings(&self) -> &ConfigurableSettings {
-        &self.configurable_settings
-    }
-    
-    fn save_settings(&self) {
-
-        let binding = env::current_exe().unwrap().parent().unwrap().join("luminix-settings.ron");
-        let input_path = binding.as_path();
-        
-        let f = File::options()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(input_path)
-            .expect("Failed opening file for writing settings");
-        
-        ron::Options::default()
-            .to_io_writer_pretty(f, &self.configurable_settings, ron::ser::PrettyConfig::new().compact_arrays(true))
-            .expect("Failed to write to file");
-    }
-        
-    fn load_settings() -> ConfigurableSettings {
-
-        let binding = env::current_exe().unwrap().parent().unwrap().join("luminix-settings.ron");
-        let input_path = binding.as_path();
-        let f = File::open(input_path);
-        
-        if f.is_err() {
-            eprintln!("Failed to load luminix-settings.ron, falling back to default configuration values. Error message: {}", f.unwrap_err());
-            return ConfigurableSettings::default()
-        }
-        
-        // return
-        ron::de::from_reader(f.unwrap()).unwrap_or_else(|e| {
-            eprintln!("Failed to load luminix-settings.ron, falling back to default configuration values. Error message: {e}");
-            ConfigurableSettings::default()
-        })
-    }
-
     pub fn show(&self) {
-        println!("opening settings window");
         self.window.set_visible(true);
         self.window.focus_window();
     }
@@ -874,4 +2165,53 @@ fn egui_key_to_winit(key: Key) -> KeyCode {
         Key::Num9 => KeyCode::Numpad9,
         Key::BrowserBack => KeyCode::BrowserBack
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_key_name_unbinds_only_that_key() {
+        let settings = ConfigurableSettings::parse("(keys: (settings: (key_code: Some(NoSuchKey)), pause: (key_code: Some(KeyP))))");
+        assert_eq!(settings.keys.settings.get_keycode(), None);
+        assert_eq!(settings.keys.pause.get_keycode(), Some(KeyCode::KeyP));
+        // keys the file leaves out keep their defaults
+        assert_eq!(settings.keys.quit.get_keycode(), Some(KeyCode::Escape));
+    }
+
+    #[test]
+    fn short_array_is_filled_with_defaults() {
+        let settings = ConfigurableSettings::parse("(confirm_actions: [true])");
+        let mut expected = [false; ACTION_AMOUNT];
+        expected[0] = true;
+        assert_eq!(settings.confirm_actions, expected);
+    }
+
+    #[test]
+    fn long_array_is_cut_short() {
+        let entries = ["true"; ACTION_AMOUNT + 3].join(", ");
+        let settings = ConfigurableSettings::parse(&format!("(confirm_actions: [{entries}])"));
+        assert_eq!(settings.confirm_actions, [true; ACTION_AMOUNT]);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        let settings = ConfigurableSettings::parse("(sharpen_strength: 1000.0, overlay_scale: 0.0, min_zoom: 0.0001, max_zoom: 1000.0)");
+        assert_eq!(settings.sharpen_strength, MAX_SHARPEN);
+        assert_eq!(settings.overlay_scale, MIN_OVERLAY_SCALE);
+        assert_eq!((settings.min_zoom, settings.max_zoom), (MIN_ZOOM, MAX_ZOOM));
+    }
+
+    #[test]
+    fn zoom_limits_that_cross_fall_back_to_defaults() {
+        let settings = ConfigurableSettings::parse("(min_zoom: 1.0, max_zoom: 1.0)");
+        let defaults = ConfigurableSettings::default();
+        assert_eq!((settings.min_zoom, settings.max_zoom), (defaults.min_zoom, defaults.max_zoom));
+    }
+
+    #[test]
+    fn garbage_falls_back_to_defaults() {
+        let settings = ConfigurableSettings::parse("\u{0}not a settings file at all }{");
+        assert_eq!(ron::to_string(&settings).unwrap(), ron::to_string(&ConfigurableSettings::default()).unwrap());
+    }
+}
@@ -6,6 +6,54 @@ struct Uniforms {
     zoom: f32,
     pan_x: f32,
     pan_y: f32,
+    // screen pixels per image pixel at which the pixel grid appears, 0.0 disables it
+    grid_threshold: f32,
+    // 1.0 draws a checkerboard behind transparent pixels
+    checkerboard: f32,
+    // 0.0 leaves colors as they are, 1.0 applies Reinhard and 2.0 ACES tone mapping
+    tone_mapping: f32,
+    // in stops, applied before tone mapping
+    exposure: f32,
+    // 0.0 fits the whole image inside the window, 1.0 matches its width and 2.0 its height
+    fit_mode: f32,
+    // unsharp mask strength, 0.0 disables it
+    sharpen: f32,
+    // clockwise, in radians
+    rotation: f32,
+    // 0.0 shows the image's own colors, 1.0 viridis, 2.0 magma and 3.0 heat
+    colormap: f32,
+    // 1.0 mirrors the image along that axis
+    flip_x: f32,
+    flip_y: f32,
+    // shown part of the texture, in texture coordinates
+    crop_x: f32,
+    crop_y: f32,
+    crop_width: f32,
+    crop_height: f32,
+    // fraction the final color is darkened by, for dimming after inactivity
+    dim: f32,
+    // 1.0 turns a scanned film negative into a positive
+    negative: f32,
+    // linear color of the unexposed film, the orange mask of color negatives
+    film_base_r: f32,
+    film_base_g: f32,
+    film_base_b: f32,
+    inset_left: f32,
+    inset_top: f32,
+    inset_right: f32,
+    inset_bottom: f32,
+    // in physical pixels, to size the frame in screen space
+    window_height: f32,
+    // border drawn around the image and the soft shadow past it, in screen pixels, 0.0 leaves them out
+    frame_width: f32,
+    frame_shadow: f32,
+    // linear border color and its opacity
+    frame_r: f32,
+    frame_g: f32,
+    frame_b: f32,
+    frame_a: f32,
+    // 0.0 shows all channels, 1.0 to 4.0 only red, green, blue or alpha as grayscale
+    channel: f32,
 }
 
 @group(1) @binding(0)
@@ -19,6 +67,9 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    // screen pixels from the image's center along its own axes, and half its size in screen pixels
+    @location(1) frame_position: vec2<f32>,
+    @location(2) image_half_size: vec2<f32>,
 }
 
 @vertex
@@ -30,30 +81,98 @@ fn vs_main(
     // Apply aspect ratio correction
     var pos = model.position;
     
-    // Calculate scale to fit image in window while maintaining aspect ratio
-    var scale: vec2<f32>;
-    if (uniforms.image_aspect > uniforms.window_aspect) {
-        // Image is wider than window
-        scale = vec2<f32>(1.0, uniforms.window_aspect / uniforms.image_aspect);
+    // Fit the rotated image's bounding box to the part of the window the insets leave uncovered
+    // while maintaining aspect ratio, sizes are in image heights and window heights
+    let rotation_sin = sin(uniforms.rotation);
+    let rotation_cos = cos(uniforms.rotation);
+    let bounds_width = uniforms.image_aspect * abs(rotation_cos) + abs(rotation_sin);
+    let bounds_height = uniforms.image_aspect * abs(rotation_sin) + abs(rotation_cos);
+    let free_width = 1.0 - uniforms.inset_left - uniforms.inset_right;
+    let free_height = 1.0 - uniforms.inset_top - uniforms.inset_bottom;
+    let fit_width = uniforms.window_aspect * free_width / bounds_width;
+    let fit_height = free_height / bounds_height;
+    var size: f32;
+    if (uniforms.fit_mode > 0.5 && uniforms.fit_mode < 1.5) {
+        size = fit_width;
+    } else if (uniforms.fit_mode > 1.5) {
+        size = fit_height;
     } else {
-        // Image is taller than window
-        scale = vec2<f32>(uniforms.image_aspect / uniforms.window_aspect, 1.0);
+        size = min(fit_width, fit_height);
     }
     
     // Apply zoom
-    scale = scale / uniforms.zoom;
+    size = size / uniforms.zoom;
     
-    // Apply pan (in normalized device coordinates)
-    pos.x = pos.x * scale.x - uniforms.pan_x * 2.0;
-    pos.y = pos.y * scale.y + uniforms.pan_y * 2.0;
+    // Grow the quad past the image's edges to make room for the frame
+    let half_size = vec2<f32>(uniforms.image_aspect * size, size);
+    let margin = (uniforms.frame_width + uniforms.frame_shadow) * 2.0 / uniforms.window_height;
+    let grow = (half_size + vec2<f32>(margin)) / half_size;
+    
+    // Rotate clockwise in window height units, so pixels stay square
+    let corner = pos.xy * half_size * grow;
+    let rotated = vec2<f32>(
+        corner.x * rotation_cos + corner.y * rotation_sin,
+        corner.y * rotation_cos - corner.x * rotation_sin,
+    );
+    
+    // Center on the uncovered area and apply pan (in normalized device coordinates)
+    pos.x = rotated.x / uniforms.window_aspect + uniforms.inset_left - uniforms.inset_right - uniforms.pan_x * 2.0;
+    pos.y = rotated.y + uniforms.inset_bottom - uniforms.inset_top + uniforms.pan_y * 2.0;
     
     out.clip_position = vec4<f32>(pos, 1.0);
-    out.tex_coords = model.tex_coords;
+    let pixels_per_unit = uniforms.window_height / 2.0;
+    out.frame_position = corner * pixels_per_unit;
+    out.image_half_size = half_size * pixels_per_unit;
+    // flip first, so mirroring happens around the image's own axes
+    var tex_coords = (model.tex_coords - vec2<f32>(0.5)) * grow + vec2<f32>(0.5);
+    if (uniforms.flip_x > 0.5) {
+        tex_coords.x = 1.0 - tex_coords.x;
+    }
+    if (uniforms.flip_y > 0.5) {
+        tex_coords.y = 1.0 - tex_coords.y;
+    }
+    out.tex_coords = vec2<f32>(uniforms.crop_x, uniforms.crop_y) + tex_coords * vec2<f32>(uniforms.crop_width, uniforms.crop_height);
     return out;
 }
 
 // Fragment shader
 
+// Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Polynomial fits of matplotlib's viridis and magma by Matt Zucker, t in 0..1 to sRGB encoded colors
+fn viridis(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(0.2777273272234177, 0.005407344544966578, 0.3340998053353061);
+    let c1 = vec3<f32>(0.1050930431085774, 1.404613529898575, 1.384590162594685);
+    let c2 = vec3<f32>(-0.3308618287255563, 0.214847559468213, 0.09509516302823659);
+    let c3 = vec3<f32>(-4.634230498983486, -5.799100973351585, -19.33244095627987);
+    let c4 = vec3<f32>(6.228269936347081, 14.17993336680509, 56.69055260068105);
+    let c5 = vec3<f32>(4.776384997670288, -13.74514537774601, -65.35303263337234);
+    let c6 = vec3<f32>(-5.435455855934631, 4.645852612178535, 26.3124352495832);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+fn magma(t: f32) -> vec3<f32> {
+    let c0 = vec3<f32>(-0.002136485053939582, -0.000749655052795221, -0.005386127855323933);
+    let c1 = vec3<f32>(0.2516605407371642, 0.6775232436837668, 2.494026599312351);
+    let c2 = vec3<f32>(8.353717279216625, -3.577719514958484, 0.3144679030132573);
+    let c3 = vec3<f32>(-27.66873308576866, 14.26473078096533, -13.64921318813922);
+    let c4 = vec3<f32>(52.17613981234068, -27.94360607168351, 12.94416944238394);
+    let c5 = vec3<f32>(-50.76852536473588, 29.04658282127291, 4.23415299384598);
+    let c6 = vec3<f32>(18.65570506591883, -11.48977351997711, -5.601961508734096);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+// black through red and yellow to white
+fn heat(t: f32) -> vec3<f32> {
+    return clamp(vec3<f32>(3.0 * t, 3.0 * t - 1.0, 3.0 * t - 2.0), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// alpha of the shadow where it meets the image
+const SHADOW_OPACITY: f32 = 0.45;
+
 @group(0) @binding(0)
 var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
@@ -61,6 +180,102 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    var color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    if (uniforms.sharpen > 0.0) {
+        // unsharp mask over the direct neighbours, sampled through the same filter as the image itself
+        let texel_size = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+        let left = textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords - vec2<f32>(texel_size.x, 0.0), 0.0);
+        let right = textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords + vec2<f32>(texel_size.x, 0.0), 0.0);
+        let up = textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords - vec2<f32>(0.0, texel_size.y), 0.0);
+        let down = textureSampleLevel(t_diffuse, s_diffuse, in.tex_coords + vec2<f32>(0.0, texel_size.y), 0.0);
+        let blurred = (left + right + up + down) * 0.25;
+        // staying within the neighbourhood's range keeps edges from ringing
+        let low = min(min(min(left, right), min(up, down)), color);
+        let high = max(max(max(left, right), max(up, down)), color);
+        color = clamp(color + (color - blurred) * uniforms.sharpen, low, high);
+    }
+    if (uniforms.channel > 0.0) {
+        // the channel's straight value, opaque so alpha can't hide it
+        var straight = vec3<f32>(0.0);
+        if (color.a > 0.0) {
+            straight = color.rgb / color.a;
+        }
+        var value: f32;
+        if (uniforms.channel < 1.5) {
+            value = straight.r;
+        } else if (uniforms.channel < 2.5) {
+            value = straight.g;
+        } else if (uniforms.channel < 3.5) {
+            value = straight.b;
+        } else {
+            // alpha is stored linearly, encoding it like the colors shows its byte values as the gray levels
+            value = pow(color.a, 2.2);
+        }
+        color = vec4<f32>(vec3<f32>(value), 1.0);
+    }
+    if (uniforms.negative > 0.0 && color.a > 0.0) {
+        // dividing by the film base turns unexposed film white, inverting the encoded result gives the positive
+        let film_base = vec3<f32>(uniforms.film_base_r, uniforms.film_base_g, uniforms.film_base_b);
+        let transmitted = clamp(color.rgb / color.a / film_base, vec3<f32>(0.0), vec3<f32>(1.0));
+        let positive = pow(vec3<f32>(1.0) - pow(transmitted, vec3<f32>(1.0 / 2.2)), vec3<f32>(2.2));
+        color = vec4<f32>(positive * color.a, color.a);
+    }
+    if (uniforms.tone_mapping > 0.0 && color.a > 0.0) {
+        // tone map the straight color, then pre-multiply again
+        var rgb = color.rgb / color.a * exp2(uniforms.exposure);
+        if (uniforms.tone_mapping < 1.5) {
+            rgb = rgb / (rgb + vec3<f32>(1.0));
+        } else {
+            rgb = aces(rgb);
+        }
+        color = vec4<f32>(rgb * color.a, color.a);
+    }
+    if (uniforms.colormap > 0.0 && color.a > 0.0) {
+        // maps the encoded luma so the gradient is spread evenly over 8 bit values, then decodes the map's color again
+        let luma = dot(color.rgb / color.a, vec3<f32>(0.2126, 0.7152, 0.0722));
+        let t = clamp(pow(luma, 1.0 / 2.2), 0.0, 1.0);
+        var mapped: vec3<f32>;
+        if (uniforms.colormap < 1.5) {
+            mapped = viridis(t);
+        } else if (uniforms.colormap < 2.5) {
+            mapped = magma(t);
+        } else {
+            mapped = heat(t);
+        }
+        color = vec4<f32>(pow(clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2)) * color.a, color.a);
+    }
+    if (uniforms.checkerboard > 0.0) {
+        // fixed size squares in screen space, so the pattern doesn't scale with zoom
+        let square = vec2<u32>(in.clip_position.xy / 8.0);
+        let shade = select(0.6, 0.8, (square.x + square.y) % 2u == 0u);
+        color = color + vec4<f32>(shade, shade, shade, 1.0) * (1.0 - color.a);
+    }
+    
+    // Pixel grid, derivatives have to be taken before any non-uniform branching
+    let texel = in.tex_coords * vec2<f32>(textureDimensions(t_diffuse));
+    let texels_per_pixel = fwidth(texel);
+    let magnification = 1.0 / max(texels_per_pixel.x, texels_per_pixel.y);
+    if (uniforms.grid_threshold > 0.0 && magnification >= uniforms.grid_threshold) {
+        // distance to the nearest texel edge in screen pixels
+        let edge_distance = min(fract(texel), 1.0 - fract(texel)) / texels_per_pixel;
+        if (min(edge_distance.x, edge_distance.y) < 0.5) {
+            // colors are premultiplied, so blend a grey line over the texel
+            let grid_color = vec4<f32>(0.5, 0.5, 0.5, 1.0);
+            color = mix(color, grid_color, 0.35);
+        }
+    }
+    
+    // Frame around the image, in place of the clamped texels the grown quad samples past the edges
+    let edge_distance = length(max(abs(in.frame_position) - in.image_half_size, vec2<f32>(0.0)));
+    if (uniforms.frame_width + uniforms.frame_shadow > 0.0 && edge_distance > 0.0) {
+        if (edge_distance <= uniforms.frame_width) {
+            color = vec4<f32>(vec3<f32>(uniforms.frame_r, uniforms.frame_g, uniforms.frame_b) * uniforms.frame_a, uniforms.frame_a);
+        } else {
+            // without a shadow this only catches the edge's rounding error, which stays transparent
+            let shadow = 1.0 - smoothstep(0.0, 1.0, (edge_distance - uniforms.frame_width) / max(uniforms.frame_shadow, 0.001));
+            color = vec4<f32>(0.0, 0.0, 0.0, SHADOW_OPACITY * shadow * shadow);
+        }
+    }
+    return vec4<f32>(color.rgb * (1.0 - uniforms.dim), color.a);
 }
 
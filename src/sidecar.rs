@@ -0,0 +1,71 @@
+use crate::errors::SidecarError;
+use crate::wgpu_renderer::Crop;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// How an image should be shown and how it was rated, read from `<image>.luminix.json` next to it so other tools can
+/// prepare the view. Missing fields keep their defaults, so a sidecar only needs what it changes
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct Sidecar {
+    /// Clockwise, in degrees
+    pub rotation: f32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    /// Part of the image to show, the whole image when absent
+    pub crop: Option<Crop>,
+    /// 1 to 5 stars, 0 when unrated
+    pub rating: u8,
+    pub flag: Flag,
+}
+
+/// Culling decision, independent of the rating
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq)]
+pub enum Flag {
+    #[default]
+    None,
+    Pick,
+    Reject,
+}
+
+impl Sidecar {
+    /// Rated or picked, rejects don't count even when rated
+    pub fn is_picked(&self) -> bool {
+        self.flag == Flag::Pick || (self.rating > 0 && self.flag != Flag::Reject)
+    }
+
+    /// Whether it changes the view, a sidecar that only holds marks leaves it as the image is
+    pub fn has_view(&self) -> bool {
+        self.rotation != 0.0 || self.flip_horizontal || self.flip_vertical || self.crop.is_some()
+    }
+
+    /// Neither rated nor flagged yet
+    pub fn is_unmarked(&self) -> bool {
+        self.rating == 0 && self.flag == Flag::None
+    }
+
+    /// `photo.jpg` has its sidecar at `photo.jpg.luminix.json`, keeping it apart from one for `photo.png`
+    pub fn path(image_path: &Path) -> PathBuf {
+        let mut path = image_path.as_os_str().to_owned();
+        path.push(".luminix.json");
+        PathBuf::from(path)
+    }
+
+    /// Reads the sidecar of `image_path`, `Ok(None)` if it has none
+    pub fn load(image_path: &Path) -> Result<Option<Sidecar>, SidecarError> {
+        let f = match File::open(Self::path(image_path)) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(serde_json::from_reader(f)?))
+    }
+
+    /// Writes the sidecar of `image_path`, returning where it went
+    pub fn save(&self, image_path: &Path) -> Result<PathBuf, SidecarError> {
+        let path = Self::path(image_path);
+        serde_json::to_writer_pretty(File::create(&path)?, self)?;
+        Ok(path)
+    }
+}
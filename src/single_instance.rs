@@ -0,0 +1,101 @@
+use crate::UserEvent;
+use interprocess::local_socket::{prelude::*, GenericFilePath, GenericNamespaced, Listener, ListenerOptions, Name, Stream};
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use winit::event_loop::EventLoopProxy;
+
+/// How this process relates to other running instances
+pub enum Instance {
+    /// No other instance is running, this one takes paths from later launches
+    Primary(Listener),
+    /// The path was handed to the running instance, this process has nothing left to do
+    Forwarded,
+    /// The socket can't be used, this process opens its own window as if single instance mode was off
+    Standalone,
+}
+
+/// Becomes the primary instance, or hands `image_path` to the one that already is.
+/// A socket nobody answers on was left behind by an instance that crashed, it's taken over once
+pub fn claim(image_path: &Path) -> Instance {
+    let name = match socket_name() {
+        Ok(name) => name,
+        Err(e) => {
+            log::error!("Failed to name the single instance socket. Error message: {e}");
+            return Instance::Standalone;
+        }
+    };
+    // the running instance has another working directory
+    let image_path = match image_path.canonicalize() {
+        Ok(image_path) => image_path,
+        Err(e) => {
+            log::error!("Failed to resolve {} for the running instance. Error message: {e}", image_path.display());
+            return Instance::Standalone;
+        }
+    };
+    let Some(image_path) = image_path.to_str() else {
+        log::error!("Can't hand {} to the running instance, the path isn't valid UTF-8", image_path.display());
+        return Instance::Standalone;
+    };
+    let mut overwrite = false;
+    loop {
+        match ListenerOptions::new().name(name.borrow()).try_overwrite(overwrite).create_sync() {
+            Ok(listener) => return Instance::Primary(listener),
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && !overwrite => {}
+            Err(e) => {
+                log::error!("Failed to listen for other instances. Error message: {e}");
+                return Instance::Standalone;
+            }
+        }
+        match forward(name.borrow(), image_path) {
+            Ok(()) => return Instance::Forwarded,
+            Err(e) if matches!(e.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::NotFound) => overwrite = true,
+            Err(e) => {
+                log::error!("Failed to reach the running instance. Error message: {e}");
+                return Instance::Standalone;
+            }
+        }
+    }
+}
+
+/// Accepts paths from later launches on a background thread and sends them to the event loop
+pub fn listen(listener: Listener, event_loop_proxy: EventLoopProxy<UserEvent>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::error!("Failed to accept another instance. Error message: {e}");
+                    continue;
+                }
+            };
+            let mut line = String::new();
+            if let Err(e) = BufReader::new(stream).read_line(&mut line) {
+                log::error!("Failed to read from another instance. Error message: {e}");
+                continue;
+            }
+            let path = PathBuf::from(line.trim_end_matches('\n'));
+            if event_loop_proxy.send_event(UserEvent::OpenPath(path)).is_err() {
+                // the event loop is gone, luminix is closing
+                return;
+            }
+        }
+    });
+}
+
+fn forward(name: Name, image_path: &str) -> io::Result<()> {
+    let mut stream = Stream::connect(name)?;
+    stream.write_all(format!("{image_path}\n").as_bytes())
+}
+
+/// One socket per user, so two people on the same machine don't share a window
+fn socket_name() -> io::Result<Name<'static>> {
+    let user = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_default();
+    let file_name = format!("luminix-{user}.sock");
+    if GenericNamespaced::is_supported() {
+        file_name.to_ns_name::<GenericNamespaced>()
+    } else {
+        env::temp_dir().join(file_name).to_fs_name::<GenericFilePath>()
+    }
+}
@@ -0,0 +1,14 @@
+/// Asks the system to keep the display on and not sleep while `awake` is set, back to normal once it's cleared
+#[cfg(target_os = "windows")]
+pub fn keep_awake(awake: bool) {
+    use windows_sys::Win32::System::Power::{SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED};
+
+    let flags = if awake { ES_CONTINUOUS | ES_DISPLAY_REQUIRED | ES_SYSTEM_REQUIRED } else { ES_CONTINUOUS };
+    // SAFETY: only changes the execution state of the calling thread, which is always the event loop's
+    if unsafe { SetThreadExecutionState(flags) } == 0 {
+        log::error!("Failed to change the system's sleep behavior");
+    }
+}
+// TODO: inhibit the screensaver through the desktop portal on linux
+#[cfg(not(target_os = "windows"))]
+pub fn keep_awake(_awake: bool) {}
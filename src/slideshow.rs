@@ -0,0 +1,93 @@
+use crate::errors::SlideshowError;
+use crate::image_loader::{image_paths_in_dir, is_supported_image, sort_key};
+use crate::UserEvent;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use winit::event_loop::EventLoopProxy;
+
+/// Quiet period after the last change before the folders are listed again, so a batch of copies is one rescan
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Cycles through the images of the configured hotfolders, picking up images added to them and dropping deleted ones
+pub struct Slideshow {
+    folders: Vec<PathBuf>,
+    /// Every image in the folders, ordered by file name like a single folder would be
+    images: Vec<PathBuf>,
+    /// Shown image, kept as a path so images added before it don't shift the rotation
+    current: Option<PathBuf>,
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl Slideshow {
+    /// Watches `folders` and lists their images, `current` starts out as the first of them
+    pub fn new(folders: &[PathBuf], event_loop_proxy: EventLoopProxy<UserEvent>) -> Result<Self, SlideshowError> {
+        if folders.is_empty() {
+            return Err(SlideshowError::NoFolders);
+        }
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                // deletions can't be checked for being images anymore, so only other files are ruled out
+                if events.iter().any(|event| is_supported_image(&event.path) || !event.path.exists()) {
+                    let _ = event_loop_proxy.send_event(UserEvent::HotfoldersChanged);
+                }
+            }
+            Err(e) => log::error!("Failed to watch the hotfolders for changes. Error message: {e}"),
+        })?;
+        for folder in folders {
+            debouncer.watcher().watch(folder, RecursiveMode::NonRecursive)?;
+        }
+        let mut slideshow = Slideshow { folders: folders.to_vec(), images: Vec::new(), current: None, _debouncer: debouncer };
+        slideshow.rescan();
+        slideshow.current = slideshow.images.first().cloned();
+        Ok(slideshow)
+    }
+
+    pub fn current(&self) -> Option<&Path> {
+        self.current.as_deref()
+    }
+
+    /// Lists the folders again, returns the images that weren't there before in rotation order.
+    /// A folder that can't be read counts as empty until it can
+    pub fn rescan(&mut self) -> Vec<PathBuf> {
+        let mut images: Vec<PathBuf> = self.folders.iter()
+            .flat_map(|folder| image_paths_in_dir(folder).unwrap_or_else(|e| {
+                log::error!("Failed to list hotfolder {}. Error message: {e}", folder.display());
+                Vec::new()
+            }))
+            .collect();
+        images.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)).then_with(|| a.cmp(b)));
+        let known: HashSet<&PathBuf> = self.images.iter().collect();
+        let added = images.iter().filter(|image| !known.contains(image)).cloned().collect();
+        self.images = images;
+        added
+    }
+
+    /// Whether the shown image is still in one of the folders
+    pub fn current_exists(&self) -> bool {
+        self.current.as_ref().is_some_and(|current| self.images.contains(current))
+    }
+
+    /// Moves on to the image after the shown one, wrapping around to the first.
+    /// After the shown image was deleted that's the one that sorts after it
+    pub fn advance(&mut self) -> Option<&Path> {
+        let next = match &self.current {
+            Some(current) => {
+                let key = (sort_key(current), current);
+                self.images.iter()
+                    .find(|image| (sort_key(image), *image) > key)
+                    .or(self.images.first())
+            }
+            None => self.images.first(),
+        };
+        self.current = next.cloned();
+        self.current.as_deref()
+    }
+
+    /// Jumps to `image`, the rotation carries on from where it sorts even if it's not in the folders
+    pub fn show(&mut self, image: PathBuf) {
+        self.current = Some(image);
+    }
+}
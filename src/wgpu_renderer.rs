@@ -1,7 +1,14 @@
+use crate::errors::{CaptureError, RendererError};
+use crate::image_loader::srgb_to_linear;
+use crate::overlay::OverlayOutput;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::mem::size_of;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
+#[cfg(target_os = "windows")]
 use wgpu::wgt::Dx12SwapchainKind;
-use winit::dpi::PhysicalPosition;
+use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
 pub struct WgpuRenderer {
@@ -16,16 +23,300 @@ pub struct WgpuRenderer {
 
     // Texture resources - must be kept alive
     _texture: Option<wgpu::Texture>,
-    _texture_view: Option<wgpu::TextureView>,
+    texture_view: Option<wgpu::TextureView>,
     _sampler: Option<wgpu::Sampler>,
     texture_bind_group: Option<wgpu::BindGroup>,
+    image_size: (u32, u32),
 
     uniform_bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
 
-    // Transform state
-    pub pan_offset: PhysicalPosition<f32>,
-    pub zoom_level: f32,
+    egui_renderer: egui_wgpu::Renderer,
+
+    // Transform state, see `set_view`
+    zoom_factor: f32,
+    pan: (f32, f32),
+
+    zoom_policy: ZoomPolicy,
+    filter_mode: wgpu::FilterMode,
+    background: Background,
+    /// Linear, for [`Background::Solid`]
+    background_color: [f32; 3],
+    /// The current texture holds float data that goes through tone mapping
+    hdr: bool,
+    tone_mapping: ToneMapping,
+    /// In stops
+    exposure: f32,
+    fit_mode: FitMode,
+    /// Unsharp mask strength, 0.0 when off
+    sharpen: f32,
+    /// Clockwise, in radians
+    rotation: f32,
+    colormap: Colormap,
+    channel: Channel,
+    /// Mirrored horizontally and vertically, before rotating
+    flip: (bool, bool),
+    crop: Crop,
+    insets: Insets,
+    frame: Option<ImageFrame>,
+    /// Width of a source pixel relative to its height, 1.0 for square pixels
+    pixel_aspect: f32,
+    /// Fraction the whole window is darkened by, 0.0 when not dimmed
+    dim: f32,
+    /// Linear color of the unexposed film while showing a negative as its positive
+    film_base: Option<[f32; 3]>,
+    /// Skips sharpening, the most expensive per pixel work, while the window is being resized
+    preview: bool,
+}
+
+/// Strongest sharpening allowed, past this edges get visible halos even with the shader's clamping
+pub const MAX_SHARPEN: f32 = 1.0;
+/// Darkest film base channel allowed, anything below is no film stock
+pub const MIN_FILM_BASE: f32 = 0.05;
+/// Slack when snapping magnification to whole numbers, for float error in the fit calculation
+const INTEGER_SCALING_TOLERANCE: f32 = 1e-4;
+
+/// What shows through transparent parts of the image
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Background {
+    /// The window itself is transparent
+    #[default]
+    Transparent,
+    Checkerboard,
+    /// Filled with the color passed to [`WgpuRenderer::set_background_color`]
+    Solid,
+}
+
+/// Which image dimension is matched to the window before zooming
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FitMode {
+    /// The whole image fits inside the window
+    #[default]
+    Inside,
+    /// Width matches the window, tall images are panned vertically
+    Width,
+    /// Height matches the window, wide images are panned horizontally
+    Height,
+}
+
+/// Operator that maps high dynamic range images to the displayable range
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, strum::EnumIter)]
+pub enum ToneMapping {
+    Reinhard,
+    #[default]
+    Aces,
+}
+impl ToneMapping {
+    /// Maps a linear channel value like the shader does, for pixels that don't go through it
+    pub fn apply(self, value: f32) -> f32 {
+        match self {
+            ToneMapping::Reinhard => value / (value + 1.0),
+            // Narkowicz's fit of the ACES filmic curve
+            ToneMapping::Aces => ((value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)).clamp(0.0, 1.0),
+        }
+    }
+}
+impl Display for ToneMapping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ToneMapping::Reinhard => write!(f, "Reinhard"),
+            ToneMapping::Aces => write!(f, "ACES"),
+        }
+    }
+}
+
+/// How long [`WgpuRenderer::shutdown`] waits for submitted work before dropping the device anyway
+pub const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Waits for `device` to finish its submitted work, shared by every window that owns a device
+pub fn drain_device(device: &wgpu::Device) {
+    if let Err(e) = device.poll(wgpu::PollType::Wait { submission_index: None, timeout: Some(SHUTDOWN_TIMEOUT) }) {
+        log::warn!("GPU work didn't finish before closing: {e}");
+    }
+}
+
+/// Part of the image that is shown, in fractions of its width and height from the top left corner
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Crop {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+impl Default for Crop {
+    fn default() -> Self {
+        Crop { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }
+    }
+}
+impl Crop {
+    /// Moves the crop inside the image, keeping at least a sliver of it so the image never collapses to nothing
+    pub fn clamped(self) -> Crop {
+        const MIN_SIZE: f32 = 0.001;
+        let finite_or = |value: f32, default: f32| if value.is_finite() { value } else { default };
+        let x = finite_or(self.x, 0.0).clamp(0.0, 1.0 - MIN_SIZE);
+        let y = finite_or(self.y, 0.0).clamp(0.0, 1.0 - MIN_SIZE);
+        Crop {
+            x,
+            y,
+            width: finite_or(self.width, 1.0).clamp(MIN_SIZE, 1.0 - x),
+            height: finite_or(self.height, 1.0).clamp(MIN_SIZE, 1.0 - y),
+        }
+    }
+}
+
+/// Parts of the window covered by UI such as a docked panel, in fractions of its width and height.
+/// Fitting keeps the image inside the rest of the window
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+impl Insets {
+    /// Keeps at least a tenth of the window uncovered along each axis
+    pub fn clamped(self) -> Insets {
+        const MAX_COVERED: f32 = 0.9;
+        let clamp = |value: f32| if value.is_finite() { value.clamp(0.0, MAX_COVERED) } else { 0.0 };
+        let (left, right) = (clamp(self.left), clamp(self.right));
+        let (top, bottom) = (clamp(self.top), clamp(self.bottom));
+        let horizontal_scale = (MAX_COVERED / (left + right)).min(1.0);
+        let vertical_scale = (MAX_COVERED / (top + bottom)).min(1.0);
+        Insets {
+            left: left * horizontal_scale,
+            top: top * vertical_scale,
+            right: right * horizontal_scale,
+            bottom: bottom * vertical_scale,
+        }
+    }
+
+    /// Uncovered fraction of the window's width and height
+    fn free_size(&self) -> (f32, f32) {
+        (1.0 - self.left - self.right, 1.0 - self.top - self.bottom)
+    }
+
+    /// Center of the uncovered area in normalized device coordinates, where an unpanned image is centered
+    pub fn center(&self) -> (f32, f32) {
+        (self.left - self.right, self.bottom - self.top)
+    }
+}
+
+/// Border and soft shadow drawn around the image, so its edges stand out against a busy desktop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImageFrame {
+    /// In screen pixels, 0.0 draws no border
+    pub width: f32,
+    /// How far past the border the shadow fades out, in screen pixels, 0.0 draws no shadow
+    pub shadow: f32,
+    /// Border color with straight alpha, sRGB when passed to [`WgpuRenderer::set_frame`] and linear once stored
+    pub color: [f32; 4],
+}
+
+/// False colors the image's brightness is mapped through, for masks, depth maps and other single channel data
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Colormap {
+    #[default]
+    Off,
+    Viridis,
+    Magma,
+    Heat,
+}
+impl Colormap {
+    /// The one after this, wrapping around to `Off`
+    pub fn next(self) -> Colormap {
+        match self {
+            Colormap::Off => Colormap::Viridis,
+            Colormap::Viridis => Colormap::Magma,
+            Colormap::Magma => Colormap::Heat,
+            Colormap::Heat => Colormap::Off,
+        }
+    }
+}
+impl Display for Colormap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Colormap::Off => write!(f, "Off"),
+            Colormap::Viridis => write!(f, "Viridis"),
+            Colormap::Magma => write!(f, "Magma"),
+            Colormap::Heat => write!(f, "Heat"),
+        }
+    }
+}
+
+/// A single channel of the image shown as grayscale, for textures that pack unrelated data into their channels
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Channel {
+    /// The image's own colors
+    #[default]
+    All,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+impl Channel {
+    /// The one after this, wrapping around to `All`
+    pub fn next(self) -> Channel {
+        match self {
+            Channel::All => Channel::Red,
+            Channel::Red => Channel::Green,
+            Channel::Green => Channel::Blue,
+            Channel::Blue => Channel::Alpha,
+            Channel::Alpha => Channel::All,
+        }
+    }
+}
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::All => write!(f, "RGB"),
+            Channel::Red => write!(f, "Red"),
+            Channel::Green => write!(f, "Green"),
+            Channel::Blue => write!(f, "Blue"),
+            Channel::Alpha => write!(f, "Alpha"),
+        }
+    }
+}
+
+/// How the color channels of an image relate to its alpha.
+/// Most files store straight alpha, but some tools write colors that are already multiplied by alpha,
+/// converting those again darkens semi-transparent edges
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize, strum::EnumIter)]
+pub enum AlphaMode {
+    #[default]
+    Straight,
+    Premultiplied,
+}
+impl Display for AlphaMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphaMode::Straight => write!(f, "Straight"),
+            AlphaMode::Premultiplied => write!(f, "Premultiplied"),
+        }
+    }
+}
+
+/// Decides how the image is filtered and decorated depending on how far it is magnified.
+/// Thresholds are in screen pixels per image pixel.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZoomPolicy {
+    pub smooth_filtering: bool,
+    /// Magnification snaps down to whole numbers, or to whole fractions when the image is shrunk. Implies nearest filtering
+    pub integer_scaling: bool,
+    /// Above this magnification nearest filtering is used even if smooth filtering is on
+    pub nearest_threshold: f32,
+    /// `None` disables the grid
+    pub pixel_grid_threshold: Option<f32>,
+}
+impl Default for ZoomPolicy {
+    fn default() -> Self {
+        ZoomPolicy {
+            smooth_filtering: false,
+            integer_scaling: false,
+            nearest_threshold: 4.0,
+            pixel_grid_threshold: None,
+        }
+    }
 }
 
 #[repr(C)]
@@ -68,6 +359,9 @@ const INDICES: &[u16] = &[
     2, 3, 0,
 ];
 
+/// Mirrors `Uniforms` in both shaders, which read the buffer by WGSL's layout rules. Sticking to `f32` fields keeps
+/// the two layouts identical without padding. A field added here needs the same name at the same place in the shaders
+/// and an entry in `UNIFORM_LAYOUT` in the tests, which check all three agree
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Uniforms {
@@ -76,11 +370,59 @@ struct Uniforms {
     zoom: f32,
     pan_x: f32,
     pan_y: f32,
-    _padding: [f32; 3],
+    // 0.0 means the grid is disabled
+    grid_threshold: f32,
+    // 1.0 when the checkerboard is drawn
+    checkerboard: f32,
+    // 0.0 for SDR images, otherwise 1.0 Reinhard and 2.0 ACES
+    tone_mapping: f32,
+    exposure: f32,
+    // 0.0 inside, 1.0 width, 2.0 height
+    fit_mode: f32,
+    // 0.0 disables sharpening
+    sharpen: f32,
+    // clockwise, in radians
+    rotation: f32,
+    // 0.0 off, 1.0 viridis, 2.0 magma, 3.0 heat
+    colormap: f32,
+    // 1.0 mirrors along that axis
+    flip_x: f32,
+    flip_y: f32,
+    // shown part of the texture, in texture coordinates
+    crop_x: f32,
+    crop_y: f32,
+    crop_width: f32,
+    crop_height: f32,
+    // fraction the final color is darkened by
+    dim: f32,
+    // 1.0 inverts film negatives
+    negative: f32,
+    // linear
+    film_base_r: f32,
+    film_base_g: f32,
+    film_base_b: f32,
+    // covered fractions of the window, fitting and centering use the rest
+    inset_left: f32,
+    inset_top: f32,
+    inset_right: f32,
+    inset_bottom: f32,
+    // physical pixels
+    window_height: f32,
+    // in screen pixels, 0.0 leaves the border or shadow out
+    frame_width: f32,
+    frame_shadow: f32,
+    // linear, straight alpha
+    frame_r: f32,
+    frame_g: f32,
+    frame_b: f32,
+    frame_a: f32,
+    // 0.0 all channels, 1.0 to 4.0 red, green, blue or alpha alone
+    channel: f32,
 }
 
 impl WgpuRenderer {
-    pub async fn new(window: Arc<Window>) -> Self {
+    /// `visual_swapchain` makes the window transparent on Windows, it's ignored elsewhere
+    pub async fn new(window: Arc<Window>, #[cfg_attr(not(target_os = "windows"), allow(unused_variables))] visual_swapchain: bool) -> Result<Self, RendererError> {
         let size = window.inner_size();
         
         // Use DX12 on Windows for transparency support
@@ -90,10 +432,12 @@ impl WgpuRenderer {
             wgpu::Backends::PRIMARY
         };
         
-        // Configure DX12 to use DxgiFromVisual for transparency on Windows
+        // Configure DX12 to use DxgiFromVisual for transparency on Windows,
+        // the default swapchain is opaque but works on drivers that flicker with it
+        #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
         let mut backend_options = wgpu::BackendOptions::default();
         #[cfg(target_os = "windows")]
-        {
+        if visual_swapchain {
             backend_options.dx12.presentation_system = Dx12SwapchainKind::DxgiFromVisual;
         }
         
@@ -103,7 +447,7 @@ impl WgpuRenderer {
             ..Default::default()
         });
 
-        let surface = instance.create_surface(window).unwrap();
+        let surface = instance.create_surface(window)?;
 
                 let adapter = instance.request_adapter(
                     &wgpu::RequestAdapterOptions {
@@ -111,7 +455,9 @@ impl WgpuRenderer {
                         compatible_surface: Some(&surface),
                         force_fallback_adapter: false,
                     },
-        ).await.unwrap();
+        ).await?;
+        let adapter_info = adapter.get_info();
+        log::info!("Drawing with {} through {}", adapter_info.name, adapter_info.backend);
 
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
@@ -122,7 +468,7 @@ impl WgpuRenderer {
                 trace: Default::default(),
                 experimental_features: Default::default(),
             },
-        ).await.unwrap();
+        ).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         
@@ -157,16 +503,22 @@ impl WgpuRenderer {
             height: size.height,
             present_mode,
             alpha_mode,
-            view_formats: vec![surface_format.add_srgb_suffix()],
+            // the image is drawn through an sRGB view, egui expects to blend in gamma space so it gets a linear one
+            view_formats: vec![surface_format.add_srgb_suffix(), surface_format.remove_srgb_suffix()],
             desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
-
-        // Create shader module
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
+        
+        let egui_renderer = egui_wgpu::Renderer::new(
+            &device,
+            surface_format.remove_srgb_suffix(),
+            egui_wgpu::RendererOptions {
+                depth_stencil_format: None,
+                msaa_samples: 1,
+                dithering: true,
+                predictable_texture_filtering: false,
+            },
+        );
 
         // Create texture bind group layout
                 let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -196,11 +548,12 @@ impl WgpuRenderer {
                     entries: &[
                         wgpu::BindGroupLayoutEntry {
                             binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
+                            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                             ty: wgpu::BindingType::Buffer {
                                 ty: wgpu::BufferBindingType::Uniform,
                                 has_dynamic_offset: false,
-                                min_binding_size: None,
+                                // shaders expecting more uniforms than the buffer holds fail to create a pipeline
+                                min_binding_size: wgpu::BufferSize::new(size_of::<Uniforms>() as u64),
                             },
                             count: None,
                         },
@@ -215,7 +568,37 @@ impl WgpuRenderer {
                     zoom: 1.0,
                     pan_x: 0.0,
                     pan_y: 0.0,
-                    _padding: [0.0; 3],
+                    grid_threshold: 0.0,
+                    checkerboard: 0.0,
+                    tone_mapping: 0.0,
+                    exposure: 0.0,
+                    fit_mode: 0.0,
+                    sharpen: 0.0,
+                    rotation: 0.0,
+                    colormap: 0.0,
+                    flip_x: 0.0,
+                    flip_y: 0.0,
+                    crop_x: 0.0,
+                    crop_y: 0.0,
+                    crop_width: 1.0,
+                    crop_height: 1.0,
+                    dim: 0.0,
+                    negative: 0.0,
+                    film_base_r: 1.0,
+                    film_base_g: 1.0,
+                    film_base_b: 1.0,
+                    inset_left: 0.0,
+                    inset_top: 0.0,
+                    inset_right: 0.0,
+                    inset_bottom: 0.0,
+                    window_height: size.height as f32,
+                    frame_width: 0.0,
+                    frame_shadow: 0.0,
+                    frame_r: 0.0,
+                    frame_g: 0.0,
+                    frame_b: 0.0,
+                    frame_a: 0.0,
+                    channel: 0.0,
                 };
 
                 let uniform_buffer = device.create_buffer_init(
@@ -244,43 +627,16 @@ impl WgpuRenderer {
                     push_constant_ranges: &[],
                 });
 
-                let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("Render Pipeline"),
-                    layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: config.format,
-                            blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })],
-                        compilation_options: Default::default(),
-                    }),
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::TriangleList,
-                        strip_index_format: None,
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: None, // Disable culling - we're rendering a 2D quad
-                        polygon_mode: wgpu::PolygonMode::Fill,
-                        unclipped_depth: false,
-                        conservative: false,
-                    },
-                    depth_stencil: None,
-                    multisample: wgpu::MultisampleState {
-                        count: 1,
-                        mask: !0,
-                        alpha_to_coverage_enabled: false,
-                    },
-                    multiview: None,
-                    cache: None,
-                });
+                let render_pipeline = match create_render_pipeline(&device, &render_pipeline_layout, config.format, include_str!("shader.wgsl")).await {
+                    Ok(render_pipeline) => render_pipeline,
+                    Err(e) => {
+                        // some drivers reject WGSL features the main shader relies on, a plain image beats a crash
+                        log::warn!("Failed to compile shader, falling back to a basic one without pixel grid and checkerboard. Error message: {e}");
+                        create_render_pipeline(&device, &render_pipeline_layout, config.format, include_str!("fallback_shader.wgsl"))
+                            .await
+                            .expect("failed to compile fallback shader")
+                    }
+                };
 
                 let vertex_buffer = device.create_buffer_init(
                     &wgpu::util::BufferInitDescriptor {
@@ -300,7 +656,7 @@ impl WgpuRenderer {
 
         let num_indices = INDICES.len() as u32;
 
-        Self {
+        Ok(Self {
                     surface,
                     device,
                     queue,
@@ -310,14 +666,36 @@ impl WgpuRenderer {
                     index_buffer,
                     num_indices,
                     _texture: None,
-                    _texture_view: None,
+                    texture_view: None,
                     _sampler: None,
                     texture_bind_group: None,
+                    image_size: (1, 1),
                     uniform_bind_group,
                     uniform_buffer,
-                    pan_offset: PhysicalPosition::new(0.0, 0.0),
-                    zoom_level: 1.0,
-                }
+                    egui_renderer,
+                    zoom_factor: 1.0,
+                    pan: (0.0, 0.0),
+                    zoom_policy: ZoomPolicy::default(),
+                    filter_mode: wgpu::FilterMode::Nearest,
+                    background: Background::Transparent,
+                    background_color: [0.0; 3],
+                    hdr: false,
+                    tone_mapping: ToneMapping::default(),
+                    exposure: 0.0,
+                    fit_mode: FitMode::Inside,
+                    sharpen: 0.0,
+                    rotation: 0.0,
+                    colormap: Colormap::Off,
+                    channel: Channel::All,
+                    flip: (false, false),
+                    crop: Crop::default(),
+                    insets: Insets::default(),
+                    frame: None,
+                    pixel_aspect: 1.0,
+                    dim: 0.0,
+                    film_base: None,
+                    preview: false,
+                })
             }
 
             pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -325,32 +703,32 @@ impl WgpuRenderer {
                     self.config.width = new_size.width;
                     self.config.height = new_size.height;
                     self.surface.configure(&self.device, &self.config);
+                    // the fit calculation depends on the window aspect
+                    self.update_uniforms();
                 }
             }
 
-    pub fn load_texture(&mut self, image_data: &[u8], width: u32, height: u32) {
-        // Convert RGBA to BGRA with pre-multiplied alpha for transparency
-        let mut bgra_data = Vec::with_capacity(image_data.len());
-        for chunk in image_data.chunks_exact(4) {
-            // ...existing code...
-                    let r = chunk[0] as f32 / 255.0;
-                    let g = chunk[1] as f32 / 255.0;
-                    let b = chunk[2] as f32 / 255.0;
-                    let a = chunk[3] as f32 / 255.0;
-
-                    // Pre-multiply RGB by alpha
-                    let r_pre = (r * a * 255.0) as u8;
-                    let g_pre = (g * a * 255.0) as u8;
-                    let b_pre = (b * a * 255.0) as u8;
-                    let a_byte = (a * 255.0) as u8;
-
-                    // BGRA format
-                    bgra_data.push(b_pre); // B
-                    bgra_data.push(g_pre); // G
-                    bgra_data.push(r_pre); // R
-            bgra_data.push(a_byte); // A
-        }
+    pub fn load_texture(&mut self, image_data: &[u8], width: u32, height: u32, alpha_mode: AlphaMode) {
+        let bgra_data = premultiply_to_bgra(image_data, alpha_mode);
+        self.hdr = false;
+        self.upload_texture(wgpu::TextureFormat::Bgra8UnormSrgb, &bgra_data, 4, width, height);
+    }
 
+    /// Uploads linear float pixels, shown through the tone mapping operator
+    pub fn load_hdr_texture(&mut self, image_data: &[f32], width: u32, height: u32, alpha_mode: AlphaMode) {
+        // pre-multiplied like the 8 bit path, and halved in size since 16 bit floats are always filterable
+        let rgba_data: Vec<half::f16> = image_data
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let alpha = if alpha_mode == AlphaMode::Premultiplied { 1.0 } else { pixel[3] };
+                [pixel[0] * alpha, pixel[1] * alpha, pixel[2] * alpha, pixel[3]].map(half::f16::from_f32)
+            })
+            .collect();
+        self.hdr = true;
+        self.upload_texture(wgpu::TextureFormat::Rgba16Float, bytemuck::cast_slice(&rgba_data), 8, width, height);
+    }
+
+    fn upload_texture(&mut self, format: wgpu::TextureFormat, data: &[u8], bytes_per_pixel: u32, width: u32, height: u32) {
         let texture_size = wgpu::Extent3d {
                     width,
                     height,
@@ -363,7 +741,7 @@ impl WgpuRenderer {
                         mip_level_count: 1,
                         sample_count: 1,
                         dimension: wgpu::TextureDimension::D2,
-                        format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                        format,
                         usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
                         label: Some("image_texture"),
                         view_formats: &[],
@@ -377,120 +755,448 @@ impl WgpuRenderer {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &bgra_data,
+            data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * width),
+                bytes_per_row: Some(bytes_per_pixel * width),
                 rows_per_image: Some(height),
             },
             texture_size,
         );
 
                 let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
-                    address_mode_u: wgpu::AddressMode::ClampToEdge,
-                    address_mode_v: wgpu::AddressMode::ClampToEdge,
-                    address_mode_w: wgpu::AddressMode::ClampToEdge,
-                    mag_filter: wgpu::FilterMode::Nearest,
-                    min_filter: wgpu::FilterMode::Nearest,
-                    mipmap_filter: wgpu::FilterMode::Nearest,
-                    ..Default::default()
-                });
-
-                let texture_bind_group = self.device.create_bind_group(
-                    &wgpu::BindGroupDescriptor {
-                        layout: &self.render_pipeline.get_bind_group_layout(0),
-                        entries: &[
-                            wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: wgpu::BindingResource::TextureView(&texture_view),
-                            },
-                            wgpu::BindGroupEntry {
-                                binding: 1,
-                                resource: wgpu::BindingResource::Sampler(&sampler),
-                            }
-                        ],
-                        label: Some("texture_bind_group"),
-                    }
-                );
 
                 // Store the resources to prevent them from being dropped
                 self._texture = Some(texture);
-                self._texture_view = Some(texture_view);
-                self._sampler = Some(sampler);
-                self.texture_bind_group = Some(texture_bind_group);
+                self.texture_view = Some(texture_view);
+                self.image_size = (width, height);
+                self.rebuild_texture_bind_group();
 
         // Update image aspect ratio in uniforms
-        let image_aspect = width as f32 / height as f32;
-        self.update_uniforms(image_aspect);
+        self.update_uniforms();
+    }
+
+    /// Blocks until the GPU finished everything submitted so far, so the device can be dropped without work in flight.
+    /// Gives up after [`SHUTDOWN_TIMEOUT`], a hung or lost device shouldn't keep luminix from closing
+    pub fn shutdown(&self) {
+        drain_device(&self.device);
+    }
+
+    /// Frees the image texture, nothing but the overlay is drawn until the next `load_texture`
+    pub fn unload_texture(&mut self) {
+        self.texture_bind_group = None;
+        self.texture_view = None;
+        self._texture = None;
     }
 
-    fn update_uniforms(&mut self, image_aspect: f32) {
+    /// Recreates the sampler and texture bind group for the current filter mode, without re-uploading the texture
+    fn rebuild_texture_bind_group(&mut self) {
+        let Some(texture_view) = &self.texture_view else { return };
+        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: self.filter_mode,
+            min_filter: self.filter_mode,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let texture_bind_group = self.device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                layout: &self.render_pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    }
+                ],
+                label: Some("texture_bind_group"),
+            }
+        );
+
+        self._sampler = Some(sampler);
+        self.texture_bind_group = Some(texture_bind_group);
+    }
+
+    fn update_uniforms(&mut self) {
+                let (visible_width, visible_height) = self.visible_size();
+                let image_aspect = visible_width / visible_height;
                 let window_aspect = self.config.width as f32 / self.config.height as f32;
 
                 let uniforms = Uniforms {
                     image_aspect,
                     window_aspect,
-                    zoom: self.zoom_level,
-                    pan_x: self.pan_offset.x,
-                    pan_y: self.pan_offset.y,
-                    _padding: [0.0; 3],
+                    zoom: 1.0 / self.displayed_zoom(),
+                    pan_x: self.pan.0,
+                    pan_y: self.pan.1,
+                    grid_threshold: self.zoom_policy.pixel_grid_threshold.unwrap_or(0.0),
+                    checkerboard: if self.background == Background::Checkerboard { 1.0 } else { 0.0 },
+                    tone_mapping: match (self.hdr, self.tone_mapping) {
+                        (false, _) => 0.0,
+                        (true, ToneMapping::Reinhard) => 1.0,
+                        (true, ToneMapping::Aces) => 2.0,
+                    },
+                    exposure: self.exposure,
+                    fit_mode: match self.fit_mode {
+                        FitMode::Inside => 0.0,
+                        FitMode::Width => 1.0,
+                        FitMode::Height => 2.0,
+                    },
+                    sharpen: if self.preview { 0.0 } else { self.sharpen },
+                    rotation: self.rotation,
+                    colormap: match self.colormap {
+                        Colormap::Off => 0.0,
+                        Colormap::Viridis => 1.0,
+                        Colormap::Magma => 2.0,
+                        Colormap::Heat => 3.0,
+                    },
+                    flip_x: if self.flip.0 { 1.0 } else { 0.0 },
+                    flip_y: if self.flip.1 { 1.0 } else { 0.0 },
+                    crop_x: self.crop.x,
+                    crop_y: self.crop.y,
+                    crop_width: self.crop.width,
+                    crop_height: self.crop.height,
+                    dim: self.dim,
+                    negative: if self.film_base.is_some() { 1.0 } else { 0.0 },
+                    film_base_r: self.film_base.map_or(1.0, |film_base| film_base[0]),
+                    film_base_g: self.film_base.map_or(1.0, |film_base| film_base[1]),
+                    film_base_b: self.film_base.map_or(1.0, |film_base| film_base[2]),
+                    inset_left: self.insets.left,
+                    inset_top: self.insets.top,
+                    inset_right: self.insets.right,
+                    inset_bottom: self.insets.bottom,
+                    window_height: self.config.height as f32,
+                    frame_width: self.frame.map_or(0.0, |frame| frame.width),
+                    frame_shadow: self.frame.map_or(0.0, |frame| frame.shadow),
+                    frame_r: self.frame.map_or(0.0, |frame| frame.color[0]),
+                    frame_g: self.frame.map_or(0.0, |frame| frame.color[1]),
+                    frame_b: self.frame.map_or(0.0, |frame| frame.color[2]),
+                    frame_a: self.frame.map_or(0.0, |frame| frame.color[3]),
+                    channel: match self.channel {
+                        Channel::All => 0.0,
+                        Channel::Red => 1.0,
+                        Channel::Green => 2.0,
+                        Channel::Blue => 3.0,
+                        Channel::Alpha => 4.0,
+                    },
                 };
 
                 self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+                self.update_filter_mode();
             }
 
-            pub fn set_zoom(&mut self, zoom_level: i32, image_aspect: f32) {
-                // Convert zoom level (-10 to 10) to zoom factor
-                // Positive zoom = zoom in (factor > 1)
-                // Negative zoom = zoom out (factor < 1)
-                let zoom_factor = if zoom_level >= 0 {
-                    1.0 + (zoom_level as f32 * 0.2)  // zoom in: 1.0 to 3.0
-                } else {
-                    1.0 / (1.0 + (-zoom_level as f32 * 0.2))  // zoom out: 1.0 to ~0.33
-                };
+    /// Size in pixels of the part of the image that is shown, with the width stretched to square pixels
+    fn visible_size(&self) -> (f32, f32) {
+        let (image_width, image_height) = self.image_size;
+        (image_width as f32 * self.crop.width * self.pixel_aspect, image_height as f32 * self.crop.height)
+    }
 
-                self.zoom_level = zoom_factor;
-                self.update_uniforms(image_aspect);
-            }
+    /// Width and height of the rotated image's bounding box, in image heights
+    fn rotated_bounds(&self) -> (f32, f32) {
+        let (visible_width, visible_height) = self.visible_size();
+        let image_aspect = visible_width / visible_height;
+        let (sin, cos) = self.rotation.sin_cos();
+        (image_aspect * cos.abs() + sin.abs(), image_aspect * sin.abs() + cos.abs())
+    }
 
-    pub fn set_pan(&mut self, pan_offset: PhysicalPosition<f32>, image_width: u32, image_height: u32) {
-        // Normalize pan offset to -1.0 to 1.0 range based on image size
-        let norm_x = pan_offset.x / image_width as f32;
-        let norm_y = pan_offset.y / image_height as f32;
+    /// Image height relative to the window height before zooming, mirrors the fit calculation in the vertex shader.
+    /// It's the rotated bounding box that gets fitted, so corners stay inside the window at any angle.
+    /// Only the part of the window the insets leave uncovered counts
+    fn fit_size(&self) -> f32 {
+        self.fit_size_in(self.config.width, self.config.height)
+    }
 
-        self.pan_offset = PhysicalPosition::new(norm_x, norm_y);
+    /// `fit_size` for a window of `window_width` by `window_height` pixels
+    fn fit_size_in(&self, window_width: u32, window_height: u32) -> f32 {
+        let window_aspect = window_width as f32 / window_height as f32;
+        let (bounds_width, bounds_height) = self.rotated_bounds();
+        let (free_width, free_height) = self.insets.free_size();
+        let fit_width = window_aspect * free_width / bounds_width;
+        let fit_height = free_height / bounds_height;
+        match self.fit_mode {
+            FitMode::Inside => fit_width.min(fit_height),
+            FitMode::Width => fit_width,
+            FitMode::Height => fit_height,
+        }
+    }
 
-        let image_aspect = image_width as f32 / image_height as f32;
-        self.update_uniforms(image_aspect);
+    /// Size of the image's bounding box on screen relative to the window, 1.0 means it spans the window along that axis
+    pub fn image_extent(&self) -> (f32, f32) {
+        let window_aspect = self.config.width as f32 / self.config.height as f32;
+        let (bounds_width, bounds_height) = self.rotated_bounds();
+        let size = self.fit_size() * self.displayed_zoom();
+        (size * bounds_width / window_aspect, size * bounds_height)
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
-            format: Some(self.config.format.add_srgb_suffix()),
-            ..Default::default()
-        });
+    /// Screen pixels per image pixel
+    pub fn magnification(&self) -> f32 {
+        self.fit_magnification() * self.displayed_zoom()
+    }
 
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
+    /// Screen pixels per image pixel when the image is fitted, counted along its height
+    fn fit_magnification(&self) -> f32 {
+        self.config.height as f32 * self.fit_size() / self.visible_size().1
+    }
+
+    /// The zoom factor that is drawn, snapped to whole magnifications with integer scaling on
+    fn displayed_zoom(&self) -> f32 {
+        if !self.zoom_policy.integer_scaling {
+            return self.zoom_factor;
+        }
+        let fit_magnification = self.fit_magnification();
+        let magnification = fit_magnification * self.zoom_factor;
+        // the tolerance keeps exact multiples from flooring to the one below
+        let snapped = if magnification >= 1.0 - INTEGER_SCALING_TOLERANCE {
+            (magnification + INTEGER_SCALING_TOLERANCE).floor()
+        } else {
+            1.0 / (1.0 / magnification - INTEGER_SCALING_TOLERANCE).ceil()
+        };
+        snapped / fit_magnification
+    }
+
+    fn update_filter_mode(&mut self) {
+        let filter_mode = if self.zoom_policy.smooth_filtering && !self.zoom_policy.integer_scaling && self.magnification() < self.zoom_policy.nearest_threshold {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+        if filter_mode != self.filter_mode {
+            self.filter_mode = filter_mode;
+            self.rebuild_texture_bind_group();
+        }
+    }
+
+    pub fn set_zoom_policy(&mut self, zoom_policy: ZoomPolicy) {
+        if zoom_policy != self.zoom_policy {
+            self.zoom_policy = zoom_policy;
+            self.update_uniforms();
+        }
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        if background != self.background {
+            self.background = background;
+            self.update_uniforms();
+        }
+    }
+
+    /// Color of the solid background, in sRGB
+    pub fn set_background_color(&mut self, color: [f32; 3]) {
+        // only the clear color, the uniforms don't change
+        self.background_color = color.map(|channel| srgb_to_linear(channel.clamp(0.0, 1.0)));
+    }
+
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        if tone_mapping != self.tone_mapping {
+            self.tone_mapping = tone_mapping;
+            self.update_uniforms();
+        }
+    }
+
+    /// Exposure adjustment in stops for HDR images
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+        self.update_uniforms();
+    }
+
+    pub fn set_colormap(&mut self, colormap: Colormap) {
+        self.colormap = colormap;
+        self.update_uniforms();
+    }
+
+    pub fn set_channel(&mut self, channel: Channel) {
+        self.channel = channel;
+        self.update_uniforms();
+    }
+
+    /// Turns the image clockwise around its center
+    pub fn set_rotation(&mut self, degrees: f32) {
+        self.rotation = degrees.to_radians();
+        self.update_uniforms();
+    }
+
+    /// Mirrors the image horizontally and vertically, around its own axes before it's rotated
+    pub fn set_flip(&mut self, horizontal: bool, vertical: bool) {
+        self.flip = (horizontal, vertical);
+        self.update_uniforms();
+    }
+
+    /// Only shows `crop` of the image, fitting and zooming treat that part as the whole image
+    pub fn set_crop(&mut self, crop: Crop) {
+        self.crop = crop.clamped();
+        self.update_uniforms();
+    }
+
+    /// Stretches the image horizontally by `pixel_aspect` so non-square pixels show in their proper proportions
+    pub fn set_pixel_aspect(&mut self, pixel_aspect: f32) {
+        let pixel_aspect = if pixel_aspect.is_finite() && pixel_aspect > 0.0 { pixel_aspect } else { 1.0 };
+        if pixel_aspect != self.pixel_aspect {
+            self.pixel_aspect = pixel_aspect;
+            self.update_uniforms();
+        }
+    }
+
+    /// Keeps the fitted image out of the parts of the window `insets` covers, all zero uses the whole window
+    pub fn set_insets(&mut self, insets: Insets) {
+        let insets = insets.clamped();
+        if insets != self.insets {
+            self.insets = insets;
+            self.update_uniforms();
+        }
+    }
+
+    /// Center of the window's uncovered area in normalized device coordinates, see [`Insets::center`]
+    pub fn fit_center(&self) -> (f32, f32) {
+        self.insets.center()
+    }
+
+    /// Draws cheaper frames while `preview` is set, for keeping up with a window being resized
+    pub fn set_preview(&mut self, preview: bool) {
+        if preview != self.preview {
+            self.preview = preview;
+            self.update_uniforms();
+        }
+    }
+
+    /// Darkens everything drawn by `dim`, from 0.0 for unchanged to 1.0 for black
+    pub fn set_dim(&mut self, dim: f32) {
+        self.dim = dim.clamp(0.0, 1.0);
+        self.update_uniforms();
+    }
+
+    /// Shows the image as a film negative turned positive, with `film_base` being the sRGB color of the bare film
+    /// which gets corrected out. `None` shows the image as it is
+    pub fn set_negative(&mut self, film_base: Option<[f32; 3]>) {
+        // a zero channel would divide by zero
+        let film_base = film_base.map(|film_base| film_base.map(|channel| srgb_to_linear(channel.clamp(MIN_FILM_BASE, 1.0))));
+        if film_base != self.film_base {
+            self.film_base = film_base;
+            self.update_uniforms();
+        }
+    }
+
+    /// Draws `frame` around the image, `None` leaves the image's edges bare
+    pub fn set_frame(&mut self, frame: Option<ImageFrame>) {
+        let frame = frame.map(|frame| {
+            let size = |value: f32| if value.is_finite() { value.max(0.0) } else { 0.0 };
+            let [r, g, b, a] = frame.color;
+            ImageFrame {
+                width: size(frame.width),
+                shadow: size(frame.shadow),
+                color: [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a.clamp(0.0, 1.0)],
+            }
         });
+        if frame != self.frame {
+            self.frame = frame;
+            self.update_uniforms();
+        }
+    }
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                        store: wgpu::StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
+    /// Sharpens edges by `strength`, 0.0 turns it off
+    pub fn set_sharpen(&mut self, strength: f32) {
+        let strength = strength.clamp(0.0, MAX_SHARPEN);
+        if strength != self.sharpen {
+            self.sharpen = strength;
+            self.update_uniforms();
+        }
+    }
+
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        if fit_mode != self.fit_mode {
+            self.fit_mode = fit_mode;
+            self.update_uniforms();
+        }
+    }
+
+    /// Shows the whole image centered in the window. Fitting inside takes the smaller of the width and height fits,
+    /// so images wider than the window span its width and taller ones its height. The fit is recomputed from the
+    /// window size, so it holds through resizes until the view is zoomed or panned
+    pub fn fit_to_window(&mut self) {
+        self.fit_mode = FitMode::Inside;
+        self.zoom_factor = 1.0;
+        self.pan = (0.0, 0.0);
+        self.update_uniforms();
+    }
+
+    /// The zoom factor that shows one pixel of an `img_width` by `img_height` image per pixel of a `window_size` window.
+    /// Window sizes from winit are physical, so on HiDPI screens it's one device pixel and the image isn't resampled.
+    /// Images larger than the window come out larger than the fit and can be panned as usual
+    pub fn actual_size_zoom(&self, img_width: u32, img_height: u32, window_size: PhysicalSize<u32>) -> f32 {
+        if img_width == 0 || img_height == 0 || window_size.width == 0 || window_size.height == 0 {
+            return 1.0;
+        }
+        // counted along the height, the crop and rotation are in the fit and pixel aspect stretches the width only
+        let fit_magnification = window_size.height as f32 * self.fit_size_in(window_size.width, window_size.height)
+            / (img_height as f32 * self.crop.height);
+        1.0 / fit_magnification
+    }
+
+    /// Zooms to one image pixel per screen pixel, see `actual_size_zoom`, and returns the zoom factor.
+    /// The pan is left as it is
+    pub fn set_actual_size(&mut self, img_width: u32, img_height: u32, window_size: PhysicalSize<u32>) -> f32 {
+        self.zoom_factor = self.actual_size_zoom(img_width, img_height, window_size);
+        self.update_uniforms();
+        self.zoom_factor
+    }
+
+    /// Sets the view transform in one uniform write.
+    /// `zoom_factor` scales the image relative to fitting the window, 2.0 shows it twice as large.
+    /// `pan_normalized` is the pan offset divided by the image size, so -1.0 to 1.0 along each axis
+    pub fn set_view(&mut self, zoom_factor: f32, pan_normalized: (f32, f32)) {
+        self.zoom_factor = zoom_factor;
+        self.pan = pan_normalized;
+        self.update_uniforms();
+    }
+
+    pub fn max_texture_side(&self) -> usize {
+        self.device.limits().max_texture_dimension_2d as usize
+    }
+
+    pub fn render(&mut self, overlay: Option<&OverlayOutput>) -> Result<(), wgpu::SurfaceError> {
+        // egui only sends texture changes once, so they are applied even if this frame fails to render
+        if let Some(overlay) = overlay {
+            for (id, image_delta) in &overlay.textures_delta.set {
+                self.egui_renderer.update_texture(&self.device, &self.queue, *id, image_delta);
+            }
+        }
+        let result = self.render_frame(overlay);
+        if let Some(overlay) = overlay {
+            for id in &overlay.textures_delta.free {
+                self.egui_renderer.free_texture(id);
+            }
+        }
+        result
+    }
+
+    /// Clears `view` and draws the image into it with the current view settings
+    fn draw_image(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        // the image is blended over the clear color, so a solid background needs nothing from the shader
+        let clear_color = match self.background {
+            Background::Solid => {
+                let [r, g, b] = self.background_color;
+                wgpu::Color { r: r as f64, g: g as f64, b: b as f64, a: 1.0 }
+            }
+            Background::Transparent | Background::Checkerboard => wgpu::Color::TRANSPARENT,
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
         });
 
         if let Some(texture_bind_group) = &self.texture_bind_group {
@@ -503,10 +1209,304 @@ impl WgpuRenderer {
         }
     }
 
-    self.queue.submit(std::iter::once(encoder.finish()));
+    /// Draws the current view into an offscreen texture at window size and reads it back as straight alpha RGBA.
+    /// It's the image as it's shown, with zoom, pan, rotation, adjustments and background, but without the overlay
+    pub fn capture_view(&self) -> Result<image::RgbaImage, CaptureError> {
+        // has to match the pipeline's target
+        let format = self.config.format;
+        let swap_red_blue = match format {
+            wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            _ => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+        let (width, height) = (self.config.width, self.config.height);
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        // rows of a texture copy have to start at multiples of 256 bytes
+        let padded_bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: u64::from(padded_bytes_per_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+        self.draw_image(&mut encoder, &texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely())?;
+        receiver.recv().map_err(|_| CaptureError::Unmapped)??;
+
+        let mapped = buffer.slice(..).get_mapped_range();
+        let mut capture = image::RgbaImage::new(width, height);
+        for (row, captured_row) in mapped.chunks_exact(padded_bytes_per_row as usize).zip(capture.rows_mut()) {
+            for (texel, pixel) in row.chunks_exact(4).zip(captured_row) {
+                let (red, blue) = if swap_red_blue { (texel[2], texel[0]) } else { (texel[0], texel[2]) };
+                // the frame is premultiplied, image files and the clipboard expect straight alpha
+                let alpha = texel[3];
+                let unpremultiply = |value: u8| if alpha == 0 { 0 } else { ((u32::from(value) * 255 + u32::from(alpha) / 2) / u32::from(alpha)).min(255) as u8 };
+                *pixel = image::Rgba([unpremultiply(red), unpremultiply(texel[1]), unpremultiply(blue), alpha]);
+            }
+        }
+        Ok(capture)
+    }
+
+    fn render_frame(&mut self, overlay: Option<&OverlayOutput>) -> Result<(), wgpu::SurfaceError> {
+        let output = self.surface.get_current_texture()?;
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.config.format.add_srgb_suffix()),
+            ..Default::default()
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+        self.draw_image(&mut encoder, &view);
+    
+    let mut command_buffers = Vec::new();
+    if let Some(overlay) = overlay {
+        let overlay_view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.config.format.remove_srgb_suffix()),
+            ..Default::default()
+        });
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.config.width, self.config.height],
+            pixels_per_point: overlay.pixels_per_point,
+        };
+        command_buffers = self.egui_renderer.update_buffers(&self.device, &self.queue, &mut encoder, &overlay.primitives, &screen_descriptor);
+        
+        let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overlay Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &overlay_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        self.egui_renderer.render(&mut render_pass.forget_lifetime(), &overlay.primitives, &screen_descriptor);
+    }
+
+    command_buffers.push(encoder.finish());
+    self.queue.submit(command_buffers);
     output.present();
 
     Ok(())
 }
 }
 
+/// Compiles `shader_source` and builds the image pipeline with it.
+/// Errors are caught with an error scope instead of going to the device's panicking error handler
+async fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader_source: &str,
+) -> Result<wgpu::RenderPipeline, wgpu::Error> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+    });
+    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None, // Disable culling - we're rendering a 2D quad
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    });
+    match device.pop_error_scope().await {
+        Some(e) => Err(e),
+        None => Ok(render_pipeline),
+    }
+}
+
+/// Converts RGBA pixels to the BGRA layout of the image texture, multiplying colors by alpha unless they already are.
+/// Fully opaque images, like most screenshots saved with an alpha channel, skip the multiplication since it
+/// leaves their colors unchanged
+pub fn premultiply_to_bgra(image_data: &[u8], alpha_mode: AlphaMode) -> Vec<u8> {
+    let mut bgra_data = Vec::with_capacity(image_data.len());
+    if alpha_mode == AlphaMode::Premultiplied || image_data.chunks_exact(4).all(|pixel| pixel[3] == u8::MAX) {
+        // already multiplied or nothing to multiply, only the channel order changes
+        for chunk in image_data.chunks_exact(4) {
+            bgra_data.extend_from_slice(&[chunk[2], chunk[1], chunk[0], chunk[3]]);
+        }
+        return bgra_data;
+    }
+    for chunk in image_data.chunks_exact(4) {
+        let r = chunk[0] as f32 / 255.0;
+        let g = chunk[1] as f32 / 255.0;
+        let b = chunk[2] as f32 / 255.0;
+        let a = chunk[3] as f32 / 255.0;
+
+        // Pre-multiply RGB by alpha
+        let r_pre = (r * a * 255.0) as u8;
+        let g_pre = (g * a * 255.0) as u8;
+        let b_pre = (b * a * 255.0) as u8;
+        let a_byte = (a * 255.0) as u8;
+
+        // BGRA format
+        bgra_data.push(b_pre); // B
+        bgra_data.push(g_pre); // G
+        bgra_data.push(r_pre); // R
+        bgra_data.push(a_byte); // A
+    }
+    bgra_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    /// Name and byte offset of every uniform, which the shaders' `Uniforms` struct has to match
+    const UNIFORM_LAYOUT: [(&str, usize); 36] = [
+        ("image_aspect", offset_of!(Uniforms, image_aspect)),
+        ("window_aspect", offset_of!(Uniforms, window_aspect)),
+        ("zoom", offset_of!(Uniforms, zoom)),
+        ("pan_x", offset_of!(Uniforms, pan_x)),
+        ("pan_y", offset_of!(Uniforms, pan_y)),
+        ("grid_threshold", offset_of!(Uniforms, grid_threshold)),
+        ("checkerboard", offset_of!(Uniforms, checkerboard)),
+        ("tone_mapping", offset_of!(Uniforms, tone_mapping)),
+        ("exposure", offset_of!(Uniforms, exposure)),
+        ("fit_mode", offset_of!(Uniforms, fit_mode)),
+        ("sharpen", offset_of!(Uniforms, sharpen)),
+        ("rotation", offset_of!(Uniforms, rotation)),
+        ("colormap", offset_of!(Uniforms, colormap)),
+        ("flip_x", offset_of!(Uniforms, flip_x)),
+        ("flip_y", offset_of!(Uniforms, flip_y)),
+        ("crop_x", offset_of!(Uniforms, crop_x)),
+        ("crop_y", offset_of!(Uniforms, crop_y)),
+        ("crop_width", offset_of!(Uniforms, crop_width)),
+        ("crop_height", offset_of!(Uniforms, crop_height)),
+        ("dim", offset_of!(Uniforms, dim)),
+        ("negative", offset_of!(Uniforms, negative)),
+        ("film_base_r", offset_of!(Uniforms, film_base_r)),
+        ("film_base_g", offset_of!(Uniforms, film_base_g)),
+        ("film_base_b", offset_of!(Uniforms, film_base_b)),
+        ("inset_left", offset_of!(Uniforms, inset_left)),
+        ("inset_top", offset_of!(Uniforms, inset_top)),
+        ("inset_right", offset_of!(Uniforms, inset_right)),
+        ("inset_bottom", offset_of!(Uniforms, inset_bottom)),
+        ("window_height", offset_of!(Uniforms, window_height)),
+        ("frame_width", offset_of!(Uniforms, frame_width)),
+        ("frame_shadow", offset_of!(Uniforms, frame_shadow)),
+        ("frame_r", offset_of!(Uniforms, frame_r)),
+        ("frame_g", offset_of!(Uniforms, frame_g)),
+        ("frame_b", offset_of!(Uniforms, frame_b)),
+        ("frame_a", offset_of!(Uniforms, frame_a)),
+        ("channel", offset_of!(Uniforms, channel)),
+    ];
+
+    /// Fails if the `Uniforms` struct in `shader_source` doesn't match [`Uniforms`] field for field. A mismatch isn't
+    /// a validation error as long as the buffer is large enough, the shader would just read values meant for other fields
+    fn check_uniform_layout(shader_source: &str) {
+        let module = naga::front::wgsl::parse_str(shader_source).expect("the shader doesn't parse");
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .expect("the shader doesn't validate");
+        let Some(naga::TypeInner::Struct { members, span }) = module.types.iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("Uniforms"))
+            .map(|(_, ty)| &ty.inner)
+        else {
+            panic!("the shader has no Uniforms struct");
+        };
+        let shader_layout: Vec<_> = members.iter()
+            .map(|member| (member.name.as_deref().unwrap_or_default(), member.offset as usize))
+            .collect();
+        assert_eq!(shader_layout, UNIFORM_LAYOUT, "the shader's uniforms don't match UNIFORM_LAYOUT");
+        assert_eq!(*span as usize, size_of::<Uniforms>(), "the shader's uniforms don't match the size of Uniforms");
+    }
+
+    #[test]
+    fn shader_uniforms_match_struct() {
+        check_uniform_layout(include_str!("shader.wgsl"));
+    }
+
+    #[test]
+    fn fallback_shader_uniforms_match_struct() {
+        check_uniform_layout(include_str!("fallback_shader.wgsl"));
+    }
+
+    #[test]
+    fn opaque_pixels_are_only_reordered() {
+        assert_eq!(premultiply_to_bgra(&[10, 20, 30, 255, 200, 100, 50, 255], AlphaMode::Straight), [30, 20, 10, 255, 50, 100, 200, 255]);
+    }
+
+    #[test]
+    fn straight_alpha_is_multiplied_in() {
+        let bgra = premultiply_to_bgra(&[10, 20, 30, 255, 200, 100, 50, 0, 200, 100, 50, 128], AlphaMode::Straight);
+        assert_eq!(bgra, [30, 20, 10, 255, 0, 0, 0, 0, 25, 50, 100, 128]);
+    }
+
+    #[test]
+    fn premultiplied_alpha_is_left_alone() {
+        let bgra = premultiply_to_bgra(&[10, 20, 30, 255, 200, 100, 50, 0, 100, 50, 25, 128], AlphaMode::Premultiplied);
+        assert_eq!(bgra, [30, 20, 10, 255, 50, 100, 200, 0, 25, 50, 100, 128]);
+    }
+}